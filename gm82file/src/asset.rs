@@ -0,0 +1,702 @@
+use crate::{library::ActionDefinition, output::PngEffort};
+use std::collections::BTreeMap;
+
+// keys starting with x_ from an asset's file, which other tools use to store their own stuff
+pub type ExtraKeys = Vec<(String, String)>;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Trigger {
+    pub name: String,
+    pub condition: String,
+    pub constant_name: String,
+    pub kind: u32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Sound {
+    pub kind: u32,
+    pub extension: String,
+    pub effects: u32,
+    pub source: String,
+    pub volume: f64,
+    pub pan: f64,
+    pub preload: bool,
+    pub data: Option<Vec<u8>>,
+    pub extra_keys: ExtraKeys,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Frame {
+    pub width: u32,
+    pub height: u32,
+    // BGRA8, same as the IDE keeps it in memory
+    pub data: Vec<u8>,
+}
+
+impl Frame {
+    pub fn get_data(&self) -> &[u8] {
+        &self.data[..(self.width * self.height * 4) as usize]
+    }
+
+    pub fn thumb(&self, out: &mut [u8], flip: bool, bg_col: [u8; 3]) {
+        // note: this assumes output format == input format
+        // these are stored as BGRA8 so make sure to double check what the output should be
+        use itertools::Itertools;
+        let data = self.get_data();
+        let (width, height) = if self.width > self.height {
+            (16, (self.height * 16 / self.width) as usize)
+        } else {
+            ((self.width * 16 / self.height) as usize, 16)
+        };
+        let (hoffset, voffset) = (8 - width / 2, 8 - height / 2);
+        for (y, row) in out.chunks_exact_mut(16 * 4).enumerate() {
+            // vertical flip for BMP
+            let y = if flip { 15 - y } else { y };
+            if y < voffset || y >= voffset + height {
+                row.fill(0);
+                continue
+            }
+            let y = y - voffset;
+            for (x, px) in row.chunks_exact_mut(4).enumerate() {
+                if x < hoffset || x >= hoffset + width {
+                    px.fill(0);
+                    continue
+                }
+                let x = x - hoffset;
+                // get sample points
+                let ox = (x as f64) / (width as f64) * f64::from(self.width);
+                let ox2 = ox + 0.5 / (width as f64) * f64::from(self.width);
+                let oy = (y as f64) / (height as f64) * f64::from(self.height);
+                let oy2 = oy + 0.5 / (height as f64) * f64::from(self.height);
+                // sum all pixels
+                let mut px_count = 0.0;
+                let sum_px = [ox, ox2]
+                    .iter()
+                    .map(|x| x.floor() as usize)
+                    .cartesian_product([oy, oy2].iter().map(|x| x.floor() as usize))
+                    .fold([0.0f64; 4], |mut px, (ox, oy)| {
+                        let offset = (oy * self.width as usize + ox) * 4;
+                        let in_px = &data[offset..offset + 4];
+                        if in_px[3] != 0 {
+                            px_count += 1.0;
+                            px.iter_mut().zip(in_px).for_each(|(o, i)| *o += f64::from(*i));
+                        }
+                        px
+                    });
+                if px_count != 0.0 {
+                    // average and place into output
+                    px[..3].iter_mut().zip(sum_px).for_each(|(o, i)| *o = (i / px_count).floor() as u8);
+                    // blend semi-transparent to white
+                    let alpha = sum_px[3] / 4.0 / 255.0;
+                    if alpha != 1.0 {
+                        px[..3]
+                            .iter_mut()
+                            .zip(&bg_col)
+                            .for_each(|(c, &b)| *c = (f64::from(b) * (1.0 - alpha) + f64::from(*c) * alpha) as u8);
+                    }
+                    px[3] = 255;
+                } else {
+                    // fully transparent
+                    px.fill(0);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Sprite {
+    pub origin_x: i32,
+    pub origin_y: i32,
+    pub collision_shape: u32,
+    pub alpha_tolerance: u32,
+    pub per_frame_colliders: bool,
+    pub bbox_type: u32,
+    pub bbox_left: i32,
+    pub bbox_top: i32,
+    pub bbox_right: i32,
+    pub bbox_bottom: i32,
+    pub frames: Vec<Frame>,
+    pub extra_keys: ExtraKeys,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Background {
+    pub frame: Frame,
+    pub is_tileset: bool,
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub h_offset: u32,
+    pub v_offset: u32,
+    pub h_sep: u32,
+    pub v_sep: u32,
+    pub extra_keys: ExtraKeys,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PathPoint {
+    pub x: f64,
+    pub y: f64,
+    pub speed: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Path {
+    pub points: Vec<PathPoint>,
+    pub connection: u32,
+    pub closed: bool,
+    pub precision: u32,
+    pub path_editor_room_background: i32,
+    pub snap_x: u32,
+    pub snap_y: u32,
+    pub extra_keys: ExtraKeys,
+}
+
+impl Default for Path {
+    fn default() -> Self {
+        Self {
+            points: Vec::new(),
+            connection: 0,
+            closed: false,
+            precision: 0,
+            path_editor_room_background: -1,
+            snap_x: 0,
+            snap_y: 0,
+            extra_keys: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Script {
+    pub source: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Font {
+    pub sys_name: String,
+    pub size: u32,
+    pub bold: bool,
+    pub italic: bool,
+    pub range_start: u32,
+    pub range_end: u32,
+    pub charset: u32,
+    // This is 1 less than what you'll see saved in .gmk or .exe or .gm81 or whatever
+    pub aa_level: u32,
+    pub extra_keys: ExtraKeys,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Action {
+    pub lib_id: u32,
+    pub id: u32,
+    pub action_kind: u32,
+    pub can_be_relative: bool,
+    pub is_condition: bool,
+    pub applies_to_something: bool,
+    pub execution_type: u32,
+    pub fn_name: String,
+    pub fn_code: String,
+    pub param_count: u32,
+    pub param_types: [u32; 8],
+    pub applies_to: i32,
+    pub is_relative: bool,
+    pub param_strings: [String; 8],
+    pub invert_condition: bool,
+}
+
+impl Default for Action {
+    fn default() -> Self {
+        Self {
+            lib_id: 0,
+            id: 0,
+            action_kind: 0,
+            can_be_relative: false,
+            is_condition: false,
+            applies_to_something: false,
+            execution_type: 0,
+            fn_name: String::new(),
+            fn_code: String::new(),
+            param_count: 0,
+            param_types: [0; 8],
+            applies_to: -1,
+            is_relative: false,
+            param_strings: Default::default(),
+            invert_condition: false,
+        }
+    }
+}
+
+impl Action {
+    pub fn fill_in(&mut self, def: &ActionDefinition) {
+        self.action_kind = def.kind;
+        self.can_be_relative = def.relative;
+        self.is_condition = def.question;
+        self.applies_to_something = def.apply_to;
+        self.execution_type = def.execution_type;
+        self.fn_name = def.function_name.clone();
+        self.fn_code = def.code_string.clone();
+        self.param_count = def.arg_count;
+        self.param_types = def.arg_types;
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Event {
+    pub actions: Vec<Action>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Timeline {
+    pub moments: Vec<(u32, Event)>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Object {
+    pub sprite_index: i32,
+    pub solid: bool,
+    pub visible: bool,
+    pub depth: i32,
+    pub persistent: bool,
+    pub parent_index: i32,
+    pub mask_index: i32,
+    // indexed by event type, then by event number (or object/trigger index)
+    pub events: [BTreeMap<usize, Event>; 12],
+    pub extra_keys: ExtraKeys,
+}
+
+impl Default for Object {
+    fn default() -> Self {
+        Self {
+            sprite_index: -1,
+            solid: false,
+            visible: true,
+            depth: 0,
+            persistent: false,
+            parent_index: -1,
+            mask_index: -1,
+            events: Default::default(),
+            extra_keys: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RoomBackground {
+    pub visible_on_start: bool,
+    pub is_foreground: bool,
+    pub source_bg: i32,
+    pub xoffset: i32,
+    pub yoffset: i32,
+    pub tile_horz: bool,
+    pub tile_vert: bool,
+    pub hspeed: i32,
+    pub vspeed: i32,
+    pub stretch: bool,
+}
+
+impl Default for RoomBackground {
+    fn default() -> Self {
+        Self {
+            visible_on_start: false,
+            is_foreground: false,
+            source_bg: -1,
+            xoffset: 0,
+            yoffset: 0,
+            tile_horz: false,
+            tile_vert: false,
+            hspeed: 0,
+            vspeed: 0,
+            stretch: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct View {
+    pub visible: bool,
+    pub source_x: i32,
+    pub source_y: i32,
+    pub source_w: u32,
+    pub source_h: u32,
+    pub port_x: i32,
+    pub port_y: i32,
+    pub port_w: u32,
+    pub port_h: u32,
+    pub following_hborder: i32,
+    pub following_vborder: i32,
+    pub following_hspeed: i32,
+    pub following_vspeed: i32,
+    pub following_target: i32,
+}
+
+impl Default for View {
+    fn default() -> Self {
+        Self {
+            visible: false,
+            source_x: 0,
+            source_y: 0,
+            source_w: 0,
+            source_h: 0,
+            port_x: 0,
+            port_y: 0,
+            port_w: 0,
+            port_h: 0,
+            following_hborder: 0,
+            following_vborder: 0,
+            following_hspeed: 0,
+            following_vspeed: 0,
+            following_target: -1,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct InstanceExtra {
+    pub name: u32,
+    pub xscale: f64,
+    pub yscale: f64,
+    pub blend: u32,
+    pub angle: f64,
+}
+
+impl InstanceExtra {
+    pub const DEFAULT: Self = Self { name: 0, xscale: 1.0, yscale: 1.0, blend: u32::MAX, angle: 0.0 };
+}
+
+impl Default for InstanceExtra {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TileExtra {
+    pub name: u32,
+    pub xscale: f64,
+    pub yscale: f64,
+    pub blend: u32,
+}
+
+impl TileExtra {
+    pub const DEFAULT: Self = Self { name: 0, xscale: 1.0, yscale: 1.0, blend: u32::MAX };
+}
+
+impl Default for TileExtra {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Instance {
+    pub x: i32,
+    pub y: i32,
+    pub object: i32,
+    pub id: usize,
+    pub creation_code: String,
+    pub locked: bool,
+    pub extra: InstanceExtra,
+}
+
+impl Default for Instance {
+    fn default() -> Self {
+        Self {
+            x: 0,
+            y: 0,
+            object: -1,
+            id: 0,
+            creation_code: String::new(),
+            locked: false,
+            extra: InstanceExtra::DEFAULT,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tile {
+    pub x: i32,
+    pub y: i32,
+    pub source_bg: i32,
+    pub u: i32,
+    pub v: i32,
+    pub width: i32,
+    pub height: i32,
+    pub depth: i32,
+    pub id: usize,
+    pub locked: bool,
+    pub extra: TileExtra,
+}
+
+impl Default for Tile {
+    fn default() -> Self {
+        Self {
+            x: 0,
+            y: 0,
+            source_bg: -1,
+            u: 0,
+            v: 0,
+            width: 0,
+            height: 0,
+            depth: 0,
+            id: 0,
+            locked: false,
+            extra: TileExtra::DEFAULT,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Room {
+    pub caption: String,
+    pub speed: u32,
+    pub width: u32,
+    pub height: u32,
+    pub snap_x: u32,
+    pub snap_y: u32,
+    pub isometric: bool,
+    pub persistent: bool,
+    pub bg_colour: i32,
+    pub clear_screen: bool,
+    pub backgrounds: [RoomBackground; 8],
+    pub views_enabled: bool,
+    pub clear_view: bool,
+    pub views: [View; 8],
+    pub creation_code: String,
+    pub instances: Vec<Instance>,
+    pub tiles: Vec<Tile>,
+    pub remember_room_editor_info: bool,
+    pub editor_width: u32,
+    pub editor_height: u32,
+    pub show_grid: bool,
+    pub show_objects: bool,
+    pub show_tiles: bool,
+    pub show_backgrounds: bool,
+    pub show_foregrounds: bool,
+    pub show_views: bool,
+    pub delete_underlying_objects: bool,
+    pub delete_underlying_tiles: bool,
+    pub tab: u32,
+    pub x_position_scroll: u32,
+    pub y_position_scroll: u32,
+    pub extra_keys: ExtraKeys,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct IncludedFile {
+    pub file_name: String,
+    pub source_path: String,
+    pub data_exists: bool,
+    pub source_length: u32,
+    pub stored_in_gmk: bool,
+    pub data: Option<Vec<u8>>,
+    pub export_setting: u32,
+    pub export_custom_folder: String,
+    pub overwrite_file: bool,
+    pub free_memory: bool,
+    pub remove_at_end: bool,
+}
+
+// same layout as ide::settings, minus the statics
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Settings {
+    pub fullscreen: bool,
+    pub interpolate_pixels: bool,
+    pub dont_draw_border: bool,
+    pub display_cursor: bool,
+    pub scaling: i32,
+    pub allow_resize: bool,
+    pub window_on_top: bool,
+    pub clear_colour: u32,
+    pub set_resolution: bool,
+    pub colour_depth: u32,
+    pub resolution: u32,
+    pub frequency: u32,
+    pub dont_show_buttons: bool,
+    pub vsync_and_force_cpu: u32,
+    pub disable_screensaver: bool,
+    pub f4_fullscreen: bool,
+    pub f1_help: bool,
+    pub esc_close: bool,
+    pub f5_save_f6_load: bool,
+    pub f9_screenshot: bool,
+    pub treat_close_as_esc: bool,
+    pub priority: u32,
+    pub freeze_on_lose_focus: bool,
+    pub loading_bar: u32,
+    // .bmp file contents
+    pub loading_background: Option<Vec<u8>>,
+    pub loading_foreground: Option<Vec<u8>>,
+    pub has_custom_load_image: bool,
+    pub custom_load_image: Option<Vec<u8>>,
+    pub loading_transparent: bool,
+    pub loading_translucency: u32,
+    pub loading_progress_bar_scale: bool,
+    // .ico file contents
+    pub icon: Vec<u8>,
+    pub show_error_messages: bool,
+    pub log_errors: bool,
+    pub always_abort: bool,
+    pub zero_uninitialized_vars: bool,
+    pub error_on_uninitialized_args: bool,
+    pub info_author: String,
+    pub info_version: String,
+    pub info_timestamp: f64,
+    pub info_information: String,
+    pub version_major: u32,
+    pub version_minor: u32,
+    pub version_release: u32,
+    pub version_build: u32,
+    pub exe_company: String,
+    pub exe_product: String,
+    pub exe_copyright: String,
+    pub exe_description: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GameInfo {
+    pub colour: u32,
+    pub new_window: bool,
+    pub caption: String,
+    pub left: i32,
+    pub top: i32,
+    pub width: i32,
+    pub height: i32,
+    pub border: bool,
+    pub resizable: bool,
+    pub window_on_top: bool,
+    pub freeze_game: bool,
+    // raw contents of game_information.rtf
+    pub rtf: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TreeNode {
+    Group(String, Vec<TreeNode>),
+    Asset(usize),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AssetList<T> {
+    pub assets: Vec<Option<T>>,
+    pub names: Vec<String>,
+    pub timestamps: Vec<f64>,
+    pub tree: Vec<TreeNode>,
+}
+
+impl<T> Default for AssetList<T> {
+    fn default() -> Self {
+        Self { assets: Vec::new(), names: Vec::new(), timestamps: Vec::new(), tree: Vec::new() }
+    }
+}
+
+// what any list of assets has, whether it's one of these or one of the IDE's own, so code that only reads one
+// works the same on both. the IDE adds its own way of changing them on top
+pub trait AssetListTrait<T> {
+    type Name;
+    fn assets(&self) -> &[Option<T>];
+    fn names(&self) -> &[Self::Name];
+    fn timestamps(&self) -> &[f64];
+}
+
+impl<T> AssetListTrait<T> for AssetList<T> {
+    type Name = String;
+
+    fn assets(&self) -> &[Option<T>] {
+        &self.assets
+    }
+
+    fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    fn timestamps(&self) -> &[f64] {
+        &self.timestamps
+    }
+}
+
+impl<T> AssetList<T> {
+    pub fn alloc(&mut self, count: usize) {
+        self.assets.resize_with(count, || None);
+        self.names.resize_with(count, String::new);
+        self.timestamps.resize(count, 0.0);
+    }
+
+    pub fn get(&self, id: i32) -> Option<&T> {
+        self.assets.get(usize::try_from(id).ok()?)?.as_ref()
+    }
+
+    pub fn name(&self, id: i32) -> &str {
+        usize::try_from(id).ok().and_then(|id| self.names.get(id)).map(String::as_str).unwrap_or_default()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &str, &T)> {
+        self.assets.iter().zip(&self.names).enumerate().filter_map(|(i, (a, n))| Some((i, n.as_str(), a.as_ref()?)))
+    }
+
+    pub fn any(&self) -> bool {
+        self.assets.iter().any(Option::is_some)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Project {
+    pub game_id: u32,
+    pub settings: Settings,
+    pub game_info: GameInfo,
+    pub constants: Vec<(String, String)>,
+    pub extensions: Vec<String>,
+    pub triggers: Vec<Option<Trigger>>,
+    pub sounds: AssetList<Sound>,
+    pub sprites: AssetList<Sprite>,
+    pub backgrounds: AssetList<Background>,
+    pub paths: AssetList<Path>,
+    pub scripts: AssetList<Script>,
+    pub fonts: AssetList<Font>,
+    pub timelines: AssetList<Timeline>,
+    pub objects: AssetList<Object>,
+    pub rooms: AssetList<Room>,
+    pub included_files: Vec<IncludedFile>,
+    pub last_instance_id: usize,
+    pub last_tile_id: usize,
+    // write instance and tile ids to the project instead of handing out new ones on load
+    pub preserve_ids: bool,
+    pub png_effort: PngEffort,
+}
+
+impl Default for Project {
+    fn default() -> Self {
+        Self {
+            game_id: 0,
+            settings: Default::default(),
+            game_info: Default::default(),
+            constants: Vec::new(),
+            extensions: Vec::new(),
+            triggers: Vec::new(),
+            sounds: Default::default(),
+            sprites: Default::default(),
+            backgrounds: Default::default(),
+            paths: Default::default(),
+            scripts: Default::default(),
+            fonts: Default::default(),
+            timelines: Default::default(),
+            objects: Default::default(),
+            rooms: Default::default(),
+            included_files: Vec::new(),
+            // what a fresh project in the IDE starts counting from
+            last_instance_id: 100000,
+            last_tile_id: 10000000,
+            preserve_ids: false,
+            png_effort: PngEffort::Default,
+        }
+    }
+}
+
+impl Project {
+    // every action in every object event and timeline moment
+    pub fn actions(&self) -> impl Iterator<Item = &Action> {
+        let object_events = self.objects.iter().flat_map(|(_, _, o)| o.events.iter().flat_map(|m| m.values()));
+        let moments = self.timelines.iter().flat_map(|(_, _, t)| t.moments.iter().map(|(_, e)| e));
+        object_events.chain(moments).flat_map(|e| &e.actions)
+    }
+}
@@ -0,0 +1,199 @@
+use gm82file::{
+    casing,
+    library::ActionLibraries,
+    load::{load_gmk, load_gmk_with_names_on_disk},
+    load_exe::{load_exe, load_gm81},
+    recompress,
+    save::save_gmk,
+    save_exe::{save_exe, save_gm81},
+    transaction, Error, Result,
+};
+use std::{path::PathBuf, process::ExitCode};
+
+const USAGE: &str = "\
+usage: gm82 <command> [options]
+
+commands:
+    check <project> [--lib <dir>]           load a project and report every error, if any
+    gm81 <project> <out> [--lib <dir>]      convert a project to an editable .gm81 file
+    import <file> <out>                     convert a .gmk or .gm81 file to a project at <out>.gm82
+    exe <project> <runner> <out> [--lib <dir>]
+                                            build a game, using <runner> as the runner template. projects with
+                                            fonts or extensions have to be built from the IDE, which renders
+                                            fonts with windows and packs extensions from their installed files
+    unpack <exe> <out>                      turn a game built by `gm82 exe` back into a project at <out>.gm82
+    fix-case <project> [--index] [--lib <dir>]
+                                            rename files whose case doesn't match index.yyd, or with --index
+                                            rename the assets to match their files instead
+    recompress <project>                    re-encode every sprite frame and background png as small as possible
+    recover <project> [--undo]              finish a save that was interrupted while moving files into place, or
+                                            with --undo put back the files from before it
+
+<project> is either the .gm82 file or the folder it's in.
+--lib points at the IDE's lib folder so actions can be checked against their definitions. gm81 and exe need it
+for any project with actions in it.";
+
+struct Args {
+    positional: Vec<String>,
+    lib: Option<PathBuf>,
+    undo: bool,
+    index: bool,
+    help: bool,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> std::result::Result<Args, String> {
+    let mut out = Args { positional: Vec::new(), lib: None, undo: false, index: false, help: false };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--lib" => out.lib = Some(args.next().ok_or("--lib needs a folder")?.into()),
+            "--undo" => out.undo = true,
+            "--index" => out.index = true,
+            "--help" | "-h" => out.help = true,
+            s if s.starts_with("--") => return Err(format!("unknown option {}", s)),
+            _ => out.positional.push(arg),
+        }
+    }
+    Ok(out)
+}
+
+// accept the project folder as well as the .gm82 itself
+fn find_project(path: PathBuf) -> Result<PathBuf> {
+    if !path.is_dir() {
+        return Ok(path)
+    }
+    let mut found = std::fs::read_dir(&path)
+        .map_err(|e| Error::DirIoError(e, path.clone()))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.is_file() && p.extension().is_some_and(|e| e.eq_ignore_ascii_case("gm82")))
+        .collect::<Vec<_>>();
+    match found.len() {
+        1 => Ok(found.remove(0)),
+        0 => Err(Error::Other(format!("no .gm82 file in {}", path.to_string_lossy()))),
+        _ => Err(Error::Other(format!("more than one .gm82 file in {}", path.to_string_lossy()))),
+    }
+}
+
+fn load_libs(lib: Option<&PathBuf>) -> Result<ActionLibraries> {
+    lib.map_or_else(|| Ok(ActionLibraries::default()), |p| ActionLibraries::load_dir(p))
+}
+
+fn check(project: &str, args: &Args) -> Result<()> {
+    let libs = load_libs(args.lib.as_ref())?;
+    let project = load_gmk(find_project(project.into())?, &libs, true)?;
+    // without them, actions are only checked for syntax
+    if libs.is_empty() {
+        let actions = project.actions().count();
+        if actions != 0 {
+            eprintln!(
+                "warning: {} actions weren't checked, pass the IDE's lib folder with --lib to check them too",
+                actions
+            );
+        }
+    }
+    Ok(())
+}
+
+fn gm81(project: &str, out: &str, args: &Args) -> Result<()> {
+    let libs = load_libs(args.lib.as_ref())?;
+    let project = load_gmk(find_project(project.into())?, &libs, false)?;
+    // built in memory so nothing gets left behind if the project can't be written
+    let mut data = Vec::new();
+    save_gm81(&project, &libs, &mut data)?;
+    let out = PathBuf::from(out);
+    std::fs::write(&out, data).map_err(|e| Error::FileIoError(e, out))
+}
+
+fn import(file: &str, out: &str, load: fn(&[u8]) -> Result<gm82file::asset::Project>) -> Result<()> {
+    let file = PathBuf::from(file);
+    let data = std::fs::read(&file).map_err(|e| Error::FileIoError(e, file.clone()))?;
+    let mut project = load(&data)?;
+    save_gmk(&mut project, &mut PathBuf::from(out))
+}
+
+fn exe(project: &str, runner: &str, out: &str, args: &Args) -> Result<()> {
+    let libs = load_libs(args.lib.as_ref())?;
+    let project = load_gmk(find_project(project.into())?, &libs, false)?;
+    let runner = PathBuf::from(runner);
+    let runner = std::fs::read(&runner).map_err(|e| Error::FileIoError(e, runner))?;
+    let mut data = Vec::new();
+    save_exe(&project, &libs, &runner, &mut data)?;
+    let out = PathBuf::from(out);
+    std::fs::write(&out, data).map_err(|e| Error::FileIoError(e, out))
+}
+
+fn recover(project: &str, args: &Args) -> Result<()> {
+    let project = find_project(project.into())?;
+    if !transaction::interrupted(&project) {
+        return Err(Error::Other(format!("no interrupted save found for {}", project.to_string_lossy())))
+    }
+    transaction::recover(&project, !args.undo)
+}
+
+fn fix_case(project: &str, args: &Args) -> Result<()> {
+    let mut project = find_project(project.into())?;
+    let mismatches = casing::mismatches(&project)?;
+    for m in &mismatches {
+        if args.index {
+            println!("{}: {} -> {}", m.dir, m.name, m.found_name);
+        } else {
+            println!("{}: {} -> {}", m.dir, m.found, m.expected);
+        }
+    }
+    if !args.index {
+        return casing::rename_files(&project, &mismatches)
+    }
+    // saving puts the new names everywhere that refers to them, all at once
+    let libs = load_libs(args.lib.as_ref())?;
+    let mut loaded = load_gmk_with_names_on_disk(project.clone(), &libs)?;
+    save_gmk(&mut loaded, &mut project)
+}
+
+fn recompress(project: &str) -> Result<()> {
+    let project = find_project(project.into())?;
+    let files = recompress::recompress(&project)?;
+    for f in &files {
+        println!("{}: {} -> {} bytes", f.file, f.old_size, f.new_size);
+    }
+    let saved = files.iter().map(|f| f.old_size - f.new_size).sum::<u64>();
+    println!("{} files, {} bytes smaller", files.len(), saved);
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let command = args.next();
+    let args = match parse_args(args) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return ExitCode::from(2)
+        },
+    };
+    if args.help || matches!(command.as_deref(), Some("help" | "--help" | "-h")) {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS
+    }
+    let result = match (command.as_deref(), &args.positional[..]) {
+        (Some("check"), [project]) => check(project, &args).map_err(|e| format!("Failed to load: {}", e)),
+        (Some("gm81"), [project, out]) => gm81(project, out, &args).map_err(|e| format!("Failed to convert: {}", e)),
+        (Some("import"), [file, out]) => import(file, out, load_gm81).map_err(|e| format!("Failed to import: {}", e)),
+        (Some("exe"), [project, runner, out]) => {
+            exe(project, runner, out, &args).map_err(|e| format!("Failed to build: {}", e))
+        },
+        (Some("unpack"), [file, out]) => import(file, out, load_exe).map_err(|e| format!("Failed to unpack: {}", e)),
+        (Some("fix-case"), [project]) => fix_case(project, &args).map_err(|e| format!("Failed to fix case: {}", e)),
+        (Some("recompress"), [project]) => recompress(project).map_err(|e| format!("Failed to recompress: {}", e)),
+        (Some("recover"), [project]) => recover(project, &args).map_err(|e| format!("Failed to recover: {}", e)),
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2)
+        },
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        },
+    }
+}
@@ -0,0 +1,173 @@
+use crate::{
+    escape::{file_name, from_file_name},
+    prune::list_dir,
+    transaction::project_dir,
+    Error, Result,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+use unicase::UniCase;
+
+// on a case-sensitive file system, like a linux checkout or after a git rename that only changed case, an
+// asset's files can be called something different to its name in index.yyd. windows doesn't care, but
+// everywhere else they can't be opened, so find them to say exactly what's wrong and put it right
+
+enum Kind {
+    // a folder called <name>
+    Folder,
+    // <name>.<ext>, or just <name> for a sound with no extension
+    Files,
+    // a file called exactly <name>
+    Whole,
+}
+
+const DIRS: [(&str, &str, Option<&str>, Kind); 12] = [
+    ("sounds", "sounds/index.yyd", Some("has_sounds"), Kind::Files),
+    ("sprites", "sprites/index.yyd", Some("has_sprites"), Kind::Folder),
+    ("backgrounds", "backgrounds/index.yyd", Some("has_backgrounds"), Kind::Files),
+    ("paths", "paths/index.yyd", Some("has_paths"), Kind::Folder),
+    ("scripts", "scripts/index.yyd", Some("has_scripts"), Kind::Files),
+    ("fonts", "fonts/index.yyd", Some("has_fonts"), Kind::Files),
+    ("timelines", "timelines/index.yyd", Some("has_timelines"), Kind::Files),
+    ("objects", "objects/index.yyd", Some("has_objects"), Kind::Files),
+    ("rooms", "rooms/index.yyd", None, Kind::Folder),
+    ("triggers", "triggers/index.yyd", Some("has_triggers"), Kind::Files),
+    ("datafiles", "datafiles/index.yyd", Some("has_datafiles"), Kind::Files),
+    ("datafiles/include", "datafiles/index.yyd", Some("has_datafiles"), Kind::Whole),
+];
+
+pub struct Mismatch {
+    // the folder it's in, relative to the project
+    pub dir: &'static str,
+    // the name in index.yyd, and the name the file or folder has on disk
+    pub name: String,
+    pub found_name: String,
+    // the file or folder as it is, and as it should be to match the index
+    pub found: String,
+    pub expected: String,
+}
+
+impl Mismatch {
+    // whether it stops the project loading here, which it doesn't on a case-insensitive file system
+    pub fn matters(&self, project: &Path) -> bool {
+        !project_dir(project).join(self.dir).join(&self.expected).exists()
+    }
+
+    pub fn error(&self, project: &Path) -> Error {
+        let dir = project_dir(project).join(self.dir);
+        Error::CaseMismatch(dir.join(&self.found), self.expected.clone())
+    }
+}
+
+// the parts of a file or folder's name that could be an asset's name
+fn stems<'a>(entry: &'a str, kind: &Kind) -> impl Iterator<Item = &'a str> {
+    let stem = match kind {
+        Kind::Files => entry.rsplit_once('.').map(|(stem, _)| stem),
+        Kind::Folder | Kind::Whole => None,
+    };
+    [Some(entry), stem].into_iter().flatten()
+}
+
+// the has_ keys that are turned off, since those types don't get loaded
+fn disabled_types(project: &Path) -> Result<HashSet<String>> {
+    let s = std::fs::read_to_string(project).map_err(|e| Error::FileIoError(e, project.to_path_buf()))?;
+    Ok(s.lines().filter_map(|l| l.strip_suffix("=0")).map(String::from).collect())
+}
+
+// project is the path to the .gm82 file
+pub fn mismatches(project: &Path) -> Result<Vec<Mismatch>> {
+    let dir = project_dir(project);
+    let disabled = disabled_types(project)?;
+    let mut out = Vec::new();
+    for (type_dir, index, has_key, kind) in &DIRS {
+        if has_key.is_some_and(|k| disabled.contains(k)) {
+            continue
+        }
+        let index_path = dir.join(index);
+        let index = match std::fs::read_to_string(&index_path) {
+            Ok(s) => s,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(Error::FileIoError(e, index_path)),
+        };
+        let names = index
+            .lines()
+            .filter(|n| !n.is_empty())
+            .map(|n| (UniCase::new(file_name(n).into_owned()), n))
+            .collect::<HashMap<_, _>>();
+        let mut entries = list_dir(&dir.join(type_dir))?;
+        entries.sort();
+        let present = entries.iter().map(|(e, _)| e.as_str()).collect::<HashSet<_>>();
+        for (entry, is_dir) in &entries {
+            if *is_dir != matches!(kind, Kind::Folder) || matches!(entry.as_str(), "index.yyd" | "tree.yyd") {
+                continue
+            }
+            for stem in stems(entry, kind) {
+                let Some(name) = names.get(&UniCase::new(stem.to_string())) else { continue };
+                let expected = file_name(name) + &entry[stem.len()..];
+                // if the right one's there too, this is just something else lying around
+                if !present.contains(&*expected) {
+                    out.push(Mismatch {
+                        dir: type_dir,
+                        name: name.to_string(),
+                        found_name: from_file_name(stem).into_owned(),
+                        found: entry.clone(),
+                        expected: expected.into_owned(),
+                    });
+                }
+                break
+            }
+        }
+    }
+    Ok(out)
+}
+
+fn rename(from: &Path, to: &Path) -> Result<()> {
+    std::fs::rename(from, to).map_err(|e| Error::FileIoError(e, from.to_path_buf()))
+}
+
+// going through another name means this works on case-insensitive file systems too
+fn rename_case(from: &Path, to: &Path) -> Result<()> {
+    let mut temp = to.to_path_buf().into_os_string();
+    temp.push(".gm82case");
+    let temp = PathBuf::from(temp);
+    rename(from, &temp)?;
+    if to.exists() {
+        rename(&temp, from)?;
+        return Err(Error::Other(format!("can't rename to {}, it already exists", to.to_string_lossy())))
+    }
+    rename(&temp, to)
+}
+
+// renames what's on disk to match the index
+pub fn rename_files(project: &Path, mismatches: &[Mismatch]) -> Result<()> {
+    let dir = project_dir(project);
+    for m in mismatches {
+        rename_case(&dir.join(m.dir).join(&m.found), &dir.join(m.dir).join(&m.expected))?;
+    }
+    Ok(())
+}
+
+// the other way to fix it, giving the assets the names their files have. that's only clear if all of an asset's
+// files agree, so it's an error if they don't. keyed by the folder the index is in and the name in it
+pub fn names_on_disk(project: &Path, mismatches: &[Mismatch]) -> Result<HashMap<(&'static str, String), String>> {
+    let dir = project_dir(project);
+    let mut out = HashMap::new();
+    for m in mismatches {
+        let Some(&(_, index, ..)) = DIRS.iter().find(|d| d.0 == m.dir) else { continue };
+        let disagree = || Error::Other(format!("the files for {} in {} don't all have the same case", m.name, m.dir));
+        let key = (index.trim_end_matches("/index.yyd"), m.name.clone());
+        if out.insert(key, m.found_name.clone()).is_some_and(|n| n != m.found_name) {
+            return Err(disagree())
+        }
+        // any of its files still named as in the index
+        let expected = file_name(&m.name);
+        for (type_dir, _, _, kind) in DIRS.iter().filter(|d| d.1 == index) {
+            if list_dir(&dir.join(type_dir))?.iter().any(|(e, _)| stems(e, kind).any(|s| s == expected)) {
+                return Err(disagree())
+            }
+        }
+    }
+    Ok(out)
+}
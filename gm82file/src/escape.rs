@@ -0,0 +1,236 @@
+use std::borrow::Cow;
+
+// escaping for values that have to fit on one line and inside a /* */ comment, like action arguments
+// a backslash followed by one of these stands for the character next to it, and '/' is only escaped after a '*'
+// this is also what older versions wrote, so their projects load the same
+const ESCAPES: [(char, char); 4] = [('\\', '\\'), ('\r', 'r'), ('\n', 'n'), ('/', '/')];
+
+pub fn delimit(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut last = None;
+    for c in s.chars() {
+        match ESCAPES.iter().find(|(raw, _)| *raw == c) {
+            Some(&('/', _)) if last != Some('*') => out.push(c),
+            Some(&(_, code)) => {
+                out.push('\\');
+                out.push(code);
+            },
+            None => out.push(c),
+        }
+        last = Some(c);
+    }
+    out
+}
+
+pub fn undelimit(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue
+        }
+        // anything that isn't an escape, like a backslash someone typed into the file by hand, stays as it is
+        match chars.clone().next().and_then(|next| ESCAPES.iter().find(|(_, code)| *code == next)) {
+            Some(&('/', _)) if !out.ends_with('*') => out.push(c),
+            Some(&(raw, _)) => {
+                out.push(raw);
+                chars.next();
+            },
+            None => out.push(c),
+        }
+    }
+    out
+}
+
+// strings in the IDE are utf-16 and can have unpaired surrogates, which a String can't, so those are written as
+// \u{XXXX}. a backslash is only written like that itself if it would otherwise be read as the start of one,
+// so any other text is written as it is
+const TEXT_ESCAPE: char = '\\';
+// the same thing for file names, which can't have backslashes in them
+const FILE_NAME_ESCAPE: char = '%';
+
+// the unit an escape stands for, if s starts with the rest of one
+fn escaped_unit(s: &str, escape: char) -> Option<u16> {
+    let hex = s.strip_prefix("u{")?.get(..5)?.strip_suffix('}')?;
+    let unit = u16::from_str_radix(hex, 16).ok().filter(|_| hex.bytes().all(|c| c.is_ascii_hexdigit()))?;
+    ((0xd800..0xe000).contains(&unit) || u32::from(unit) == u32::from(escape)).then_some(unit)
+}
+
+fn encode(units: &[u16], escape: char) -> String {
+    let chars = char::decode_utf16(units.iter().copied()).map(|c| c.map_err(|e| e.unpaired_surrogate()));
+    let chars = chars.collect::<Vec<_>>();
+    let mut out = String::with_capacity(units.len());
+    for (i, c) in chars.iter().enumerate() {
+        let unit = match *c {
+            Ok(c) if c == escape => {
+                let next = chars[i + 1..].iter().take(7).map_while(|c| c.ok()).collect::<String>();
+                escaped_unit(&next, escape).map(|_| c as u16)
+            },
+            Ok(_) => None,
+            Err(unit) => Some(unit),
+        };
+        match (unit, c) {
+            (Some(unit), _) => out += &format!("{escape}u{{{unit:04X}}}"),
+            (None, Ok(c)) => out.push(*c),
+            (None, Err(_)) => unreachable!(),
+        }
+    }
+    out
+}
+
+fn decode(s: &str, escape: char) -> Vec<u16> {
+    let mut out = Vec::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match escaped_unit(chars.as_str(), escape).filter(|_| c == escape) {
+            Some(unit) => {
+                out.push(unit);
+                chars.nth(6);
+            },
+            None => out.extend(c.encode_utf16(&mut [0; 2]).iter()),
+        }
+    }
+    out
+}
+
+pub fn from_wide(units: &[u16]) -> String {
+    encode(units, TEXT_ESCAPE)
+}
+
+pub fn to_wide(s: &str) -> Vec<u16> {
+    decode(s, TEXT_ESCAPE)
+}
+
+// versions before 8 wrote text as it is, so anything in it that reads as an escape now gets its backslash escaped
+pub fn upgrade(s: &mut String) {
+    if s.contains("\\u{") {
+        *s = from_wide(&s.encode_utf16().collect::<Vec<_>>());
+    }
+}
+
+// the file or folder an asset called this is saved in
+pub fn file_name(name: &str) -> Cow<'_, str> {
+    if name.contains([TEXT_ESCAPE, FILE_NAME_ESCAPE]) {
+        Cow::Owned(encode(&decode(name, TEXT_ESCAPE), FILE_NAME_ESCAPE))
+    } else {
+        Cow::Borrowed(name)
+    }
+}
+
+// and the other way round
+pub fn from_file_name(file: &str) -> Cow<'_, str> {
+    if file.contains([TEXT_ESCAPE, FILE_NAME_ESCAPE]) {
+        Cow::Owned(encode(&decode(file, FILE_NAME_ESCAPE), TEXT_ESCAPE))
+    } else {
+        Cow::Borrowed(file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // every string up to this long made of these, which covers every escape and how they can run into each other
+    fn strings(alphabet: &[char], max_len: u32) -> impl Iterator<Item = String> + '_ {
+        (0..=max_len).flat_map(move |len| {
+            (0..alphabet.len().pow(len)).map(move |mut n| {
+                (0..len)
+                    .map(|_| {
+                        let c = alphabet[n % alphabet.len()];
+                        n /= alphabet.len();
+                        c
+                    })
+                    .collect()
+            })
+        })
+    }
+
+    // what versions before 8 wrote
+    fn old_delimit(s: &str) -> String {
+        s.replace('\\', "\\\\").replace('\r', "\\r").replace('\n', "\\n").replace("*/", "*\\/")
+    }
+
+    #[test]
+    fn delimit_round_trips() {
+        for s in strings(&['\\', '\r', '\n', '/', '*', 'r', 'n', 'a'], 5) {
+            let delimited = delimit(&s);
+            assert!(!delimited.contains(['\r', '\n']) && !delimited.contains("*/"), "{s:?}");
+            assert_eq!(undelimit(&delimited), s);
+        }
+    }
+
+    #[test]
+    fn delimit_matches_older_versions() {
+        for s in strings(&['\\', '\r', '\n', '/', '*', 'r', 'n', 'a'], 5) {
+            assert_eq!(delimit(&s), old_delimit(&s), "{s:?}");
+        }
+    }
+
+    // runs of these make every escape, things that look like one and the units they could be confused with
+    fn wide_strings() -> impl Iterator<Item = Vec<u16>> {
+        let tokens: [&[u16]; 8] = [
+            &[0x5c],
+            &[0x25],
+            &[0xd800],
+            &[0xdc00],
+            &[0x61],
+            &[0x75, 0x7b, 0x44, 0x38, 0x30, 0x30, 0x7d],
+            &[0x75, 0x7b, 0x30, 0x30, 0x35, 0x43, 0x7d],
+            &[0x75, 0x7b, 0x30, 0x30, 0x32, 0x35, 0x7d],
+        ];
+        (0..=4u32).flat_map(move |len| {
+            (0..tokens.len().pow(len)).map(move |mut n| {
+                (0..len)
+                    .flat_map(|_| {
+                        let t = tokens[n % tokens.len()];
+                        n /= tokens.len();
+                        t.iter().copied()
+                    })
+                    .collect()
+            })
+        })
+    }
+
+    #[test]
+    fn wide_strings_round_trip() {
+        for units in wide_strings() {
+            let text = from_wide(&units);
+            assert_eq!(to_wide(&text), units, "{text:?}");
+            let file = file_name(&text);
+            assert_eq!(from_file_name(&file), text, "{file:?}");
+        }
+    }
+
+    #[test]
+    fn plain_text_is_written_as_it_is() {
+        for text in ["", "abc", "a\\b", "100%", "\\u{41}", "%u{0041}", "\u{1F600}"] {
+            assert_eq!(from_wide(&text.encode_utf16().collect::<Vec<_>>()), text);
+            assert_eq!(file_name(text), text);
+        }
+    }
+
+    #[test]
+    fn upgraded_text_reads_as_it_was() {
+        for units in wide_strings() {
+            let Ok(old) = String::from_utf16(&units) else { continue };
+            let mut text = old.clone();
+            upgrade(&mut text);
+            assert_eq!(to_wide(&text), units, "{old:?}");
+        }
+    }
+
+    #[test]
+    fn undelimit_leaves_other_backslashes() {
+        for (delimited, s) in [
+            ("a\\/b", "a\\/b"),
+            ("a\\x", "a\\x"),
+            ("a\\", "a\\"),
+            ("a/*\\/b", "a/*/b"),
+            ("\\\\n", "\\n"),
+            ("\\\\\\n", "\\\n"),
+        ] {
+            assert_eq!(undelimit(delimited), s, "{delimited:?}");
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use crate::escape::{file_name, from_wide, to_wide};
+use std::fmt;
+
+// which names can be saved as files, so that a project can be checked out on windows, mac or linux alike.
+// names are checked as the IDE has them, so a backslash is a backslash and not the start of an escape
+
+// windows can't use paths longer than this without extra setup, which git and most tools don't do
+const MAX_PATH: usize = 260;
+// room for the folder the project itself is in, like C:\Users\someone\Documents\GameMaker\mygame\
+const PROJECT_DIR: usize = 100;
+// the deepest a name goes inside the project is the staging folder during a save, then cache/backgrounds/
+// or datafiles/include/, and the longest file save puts after a name is a tile layer like /-2147483648.txt
+const DEEPEST_PREFIX: usize = ".gm82save/staging/".len() + "datafiles/include/".len();
+const LONGEST_SUFFIX: usize = "/-2147483648.txt".len();
+pub const MAX_NAME_LEN: usize = MAX_PATH - PROJECT_DIR - DEEPEST_PREFIX - LONGEST_SUFFIX;
+
+const BAD_CHARS: &[char] = &['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+// windows won't make files with these names, with or without an extension
+const RESERVED: [&str; 28] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9", "COM¹", "COM²",
+    "COM³", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9", "LPT¹", "LPT²", "LPT³",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Problem {
+    Blank,
+    Dot,
+    TrailingSpace,
+    Char(char),
+    Reserved,
+    TooLong(usize),
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Blank => write!(f, "is blank"),
+            Self::Dot => write!(f, "can't be . or .. or end with a dot"),
+            Self::TrailingSpace => write!(f, "can't end with a space"),
+            Self::Char(c) if c.is_control() => write!(f, "can't contain control character {}", c.escape_unicode()),
+            Self::Char(c) => write!(f, "can't contain character {c}"),
+            Self::Reserved => write!(f, "is reserved by windows"),
+            Self::TooLong(len) => write!(f, "is {len} characters long as a file name, the most is {MAX_NAME_LEN}"),
+        }
+    }
+}
+
+fn bad_char(c: char) -> bool {
+    c.is_ascii_control() || BAD_CHARS.contains(&c)
+}
+
+fn reserved(name: &str) -> bool {
+    let stem = name.split('.').next().unwrap_or_default().trim_end_matches(' ');
+    RESERVED.iter().any(|r| stem.eq_ignore_ascii_case(r))
+}
+
+// how long a name will be on disk, in the utf-16 units windows counts
+fn file_name_len(name: &str) -> usize {
+    file_name(name).encode_utf16().count()
+}
+
+pub fn check(name: &str) -> Result<(), Problem> {
+    // lone surrogates are fine here, they're escaped in the file name
+    let plain = String::from_utf16_lossy(&to_wide(name));
+    if plain.trim().is_empty() {
+        Err(Problem::Blank)
+    } else if plain.ends_with('.') {
+        Err(Problem::Dot)
+    } else if plain.ends_with(' ') {
+        Err(Problem::TrailingSpace)
+    } else if let Some(c) = plain.chars().find(|&c| bad_char(c)) {
+        Err(Problem::Char(c))
+    } else if reserved(&plain) {
+        Err(Problem::Reserved)
+    } else if file_name_len(name) > MAX_NAME_LEN {
+        Err(Problem::TooLong(file_name_len(name)))
+    } else {
+        Ok(())
+    }
+}
+
+// the closest name to this one that passes check
+pub fn fix(name: &str) -> String {
+    let trim = |units: &mut Vec<u16>| {
+        while units.last().is_some_and(|&u| u == u16::from(b' ') || u == u16::from(b'.')) {
+            units.pop();
+        }
+    };
+    let mut units = to_wide(name)
+        .into_iter()
+        .map(|u| match char::from_u32(u.into()) {
+            Some(c) if bad_char(c) => u16::from(b'_'),
+            _ => u,
+        })
+        .collect::<Vec<_>>();
+    trim(&mut units);
+    if units.is_empty() {
+        units.push(u16::from(b'_'));
+    }
+    if reserved(&String::from_utf16_lossy(&units)) {
+        let stem = units.iter().position(|&u| u == u16::from(b'.')).unwrap_or(units.len());
+        let stem = units[..stem].iter().rposition(|&u| u != u16::from(b' ')).map_or(0, |i| i + 1);
+        units.insert(stem, u16::from(b'_'));
+    }
+    let mut out = from_wide(&units);
+    while file_name_len(&out) > MAX_NAME_LEN {
+        units.pop();
+        trim(&mut units);
+        out = from_wide(&units);
+    }
+    out
+}
@@ -0,0 +1,243 @@
+pub mod asset;
+pub mod casing;
+pub mod escape;
+pub mod events;
+pub mod filename;
+pub mod library;
+pub mod load;
+pub mod load_exe;
+pub mod manifest;
+pub mod output;
+pub mod prune;
+pub mod recompress;
+pub mod save;
+pub mod save_exe;
+pub mod transaction;
+
+use std::{collections::HashSet, path::PathBuf, time::SystemTime};
+
+#[derive(Debug)]
+pub enum Error {
+    IoError(std::io::Error),
+    FileIoError(std::io::Error, PathBuf),
+    DirIoError(std::io::Error, PathBuf),
+    PngDecodeError(PathBuf, png::DecodingError),
+    UnicodeError(String),
+    AssetNotFound(String, &'static str, String),
+    SyntaxError(PathBuf),
+    UnknownKey(PathBuf, String),
+    UnknownAction(u32, u32),
+    ParseIntError(std::num::ParseIntError),
+    ParseFloatError(std::num::ParseFloatError),
+    InvalidVersion(String),
+    DuplicateAsset(String),
+    DuplicateIncludedFile(String),
+    DuplicateTrigger(String),
+    DuplicateId(&'static str, usize, String),
+    BadAssetName(String, filename::Problem),
+    BadIncludedFileName(String, filename::Problem),
+    BadTriggerName(String, filename::Problem),
+    OldGM82,
+    InterruptedSave(PathBuf),
+    CaseMismatch(PathBuf, String),
+    InFile(PathBuf, Box<Error>),
+    AtLine(usize, usize, Box<Error>),
+    Multiple(Vec<Error>),
+    Other(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::IoError(e) => write!(f, "io error: {}", e),
+            Self::FileIoError(e, p) => write!(f, "io error in file {}: {}", p.to_string_lossy(), e),
+            Self::DirIoError(e, p) => write!(f, "io error in directory {}: {}", p.to_string_lossy(), e),
+            Self::PngDecodeError(p, e) => write!(f, "couldn't decode image {}: {}", p.to_string_lossy(), e),
+            Self::UnicodeError(s) => write!(f, "couldn't encode {}", s),
+            Self::AssetNotFound(s, t, src) => write!(f, "couldn't find {} {} (from {})", t, s, src),
+            Self::SyntaxError(p) => write!(f, "syntax error in file {}", p.to_string_lossy()),
+            Self::UnknownKey(p, k) => write!(f, "unknown key in {}: {:?}", p.to_string_lossy(), k),
+            Self::UnknownAction(lib_id, act_id) => write!(f, "unknown action {} in lib with id {}", act_id, lib_id),
+            Self::ParseIntError(e) => write!(f, "integer parse error: {}", e),
+            Self::ParseFloatError(e) => write!(f, "float parse error: {}", e),
+            Self::InvalidVersion(v) => write!(f, "invalid exe_version {}", v),
+            Self::DuplicateAsset(n) => write!(f, "multiple assets named {}", n),
+            Self::DuplicateIncludedFile(n) => write!(f, "multiple included files named {}", n),
+            Self::DuplicateTrigger(n) => write!(f, "multiple triggers named {}", n),
+            Self::DuplicateId(t, id, room) => write!(f, "{} id {} in room {} is already in use", t, id, room),
+            Self::BadAssetName(n, p) => write!(f, "asset name \"{n}\" {p}, try \"{}\" instead", filename::fix(n)),
+            Self::BadIncludedFileName(n, p) => {
+                write!(f, "included file name \"{n}\" {p}, try \"{}\" instead", filename::fix(n))
+            },
+            Self::BadTriggerName(n, p) => write!(f, "trigger name \"{n}\" {p}, try \"{}\" instead", filename::fix(n)),
+            Self::OldGM82 => write!(f, "this project was made with a newer version of gm82save, please update"),
+            Self::InterruptedSave(p) => {
+                write!(f, "a save of {} was interrupted and needs recovering", p.to_string_lossy())
+            },
+            Self::CaseMismatch(p, expected) => {
+                write!(f, "{} should be called {} to match index.yyd", p.to_string_lossy(), expected)
+            },
+            Self::InFile(p, e) => write!(f, "in {}: {}", p.to_string_lossy(), e),
+            Self::AtLine(line, column, e) => match column {
+                0 => write!(f, "{} (line {})", e, line),
+                _ => write!(f, "{} (line {}, column {})", e, line, column),
+            },
+            Self::Multiple(errors) => {
+                write!(f, "{} errors:", errors.len())?;
+                errors.iter().try_for_each(|e| write!(f, "\n{}", e))
+            },
+            Self::Other(s) => write!(f, "other error: {}", s),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl Error {
+    // say which file an error came from, unless it does already
+    pub fn in_file(self, path: &std::path::Path) -> Self {
+        match self {
+            Self::FileIoError(..)
+            | Self::DirIoError(..)
+            | Self::PngDecodeError(..)
+            | Self::SyntaxError(..)
+            | Self::UnknownKey(..)
+            | Self::InterruptedSave(..)
+            | Self::InFile(..)
+            | Self::AtLine(..)
+            | Self::Multiple(..) => self,
+            e => Self::InFile(path.to_path_buf(), Box::new(e)),
+        }
+    }
+
+    // say which line of a file an error is on, and which column unless that's 0
+    // errors about some other file, or that have a line already, are left alone
+    pub fn at(self, path: &std::path::Path, line: usize, column: usize) -> Self {
+        match self.in_file(path) {
+            e @ (Self::SyntaxError(..) | Self::UnknownKey(..) | Self::InFile(..)) if e.path() == Some(path) => {
+                Self::AtLine(line, column, Box::new(e))
+            },
+            e => e,
+        }
+    }
+
+    fn path(&self) -> Option<&std::path::Path> {
+        match self {
+            Self::SyntaxError(p) | Self::UnknownKey(p, _) | Self::InFile(p, _) => Some(p),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::IoError(err)
+    }
+}
+
+impl From<std::num::ParseIntError> for Error {
+    fn from(err: std::num::ParseIntError) -> Self {
+        Error::ParseIntError(err)
+    }
+}
+
+impl From<std::num::ParseFloatError> for Error {
+    fn from(err: std::num::ParseFloatError) -> Self {
+        Error::ParseFloatError(err)
+    }
+}
+
+// line iterator that strips right end but only if not in a string
+pub struct GMLLines<'a>(std::str::Lines<'a>, u8);
+
+impl<'a> GMLLines<'a> {
+    pub fn new(lines: std::str::Lines<'a>) -> Self {
+        Self(lines, 0)
+    }
+}
+
+impl<'a> Iterator for GMLLines<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // trim line only if EOL is not in a string
+        let line = self.0.next()?;
+        let trimmed = line.trim_end();
+        for c in trimmed.bytes() {
+            if self.1 == 0 && (c == b'"' || c == b'\'') {
+                self.1 = c;
+            } else if c == self.1 {
+                self.1 = 0;
+            }
+        }
+        Some(if self.1 == 0 { trimmed } else { line })
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+// how far through loading or saving a project is, out of 100
+// it can get called from any thread, not just the one that started it
+pub type Progress<'a> = &'a (dyn Fn(u32) + Sync);
+
+pub const ACTION_TOKEN: &str = "/*\"/*'/**//* YYD ACTION";
+
+// keys in asset files that start with this are left for other tools, and kept as they are
+pub const EXTRA_KEY_PREFIX: &str = "x_";
+
+// the newest project format this crate can read and the one it writes
+// 8 added extra keys and png_effort, and changed how text and file names are escaped
+pub const GM82_VERSION: u8 = 8;
+
+// columns in instances.txt and the tile layer files, which start with a row naming the ones they use
+// files from before version 7 have no such row and always use these in this order
+pub const INSTANCE_COLUMNS: [&str; 11] =
+    ["object", "x", "y", "name", "locked", "xscale", "yscale", "blend", "angle", "has_code", "id"];
+pub const TILE_COLUMNS: [&str; 13] =
+    ["background", "x", "y", "u", "v", "width", "height", "locked", "xscale", "yscale", "blend", "name", "id"];
+
+// a time as a delphi TDateTime, which is what asset timestamps are
+pub fn delphi_time(time: SystemTime) -> f64 {
+    let secs = time.duration_since(SystemTime::UNIX_EPOCH).map_or(0.0, |d| d.as_secs_f64());
+    secs / 86400.0 + 25569.0
+}
+
+pub fn now() -> f64 {
+    delphi_time(SystemTime::now())
+}
+
+// new names for instances and tiles, worked out from the room, what's placed and where. if that's taken, it tries
+// again with a count after it. so the name something gets only depends on the names already taken, and naming
+// the same project always comes out the same, whichever program does it
+pub struct Namer {
+    // every name in the project, new ones included
+    taken: HashSet<u32>,
+    room: String,
+}
+
+impl Namer {
+    pub fn new(taken: HashSet<u32>) -> Self {
+        Self { taken, room: String::new() }
+    }
+
+    pub fn room(&mut self, name: &str) {
+        self.room = name.to_string();
+    }
+
+    // asset is the object or background's name
+    pub fn name(&mut self, asset: &str, x: i32, y: i32) -> u32 {
+        for count in 0u32.. {
+            let fields: [&[u8]; 5] =
+                [self.room.as_bytes(), asset.as_bytes(), &x.to_le_bytes(), &y.to_le_bytes(), &count.to_le_bytes()];
+            // fnv-1a, unlike std's hasher it's guaranteed to stay the same
+            let mut name = 0x811c9dc5u32;
+            for byte in fields.join(&0) {
+                name = (name ^ u32::from(byte)).wrapping_mul(0x01000193);
+            }
+            if name != 0 && self.taken.insert(name) {
+                return name
+            }
+        }
+        unreachable!()
+    }
+}
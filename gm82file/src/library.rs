@@ -0,0 +1,176 @@
+use crate::{Error, Result};
+use std::{
+    io::{self, Read},
+    path::Path,
+};
+
+// a GM8.1 action library (.lib), minus the images
+#[derive(Clone, Debug, Default)]
+pub struct ActionDefinition {
+    pub name: String,
+    pub id: u32,
+    pub hidden: bool,
+    pub advanced: bool,
+    pub pro_only: bool,
+    pub short_desc: String,
+    pub list_text: String,
+    pub hint_text: String,
+    pub kind: u32,
+    pub interface: u32,
+    pub question: bool,
+    pub apply_to: bool,
+    pub relative: bool,
+    pub arg_count: u32,
+    pub arg_captions: [String; 8],
+    pub arg_types: [u32; 8],
+    pub arg_defaults: [String; 8],
+    pub arg_menus: [String; 8],
+    pub execution_type: u32,
+    pub function_name: String,
+    pub code_string: String,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ActionLibrary {
+    pub caption: String,
+    pub id: u32,
+    pub author: String,
+    pub version: u32,
+    pub last_changed: f64,
+    pub information: String,
+    pub init_code: String,
+    pub advanced: bool,
+    pub actions: Vec<ActionDefinition>,
+}
+
+// what the IDE would have loaded from its lib folder
+// if this is empty, actions are read without checking them against a definition
+#[derive(Clone, Debug, Default)]
+pub struct ActionLibraries(pub Vec<ActionLibrary>);
+
+// windows-1252, which is what a stock install's libs are written in
+const CP1252_HIGH: [char; 32] = [
+    '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8d}', 'Ž', '\u{8f}', '\u{90}', '‘', '’',
+    '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9d}', 'ž', 'Ÿ',
+];
+
+pub fn decode_ansi(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&b| match b {
+            0x80..=0x9f => CP1252_HIGH[usize::from(b - 0x80)],
+            b => char::from(b),
+        })
+        .collect()
+}
+
+fn read_u32(f: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0; 4];
+    f.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_bool(f: &mut impl Read) -> io::Result<bool> {
+    Ok(read_u32(f)? != 0)
+}
+
+fn read_f64(f: &mut impl Read) -> io::Result<f64> {
+    let mut buf = [0; 8];
+    f.read_exact(&mut buf)?;
+    Ok(f64::from_le_bytes(buf))
+}
+
+fn read_bytes(f: &mut impl Read) -> io::Result<Vec<u8>> {
+    let len = read_u32(f)? as usize;
+    let mut buf = vec![0; len];
+    f.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn read_string(f: &mut impl Read) -> io::Result<String> {
+    read_bytes(f).map(|b| decode_ansi(&b))
+}
+
+impl ActionLibrary {
+    pub fn read(mut f: impl Read) -> io::Result<Self> {
+        let bad_version = |v| io::Error::new(io::ErrorKind::InvalidData, format!("unsupported lib version {}", v));
+        let version = read_u32(&mut f)?;
+        if version != 500 && version != 520 {
+            return Err(bad_version(version))
+        }
+        let mut lib = Self {
+            caption: read_string(&mut f)?,
+            id: read_u32(&mut f)?,
+            author: read_string(&mut f)?,
+            version: read_u32(&mut f)?,
+            last_changed: read_f64(&mut f)?,
+            information: read_string(&mut f)?,
+            init_code: read_string(&mut f)?,
+            advanced: read_bool(&mut f)?,
+            actions: Vec::new(),
+        };
+        let _max_id = read_u32(&mut f)?;
+        let count = read_u32(&mut f)?;
+        lib.actions.reserve(count as usize);
+        for _ in 0..count {
+            let act_version = read_u32(&mut f)?;
+            if act_version != 500 && act_version != 520 {
+                return Err(bad_version(act_version))
+            }
+            let mut act = ActionDefinition { name: read_string(&mut f)?, id: read_u32(&mut f)?, ..Default::default() };
+            let _image = read_bytes(&mut f)?;
+            act.hidden = read_bool(&mut f)?;
+            act.advanced = read_bool(&mut f)?;
+            if version == 520 {
+                act.pro_only = read_bool(&mut f)?;
+            }
+            act.short_desc = read_string(&mut f)?;
+            act.list_text = read_string(&mut f)?;
+            act.hint_text = read_string(&mut f)?;
+            act.kind = read_u32(&mut f)?;
+            act.interface = read_u32(&mut f)?;
+            act.question = read_bool(&mut f)?;
+            act.apply_to = read_bool(&mut f)?;
+            act.relative = read_bool(&mut f)?;
+            act.arg_count = read_u32(&mut f)?;
+            // all 8 slots are always there
+            for i in 0..8 {
+                act.arg_captions[i] = read_string(&mut f)?;
+                act.arg_types[i] = read_u32(&mut f)?;
+                act.arg_defaults[i] = read_string(&mut f)?;
+                act.arg_menus[i] = read_string(&mut f)?;
+            }
+            act.execution_type = read_u32(&mut f)?;
+            act.function_name = read_string(&mut f)?;
+            act.code_string = read_string(&mut f)?;
+            lib.actions.push(act);
+        }
+        Ok(lib)
+    }
+}
+
+impl ActionLibraries {
+    pub fn load_dir(path: &Path) -> Result<Self> {
+        let mut files = std::fs::read_dir(path)
+            .map_err(|e| Error::DirIoError(e, path.to_path_buf()))?
+            .map(|e| e.map(|e| e.path()))
+            .collect::<io::Result<Vec<_>>>()
+            .map_err(|e| Error::DirIoError(e, path.to_path_buf()))?;
+        files.retain(|p| p.extension().is_some_and(|e| e.eq_ignore_ascii_case("lib")));
+        files.sort();
+        let mut libs = Vec::with_capacity(files.len());
+        for file in files {
+            let f = std::fs::File::open(&file).map_err(|e| Error::FileIoError(e, file.clone()))?;
+            libs.push(ActionLibrary::read(io::BufReader::new(f)).map_err(|e| Error::FileIoError(e, file))?);
+        }
+        Ok(Self(libs))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn get(&self, lib_id: u32, act_id: u32) -> Option<&ActionDefinition> {
+        self.0.iter().filter(|l| l.id == lib_id).flat_map(|l| &l.actions).find(|a| a.id == act_id)
+    }
+}
@@ -0,0 +1,1754 @@
+use crate::{
+    asset::*,
+    casing, delphi_time,
+    escape::{file_name, undelimit, upgrade},
+    events,
+    library::ActionLibraries,
+    output::PngEffort,
+    save::name_instances,
+    transaction, Error, GMLLines, Progress, Result, ACTION_TOKEN, EXTRA_KEY_PREFIX, GM82_VERSION, INSTANCE_COLUMNS,
+    TILE_COLUMNS,
+};
+use rayon::prelude::*;
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    ffi::OsStr,
+    fs::File,
+    io::{BufRead, BufReader},
+    path::PathBuf,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+pub fn load_gml(code: &str) -> String {
+    let mut buf = String::with_capacity(code.len());
+    // don't use string.replace() in case your gml is \r\n for some reason
+    for line in GMLLines::new(code.lines()) {
+        buf += line;
+        buf += "\r\n";
+    }
+    buf
+}
+
+pub struct Assets {
+    pub index: Vec<String>,
+    pub map: HashMap<String, usize>,
+}
+
+impl Assets {
+    // the old name still finds it, so whatever refers to it by that loads the same
+    fn rename(&mut self, from: &str, to: &str) {
+        if let Some(&i) = self.map.get(from) {
+            self.index[i] = to.to_string();
+            self.map.insert(to.to_string(), i);
+        }
+    }
+}
+
+pub struct AssetMaps<'a> {
+    pub triggers: Assets,
+    pub sprites: Assets,
+    pub sounds: Assets,
+    pub backgrounds: Assets,
+    pub paths: Assets,
+    pub scripts: Assets,
+    pub objects: Assets,
+    pub rooms: Assets,
+    pub fonts: Assets,
+    pub timelines: Assets,
+    pub libs: &'a ActionLibraries,
+    // the format version of the project these are from
+    pub version: u8,
+}
+
+impl AssetMaps<'_> {
+    fn assets_mut(&mut self, dir: &str) -> Option<&mut Assets> {
+        match dir {
+            "triggers" => Some(&mut self.triggers),
+            "sprites" => Some(&mut self.sprites),
+            "sounds" => Some(&mut self.sounds),
+            "backgrounds" => Some(&mut self.backgrounds),
+            "paths" => Some(&mut self.paths),
+            "scripts" => Some(&mut self.scripts),
+            "objects" => Some(&mut self.objects),
+            "rooms" => Some(&mut self.rooms),
+            "fonts" => Some(&mut self.fonts),
+            "timelines" => Some(&mut self.timelines),
+            _ => None,
+        }
+    }
+
+    // versions before 8 named files exactly what the asset's called
+    pub fn file_name<'b>(&self, name: &'b str) -> Cow<'b, str> {
+        if self.version < 8 {
+            Cow::Borrowed(name)
+        } else {
+            file_name(name)
+        }
+    }
+}
+
+fn open_file(path: &std::path::Path) -> Result<BufReader<File>> {
+    Ok(BufReader::new(File::open(path).map_err(|e| Error::FileIoError(e, path.to_path_buf()))?))
+}
+
+fn read_file<P: AsRef<std::path::Path>>(path: P) -> Result<String> {
+    std::fs::read_to_string(path.as_ref()).map_err(|e| Error::FileIoError(e, path.as_ref().to_path_buf()))
+}
+
+fn read_binary<P: AsRef<std::path::Path>>(path: P) -> Result<Vec<u8>> {
+    std::fs::read(path.as_ref()).map_err(|e| Error::FileIoError(e, path.as_ref().to_path_buf()))
+}
+
+// the line a slice of a file's text starts on, counting from 1
+pub fn line_of(text: &str, part: &str) -> usize {
+    let offset = (part.as_ptr() as usize).saturating_sub(text.as_ptr() as usize).min(text.len());
+    text.as_bytes()[..offset].iter().filter(|&&c| c == b'\n').count() + 1
+}
+
+pub fn decode_line<'a, F: FnMut(&'a str, &'a str) -> Result<()>>(
+    path: &std::path::Path,
+    line_number: usize,
+    line: &'a str,
+    func: &mut F,
+) -> Result<()> {
+    if !line.is_empty() {
+        let (key, value) =
+            line.split_once('=').ok_or_else(|| Error::SyntaxError(path.to_path_buf()).at(path, line_number, 0))?;
+        func(key, value).map_err(|e| match e {
+            Error::UnknownKey(..) => e.at(path, line_number, 1),
+            // anything else is about the value
+            e => e.at(path, line_number, key.len() + 2),
+        })?;
+    }
+    Ok(())
+}
+
+pub fn read_txt<F: FnMut(&str, &str) -> Result<()>>(path: &std::path::Path, mut func: F) -> Result<()> {
+    let f = open_file(path)?;
+    for (i, line) in f.lines().enumerate() {
+        decode_line(path, i + 1, &line?, &mut func)?;
+    }
+    Ok(())
+}
+
+pub fn read_resource_tree(
+    type_name: &'static str,
+    names: &HashMap<String, usize>,
+    path: &mut PathBuf,
+) -> Result<Vec<TreeNode>> {
+    if names.is_empty() {
+        return Ok(Vec::new())
+    }
+    path.push(type_name);
+    path.push("tree.yyd");
+    let f = open_file(path)?;
+    // each level is the list of children of the group one level up
+    let mut stack: Vec<(String, Vec<TreeNode>)> = vec![(String::new(), Vec::new())];
+    for (i, line) in f.lines().enumerate() {
+        let line = line?;
+        if line.is_empty() {
+            continue
+        }
+        let trimmed = line.trim_start();
+        let level = line.len() - trimmed.len();
+        let err_at = |e: Error| e.at(path, i + 1, level + 1);
+        while stack.len() > level + 1 {
+            let (name, children) = stack.pop().unwrap();
+            stack.last_mut().unwrap().1.push(TreeNode::Group(name, children));
+        }
+        let name = &trimmed[1..];
+        match trimmed.chars().next() {
+            Some('+') => stack.push((name.to_string(), Vec::new())),
+            Some('|') => {
+                let index = *names.get(name).ok_or_else(|| {
+                    err_at(Error::AssetNotFound(
+                        name.to_string(),
+                        &type_name[..type_name.len() - 1],
+                        "resource tree".to_string(),
+                    ))
+                })?;
+                stack.last_mut().unwrap().1.push(TreeNode::Asset(index));
+            },
+            _ => return Err(err_at(Error::SyntaxError(path.to_path_buf()))),
+        }
+    }
+    while stack.len() > 1 {
+        let (name, children) = stack.pop().unwrap();
+        stack.last_mut().unwrap().1.push(TreeNode::Group(name, children));
+    }
+    path.pop();
+    path.pop();
+    Ok(stack.pop().unwrap().1)
+}
+
+fn load_trigger(name: &str, mut path: PathBuf) -> Result<Trigger> {
+    let mut trig = Trigger { name: name.to_string(), ..Default::default() };
+    path.set_extension("txt");
+    read_txt(&path, |k, v| {
+        match k {
+            "constant" => trig.constant_name = v.to_string(),
+            "kind" => trig.kind = v.parse()?,
+            _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
+        }
+        Ok(())
+    })?;
+    path.set_extension("gml");
+    trig.condition = load_gml(&read_file(&path)?);
+    Ok(trig)
+}
+
+fn load_triggers(maps: &AssetMaps, path: &mut PathBuf, diagnostics: &mut Diagnostics) -> Result<Vec<Option<Trigger>>> {
+    path.push("triggers");
+    let names = &maps.triggers.index;
+    let mut triggers = Vec::with_capacity(names.len());
+    for name in names {
+        if name.is_empty() {
+            triggers.push(None);
+            continue
+        }
+        triggers.push(diagnostics.keep(load_trigger(name, path.join(&*maps.file_name(name))))?);
+    }
+    path.pop();
+    Ok(triggers)
+}
+
+fn verify_path(path: &std::path::Path) -> Result<()> {
+    if path.exists() {
+        Ok(())
+    } else {
+        Err(Error::FileIoError(std::io::Error::new(std::io::ErrorKind::NotFound, "file not found"), path.to_path_buf()))
+    }
+}
+
+fn load_sound(path: &mut PathBuf, _asset_maps: &AssetMaps, timestamp: &mut f64) -> Result<Sound> {
+    let mut snd = Sound::default();
+    path.set_extension("txt");
+    let mut exists = false;
+    read_txt(path, |k, v| {
+        match k {
+            k if k.starts_with(EXTRA_KEY_PREFIX) => snd.extra_keys.push((k.to_string(), v.to_string())),
+            "extension" => snd.extension = v.to_string(),
+            "source" => snd.source = v.to_string(),
+            "exists" => exists = v.parse::<u8>()? != 0,
+            "kind" => snd.kind = v.parse()?,
+            "effects" => snd.effects = v.parse()?,
+            "volume" => snd.volume = v.parse()?,
+            "pan" => snd.pan = v.parse()?,
+            "preload" => snd.preload = v.parse::<u8>()? != 0,
+            "timestamp" => *timestamp = v.parse()?, // legacy
+            _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
+        }
+        Ok(())
+    })?;
+    if exists {
+        path.set_extension(snd.extension.trim_matches('.'));
+        verify_path(path)?;
+        snd.data = Some(read_binary(&path)?);
+    }
+    Ok(snd)
+}
+
+pub fn load_frame(path: &std::path::Path) -> Result<Frame> {
+    use png::{BitDepth, ColorType, Decoder, Transformations};
+    let err = |e| Error::PngDecodeError(path.to_path_buf(), e);
+    // no open_file because png uses BufReader internally
+    let mut decoder = Decoder::new(File::open(path).map_err(|e| Error::FileIoError(e, path.to_path_buf()))?);
+    decoder.set_transformations(Transformations::EXPAND | Transformations::STRIP_16);
+    let mut reader = decoder.read_info().map_err(err)?;
+    let info = reader.info();
+    let (width, height) = (info.width, info.height);
+    // do this calculation myself in case the png is a weird format
+    let line_size = width as usize * 4;
+    let mut data = vec![0u8; line_size * height as usize];
+    match (info.bit_depth, info.color_type) {
+        (BitDepth::Eight, ColorType::Rgba) => {
+            // this should be the only one that actually gets used
+            // but i'll allow other formats too just to be nice
+            reader.next_frame(&mut data).map_err(err)?;
+            // RGBA8 -> BGRA8
+            data.par_chunks_exact_mut(4).for_each(|px| px.swap(0, 2));
+        },
+        (BitDepth::Eight, ColorType::Rgb) => {
+            for dst_row in data.chunks_exact_mut(line_size) {
+                let src_row = reader.next_row().map_err(err)?.ok_or_else(|| {
+                    Error::Other(format!("decoding ended too soon for image {}", path.to_string_lossy()))
+                })?;
+                // RGB8 -> BGR8
+                for (dst, src) in dst_row.chunks_exact_mut(4).zip(src_row.data().chunks_exact(3)) {
+                    dst[0] = src[2];
+                    dst[1] = src[1];
+                    dst[2] = src[0];
+                    dst[3] = 255;
+                }
+            }
+        },
+        (BitDepth::Eight, ColorType::Grayscale) => {
+            for dst_row in data.chunks_exact_mut(line_size) {
+                let src_row = reader.next_row().map_err(err)?.ok_or_else(|| {
+                    Error::Other(format!("decoding ended too soon for image {}", path.to_string_lossy()))
+                })?;
+                for (dst, &src) in dst_row.chunks_exact_mut(4).zip(src_row.data()) {
+                    dst[0..3].fill(src);
+                    dst[3] = 255;
+                }
+            }
+        },
+        (BitDepth::Eight, ColorType::GrayscaleAlpha) => {
+            for dst_row in data.chunks_exact_mut(line_size) {
+                let src_row = reader.next_row().map_err(err)?.ok_or_else(|| {
+                    Error::Other(format!("decoding ended too soon for image {}", path.to_string_lossy()))
+                })?;
+                for (dst, src) in dst_row.chunks_exact_mut(4).zip(src_row.data().chunks_exact(2)) {
+                    dst[0..3].fill(src[0]);
+                    dst[3] = src[1];
+                }
+            }
+        },
+        (depth, coltype) => {
+            // the above should cover all valid formats
+            // see https://www.w3.org/TR/PNG-Chunks.html
+            return Err(Error::Other(format!(
+                "couldn't understand format {:?}/{:?} for image {}",
+                depth,
+                coltype,
+                path.to_string_lossy()
+            )))
+        },
+    }
+    Ok(Frame { width, height, data })
+}
+
+fn load_background(path: &mut PathBuf, _asset_maps: &AssetMaps, timestamp: &mut f64) -> Result<Background> {
+    let mut bg = Background::default();
+    path.set_extension("txt");
+    let mut bg_exists = false;
+    read_txt(path, |k, v| {
+        match k {
+            k if k.starts_with(EXTRA_KEY_PREFIX) => bg.extra_keys.push((k.to_string(), v.to_string())),
+            "exists" => bg_exists = v.parse::<u8>()? != 0,
+            "tileset" => bg.is_tileset = v.parse::<u8>()? != 0,
+            "tile_width" => bg.tile_width = v.parse()?,
+            "tile_height" => bg.tile_height = v.parse()?,
+            "tile_hoffset" => bg.h_offset = v.parse()?,
+            "tile_voffset" => bg.v_offset = v.parse()?,
+            "tile_hsep" => bg.h_sep = v.parse()?,
+            "tile_vsep" => bg.v_sep = v.parse()?,
+            "timestamp" => *timestamp = v.parse()?, // legacy
+            _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
+        }
+        Ok(())
+    })?;
+    if bg_exists {
+        path.set_extension("png");
+        bg.frame = load_frame(path)?;
+    }
+    Ok(bg)
+}
+
+fn load_sprite(path: &mut PathBuf, _asset_maps: &AssetMaps, timestamp: &mut f64) -> Result<Sprite> {
+    let mut sp = Sprite::default();
+    let mut frame_count = 0usize;
+    path.push("sprite.txt");
+    read_txt(path, |k, v| {
+        match k {
+            k if k.starts_with(EXTRA_KEY_PREFIX) => sp.extra_keys.push((k.to_string(), v.to_string())),
+            "frames" => frame_count = v.parse()?,
+            "origin_x" => sp.origin_x = v.parse()?,
+            "origin_y" => sp.origin_y = v.parse()?,
+            "collision_shape" => sp.collision_shape = v.parse()?,
+            "alpha_tolerance" => sp.alpha_tolerance = v.parse()?,
+            "per_frame_colliders" => sp.per_frame_colliders = v.parse::<u8>()? != 0,
+            "bbox_type" => sp.bbox_type = v.parse()?,
+            "bbox_left" => sp.bbox_left = v.parse()?,
+            "bbox_bottom" => sp.bbox_bottom = v.parse()?,
+            "bbox_right" => sp.bbox_right = v.parse()?,
+            "bbox_top" => sp.bbox_top = v.parse()?,
+            "timestamp" => *timestamp = v.parse()?, // legacy
+            _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
+        }
+        Ok(())
+    })?;
+    path.pop();
+    sp.frames.reserve(frame_count);
+    for i in 0..frame_count {
+        path.push(format!("{}.png", i));
+        sp.frames.push(load_frame(path)?);
+        path.pop();
+    }
+    Ok(sp)
+}
+
+fn load_script(path: &mut PathBuf, _asset_maps: &AssetMaps, _timestamp: &mut f64) -> Result<Script> {
+    path.set_extension("gml");
+    Ok(Script { source: load_gml(&read_file(path)?) })
+}
+
+fn load_font(path: &mut PathBuf, _asset_maps: &AssetMaps, timestamp: &mut f64) -> Result<Font> {
+    let mut f = Font::default();
+    path.set_extension("txt");
+    read_txt(path, |k, v| {
+        match k {
+            k if k.starts_with(EXTRA_KEY_PREFIX) => f.extra_keys.push((k.to_string(), v.to_string())),
+            "name" => f.sys_name = v.to_string(),
+            "size" => f.size = v.parse()?,
+            "bold" => f.bold = v.parse::<u8>()? != 0,
+            "italic" => f.italic = v.parse::<u8>()? != 0,
+            "charset" => f.charset = v.parse()?,
+            "aa_level" => f.aa_level = v.parse()?, // DOES NOT CORRESPOND TO .GMK OR .EXE
+            "range_start" => f.range_start = v.parse()?,
+            "range_end" => f.range_end = v.parse()?,
+            "timestamp" => *timestamp = v.parse()?, // legacy
+            _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
+        }
+        Ok(())
+    })?;
+    Ok(f)
+}
+
+// without any action libraries there's no definition to fill in from,
+// so make a best guess at what kind of action this was from what got written
+fn infer_action(action: &mut Action, keys: &HashSet<&str>, code: &str) {
+    action.can_be_relative = keys.contains("relative");
+    action.applies_to_something = keys.contains("applies_to");
+    action.param_count = (0..8u32).rev().find(|i| keys.contains(format!("arg{}", i).as_str())).map_or(0, |i| i + 1);
+    action.action_kind = if keys.contains("repeats") {
+        5
+    } else if keys.contains("var_name") || keys.contains("var_value") {
+        6
+    } else if keys.contains("invert") || action.param_count != 0 {
+        0
+    } else {
+        // stock control actions that don't write anything
+        match (action.lib_id, action.id) {
+            _ if !code.trim().is_empty() => 7,
+            (1, 421) => 3, // else
+            (1, 422) => 1, // start block
+            (1, 424) => 2, // end block
+            (1, 425) => 4, // exit event
+            _ => 7,
+        }
+    };
+}
+
+// first_line is the line event_code starts on in the file
+fn load_event(
+    path: &std::path::Path,
+    event: &mut Event,
+    event_code: &str,
+    first_line: usize,
+    asset_maps: &AssetMaps,
+) -> Result<()> {
+    let object_name = path.file_stem().map(OsStr::to_string_lossy).unwrap_or_default();
+    let strict = !asset_maps.libs.is_empty();
+    let mut line_number = first_line;
+    for action_code in event_code.split(ACTION_TOKEN) {
+        let first_line = line_number;
+        line_number += action_code.matches('\n').count();
+        if action_code.trim().is_empty() {
+            continue
+        }
+        let (params, code) = action_code
+            .split_once("*/")
+            .ok_or_else(|| Error::SyntaxError(path.to_path_buf()).at(path, first_line, 0))?;
+        let mut action = Action::default();
+        let mut lib_id_set = false;
+        let mut act_id_set = false;
+        let mut keys = HashSet::new();
+        for (i, line) in params.lines().enumerate() {
+            decode_line(path, first_line + i, line, &mut |k, v| {
+                keys.insert(k);
+                match k {
+                    "lib_id" => {
+                        action.lib_id = v.parse()?;
+                        if lib_id_set {
+                            return Err(Error::SyntaxError(path.to_path_buf()))
+                        }
+                        lib_id_set = true;
+                    },
+                    "action_id" => {
+                        action.id = v.parse()?;
+                        if !lib_id_set || act_id_set {
+                            return Err(Error::SyntaxError(path.to_path_buf()))
+                        }
+                        act_id_set = true;
+                        // manually check if action exists so we can throw an error
+                        if strict {
+                            match asset_maps.libs.get(action.lib_id, action.id) {
+                                Some(def) => action.fill_in(def),
+                                None => return Err(Error::UnknownAction(action.lib_id, action.id)),
+                            }
+                        }
+                    },
+                    "relative" => action.is_relative = v.parse::<u8>()? != 0,
+                    "applies_to" => {
+                        action.applies_to = match v {
+                            "other" => -2,
+                            "self" => -1,
+                            "" => -4,
+                            name => *asset_maps.objects.map.get(name).ok_or_else(|| {
+                                Error::AssetNotFound(
+                                    name.to_string(),
+                                    "object",
+                                    format!("object {object_name} code action applies_to"),
+                                )
+                            })? as _,
+                        }
+                    },
+                    "invert" => action.invert_condition = v.parse::<u8>()? != 0,
+                    "var_name" | "repeats" => action.param_strings[0] = v.to_string(),
+                    "var_value" => action.param_strings[1] = v.to_string(),
+                    "arg0" | "arg1" | "arg2" | "arg3" | "arg4" | "arg5" | "arg6" | "arg7" => {
+                        if !act_id_set {
+                            return Err(Error::SyntaxError(path.to_path_buf()))
+                        }
+                        let i = k.chars().last().unwrap().to_digit(8).unwrap() as usize;
+                        let err = |t| {
+                            Error::AssetNotFound(
+                                v.to_string(),
+                                t,
+                                format!("object {object_name} code action parameter"),
+                            )
+                        };
+                        let ptype = action.param_types[i];
+                        if strict && (5..=14).contains(&ptype) && ptype != 13 {
+                            action.param_strings[i] = match ptype {
+                                _ if v.is_empty() => -1,
+                                5 => *asset_maps.sprites.map.get(v).ok_or_else(|| err("sprite"))? as _,
+                                6 => *asset_maps.sounds.map.get(v).ok_or_else(|| err("sound"))? as _,
+                                7 => *asset_maps.backgrounds.map.get(v).ok_or_else(|| err("background"))? as _,
+                                8 => *asset_maps.paths.map.get(v).ok_or_else(|| err("path"))? as _,
+                                9 => *asset_maps.scripts.map.get(v).ok_or_else(|| err("script"))? as _,
+                                10 => *asset_maps.objects.map.get(v).ok_or_else(|| err("object"))? as _,
+                                11 => *asset_maps.rooms.map.get(v).ok_or_else(|| err("room"))? as _,
+                                12 => *asset_maps.fonts.map.get(v).ok_or_else(|| err("font"))? as _,
+                                14 => *asset_maps.timelines.map.get(v).ok_or_else(|| err("timeline"))? as _,
+                                _ => unreachable!(),
+                            }
+                            .to_string();
+                        } else {
+                            action.param_strings[i] = undelimit(v);
+                        }
+                    },
+                    _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
+                }
+                Ok(())
+            })?;
+        }
+        if !strict {
+            infer_action(&mut action, &keys, code);
+        }
+        if action.action_kind == 7 {
+            // first character will always be a newline because it doesn't cut the newline when searching for */
+            // so skip it
+            let code = if let Some(code) = code.strip_prefix('\n') {
+                code
+            } else if let Some(code) = code.strip_prefix("\r\n") {
+                code
+            } else {
+                code
+            };
+            action.param_strings[0] = load_gml(code);
+        }
+        event.actions.push(action);
+    }
+    Ok(())
+}
+
+fn load_object(path: &mut PathBuf, asset_maps: &AssetMaps, timestamp: &mut f64) -> Result<Object> {
+    path.set_extension("txt");
+    let object_name = path.file_stem().map(OsStr::to_string_lossy).unwrap_or_default().into_owned();
+    let mut obj = Object::default();
+    let sprite_map = &asset_maps.sprites.map;
+    let object_map = &asset_maps.objects.map;
+    let trigger_map = &asset_maps.triggers.map;
+    read_txt(path, |k, v| {
+        match k {
+            k if k.starts_with(EXTRA_KEY_PREFIX) => obj.extra_keys.push((k.to_string(), v.to_string())),
+            "sprite" => {
+                obj.sprite_index = match sprite_map.get(v) {
+                    Some(&i) => i as _,
+                    None if v.is_empty() => -1,
+                    _ => {
+                        return Err(Error::AssetNotFound(
+                            v.to_string(),
+                            "sprite",
+                            format!("object {object_name} sprite"),
+                        ))
+                    },
+                }
+            },
+            "visible" => obj.visible = v.parse::<u8>()? != 0,
+            "solid" => obj.solid = v.parse::<u8>()? != 0,
+            "persistent" => obj.persistent = v.parse::<u8>()? != 0,
+            "depth" => obj.depth = v.parse()?,
+            "parent" => {
+                obj.parent_index = match object_map.get(v) {
+                    Some(&i) => i as _,
+                    None if v.is_empty() => -1,
+                    _ => {
+                        return Err(Error::AssetNotFound(
+                            v.to_string(),
+                            "object",
+                            format!("object {object_name} parent"),
+                        ))
+                    },
+                }
+            },
+            "mask" => {
+                obj.mask_index = match sprite_map.get(v) {
+                    Some(&i) => i as _,
+                    None if v.is_empty() => -1,
+                    _ => {
+                        return Err(Error::AssetNotFound(v.to_string(), "sprite", format!("object {object_name} mask")))
+                    },
+                }
+            },
+            "timestamp" => *timestamp = v.parse()?, // legacy
+            _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
+        }
+        Ok(())
+    })?;
+    path.set_extension("gml");
+    let code = read_file(&path)?;
+    let mut line_number = 1;
+    for event in code.trim_start_matches("#define ").split("\n#define ") {
+        let first_line = line_number;
+        line_number += event.matches('\n').count() + 1;
+        if event.trim().is_empty() {
+            continue
+        }
+        let at_header = |e: Error| e.at(path, first_line, 0);
+        let err = || at_header(Error::SyntaxError(path.to_path_buf()));
+        let (name, actions) = event.split_once('\n').ok_or_else(err)?;
+        let (ev_type_s, ev_numb_s) = name.trim().split_once('_').ok_or_else(err)?;
+        let ev_type = events::EVENT_NAMES.iter().position(|&s| s == ev_type_s).ok_or_else(err)?;
+        let ev_numb = match ev_type {
+            events::EV_COLLISION => *object_map.get(ev_numb_s).ok_or_else(err)?,
+            events::EV_TRIGGER => *trigger_map.get(ev_numb_s).ok_or_else(err)?,
+            _ => ev_numb_s.parse::<usize>().map_err(|e| at_header(e.into()))?,
+        };
+        let event = obj.events[ev_type].entry(ev_numb).or_default();
+        load_event(path, event, actions, first_line + 1, asset_maps)?;
+    }
+    Ok(obj)
+}
+
+fn load_timeline(path: &mut PathBuf, asset_maps: &AssetMaps, _timestamp: &mut f64) -> Result<Timeline> {
+    let mut tl = Timeline::default();
+    path.set_extension("gml");
+    let code = read_file(&path)?;
+    let mut line_number = 1;
+    for code in code.trim_start_matches("#define ").split("\n#define ") {
+        let first_line = line_number;
+        line_number += code.matches('\n').count() + 1;
+        let mut event = Event::default();
+        if code.trim().is_empty() {
+            // the IDE keeps a blank moment here
+            tl.moments.push((0, event));
+            continue
+        }
+        let (name, actions) = match code.split_once('\n') {
+            Some(tuple) => tuple,
+            None if code.as_bytes().iter().all(u8::is_ascii_digit) => (code, ""), // #define 1\n#define 2
+            None => return Err(Error::SyntaxError(path.to_path_buf()).at(path, first_line, 0)),
+        };
+        let time = name.trim().parse::<u32>().map_err(|e| Error::from(e).at(path, first_line, 0))?;
+        load_event(path, &mut event, actions, first_line + 1, asset_maps)?;
+        tl.moments.push((time, event));
+    }
+    Ok(tl)
+}
+
+pub fn load_path(file_path: &mut PathBuf, asset_maps: &AssetMaps, timestamp: &mut f64) -> Result<Path> {
+    let mut path = Path::default();
+    file_path.push("path.txt");
+    let path_name = file_path
+        .parent()
+        .and_then(std::path::Path::file_name)
+        .map(OsStr::to_string_lossy)
+        .unwrap_or_default()
+        .into_owned();
+    read_txt(file_path, |k, v| {
+        match k {
+            k if k.starts_with(EXTRA_KEY_PREFIX) => path.extra_keys.push((k.to_string(), v.to_string())),
+            "connection" => path.connection = v.parse()?,
+            "closed" => path.closed = v.parse::<u8>()? != 0,
+            "precision" => path.precision = v.parse()?,
+            "background" => {
+                path.path_editor_room_background = if v.is_empty() {
+                    -1
+                } else {
+                    *asset_maps.rooms.map.get(v).ok_or_else(|| {
+                        Error::AssetNotFound(v.to_string(), "room", format!("path {path_name} room background"))
+                    })? as _
+                }
+            },
+            "snap_x" => path.snap_x = v.parse()?,
+            "snap_y" => path.snap_y = v.parse()?,
+            "timestamp" => *timestamp = v.parse()?, // legacy
+            _ => return Err(Error::UnknownKey(file_path.to_path_buf(), k.to_string())),
+        }
+        Ok(())
+    })?;
+    file_path.pop();
+    file_path.push("points.txt");
+    let points_txt = read_file(&file_path)?;
+    path.points = points_txt
+        .par_lines()
+        .map(|line| {
+            let mut iter = line.split(',');
+            let err = || Error::SyntaxError(file_path.to_path_buf());
+            let point = PathPoint {
+                x: iter.next().ok_or_else(err)?.parse()?,
+                y: iter.next().ok_or_else(err)?.parse()?,
+                speed: iter.next().ok_or_else(err)?.parse()?,
+            };
+            if iter.next().is_some() {
+                return Err(err())
+            }
+            Ok(point)
+        })
+        .collect::<Result<_>>()?;
+    file_path.pop();
+    Ok(path)
+}
+
+// the columns of an instance or tile file, in the order its rows have them
+pub struct Columns<'a>(Vec<&'a str>);
+
+impl<'a> Columns<'a> {
+    // the columns, and the rest of the file's non-empty lines after the header row if there is one
+    pub fn read(text: &'a str, known: &[&'a str], path: &std::path::Path) -> Result<(Self, Vec<&'a str>)> {
+        let mut lines = text.lines().filter(|s| !s.is_empty()).collect::<Vec<_>>();
+        match lines.first().copied() {
+            // the second field of a record is always a number, so it can't be mistaken for this
+            Some(header) if header.split(',').nth(1) == Some("x") => {
+                let names = header.split(',').collect::<Vec<_>>();
+                if let Some(name) = names.iter().find(|n| !known.contains(n)) {
+                    let column = name.as_ptr() as usize - header.as_ptr() as usize + 1;
+                    let err = Error::UnknownKey(path.to_path_buf(), name.to_string());
+                    return Err(err.at(path, line_of(text, header), column))
+                }
+                lines.remove(0);
+                Ok((Self(names), lines))
+            },
+            _ => Ok((Self(known.to_vec()), lines)),
+        }
+    }
+
+    // None if the row has more fields than there are columns
+    pub fn row<'b>(&'b self, line: &'b str) -> Option<Row<'b>> {
+        let fields = line.split(',').collect::<Vec<_>>();
+        (fields.len() <= self.0.len()).then_some(Row { columns: &self.0, fields })
+    }
+}
+
+pub struct Row<'a> {
+    columns: &'a [&'a str],
+    fields: Vec<&'a str>,
+}
+
+impl<'a> Row<'a> {
+    pub fn get(&self, column: &str) -> Option<&'a str> {
+        self.columns.iter().position(|c| *c == column).and_then(|i| self.fields.get(i).copied())
+    }
+}
+
+fn load_instances(room: &mut Room, path: &mut PathBuf, objs: &HashMap<String, usize>) -> Result<()> {
+    path.push("instances.txt");
+    let instances_txt = read_file(&path)?;
+    let (columns, instances) = Columns::read(&instances_txt, &INSTANCE_COLUMNS, path)?;
+    let inst_path = path.to_path_buf(); // save instances.txt path for errors
+    path.pop();
+    let room_name = path.file_name().map(OsStr::to_string_lossy).unwrap_or_default();
+    let err = || Error::SyntaxError(inst_path.to_path_buf());
+    let load_instance = |line: &str| -> Result<Instance> {
+        let mut instance = Instance::default();
+        let extra = &mut instance.extra;
+        let row = columns.row(line).ok_or_else(err)?;
+        instance.object = match row.get("object").ok_or_else(err)? {
+            "" => -1,
+            obj => *objs
+                .get(obj)
+                .ok_or_else(|| Error::AssetNotFound(obj.to_string(), "object", format!("room {room_name} instances")))?
+                as _,
+        };
+        instance.x = row.get("x").ok_or_else(err)?.parse()?;
+        instance.y = row.get("y").ok_or_else(err)?.parse()?;
+        let code_hash = row.get("name").ok_or_else(err)?;
+        if !code_hash.is_empty() {
+            extra.name = u32::from_str_radix(code_hash, 16)?;
+        }
+        instance.locked = row.get("locked").ok_or_else(err)?.parse::<u8>()? != 0;
+        if let Some(s) = row.get("xscale") {
+            extra.xscale = s.parse()?;
+        }
+        if let Some(s) = row.get("yscale") {
+            extra.yscale = s.parse()?;
+        }
+        if let Some(s) = row.get("blend") {
+            extra.blend = s.parse()?;
+        }
+        if let Some(s) = row.get("angle") {
+            extra.angle = s.parse()?;
+        }
+        // use the id from the file, not the generated one if relevant
+        let has_code = if let Some(s) = row.get("has_code") { s.parse::<u8>()? != 0 } else { !code_hash.is_empty() };
+        if let Some(s) = row.get("id") {
+            instance.id = s.parse()?;
+        }
+        if has_code {
+            let mut path = path.join(code_hash);
+            path.set_extension("gml");
+            instance.creation_code = load_gml(&read_file(&path)?);
+        }
+        Ok(instance)
+    };
+    room.instances = instances
+        .par_iter()
+        .map(|line| load_instance(line).map_err(|e| e.at(&inst_path, line_of(&instances_txt, line), 0)))
+        .collect::<Result<_>>()?;
+    Ok(())
+}
+
+fn load_tiles(path: &mut PathBuf, bgs: &HashMap<String, usize>) -> Result<Vec<Tile>> {
+    let mut tiles = Vec::new();
+    path.push("layers.txt");
+    let f = open_file(path)?;
+    path.pop();
+    for line in f.lines() {
+        let line = line?;
+        if line.is_empty() {
+            continue
+        }
+        let depth = line.parse()?;
+        path.push(line);
+        path.set_extension("txt");
+        let room_name =
+            path.parent().and_then(std::path::Path::file_name).map(OsStr::to_string_lossy).unwrap_or_default();
+        let layer_txt = read_file(&path)?;
+        let (columns, layer) = Columns::read(&layer_txt, &TILE_COLUMNS, path)?;
+        let err = || Error::SyntaxError(path.to_path_buf());
+        let load_tile = |tile: &str| -> Result<Tile> {
+            let row = columns.row(tile).ok_or_else(err)?;
+            let mut t = Tile {
+                source_bg: match row.get("background").ok_or_else(err)? {
+                    "" => -1,
+                    bg => *bgs.get(bg).ok_or_else(|| {
+                        Error::AssetNotFound(bg.to_string(), "background", format!("room {room_name} tiles"))
+                    })? as _,
+                },
+                x: row.get("x").ok_or_else(err)?.parse()?,
+                y: row.get("y").ok_or_else(err)?.parse()?,
+                u: row.get("u").ok_or_else(err)?.parse()?,
+                v: row.get("v").ok_or_else(err)?.parse()?,
+                width: row.get("width").ok_or_else(err)?.parse()?,
+                height: row.get("height").ok_or_else(err)?.parse()?,
+                locked: row.get("locked").ok_or_else(err)?.parse::<u8>()? != 0,
+                depth,
+                id: 0,
+                extra: TileExtra::DEFAULT,
+            };
+            if let Some(s) = row.get("xscale") {
+                t.extra.xscale = s.parse()?;
+            }
+            if let Some(s) = row.get("yscale") {
+                t.extra.yscale = s.parse()?;
+            }
+            if let Some(s) = row.get("blend") {
+                t.extra.blend = s.parse()?;
+            }
+            if let Some(s) = row.get("name") {
+                t.extra.name = u32::from_str_radix(s, 16)?;
+            }
+            if let Some(s) = row.get("id") {
+                t.id = s.parse()?;
+            }
+            Ok(t)
+        };
+        let layer_tiles = layer
+            .par_iter()
+            .map(|tile| load_tile(tile).map_err(|e| e.at(path, line_of(&layer_txt, tile), 0)))
+            .collect::<Result<Vec<_>>>()?;
+        tiles.extend(layer_tiles);
+        path.pop();
+    }
+    Ok(tiles)
+}
+
+pub fn load_room(path: &mut PathBuf, asset_maps: &AssetMaps, timestamp: &mut f64) -> Result<Room> {
+    let mut room = Room::default();
+    path.push("room.txt");
+    let room_name =
+        path.parent().and_then(std::path::Path::file_name).map(OsStr::to_string_lossy).unwrap_or_default().into_owned();
+    read_txt(path, |k, v| {
+        match k {
+            k if k.starts_with(EXTRA_KEY_PREFIX) => room.extra_keys.push((k.to_string(), v.to_string())),
+            "caption" => room.caption = v.to_string(),
+            "width" => room.width = v.parse()?,
+            "height" => room.height = v.parse()?,
+            "snap_x" => room.snap_x = v.parse()?,
+            "snap_y" => room.snap_y = v.parse()?,
+            "isometric" => room.isometric = v.parse::<u8>()? != 0,
+            "roomspeed" => room.speed = v.parse()?,
+            "roompersistent" => room.persistent = v.parse::<u8>()? != 0,
+            "bg_color" => room.bg_colour = v.parse()?,
+            "clear_screen" => room.clear_screen = v.parse::<u8>()? != 0,
+            "clear_view" => room.clear_view = v.parse::<u8>()? != 0,
+            // 8 backgrounds/views
+            k if k.chars().last().map(|c| c.is_digit(8)) == Some(true) => {
+                let i = k.chars().last().and_then(|c| c.to_digit(8)).unwrap() as usize;
+                match &k[..k.len() - 1] {
+                    "bg_visible" => room.backgrounds[i].visible_on_start = v.parse::<u8>()? != 0,
+                    "bg_is_foreground" => room.backgrounds[i].is_foreground = v.parse::<u8>()? != 0,
+                    "bg_source" => {
+                        room.backgrounds[i].source_bg = if v.is_empty() {
+                            -1
+                        } else {
+                            *asset_maps.backgrounds.map.get(v).ok_or_else(|| {
+                                Error::AssetNotFound(
+                                    v.to_string(),
+                                    "background",
+                                    format!("room {room_name} backgrounds"),
+                                )
+                            })? as _
+                        }
+                    },
+                    "bg_xoffset" => room.backgrounds[i].xoffset = v.parse()?,
+                    "bg_yoffset" => room.backgrounds[i].yoffset = v.parse()?,
+                    "bg_tile_h" => room.backgrounds[i].tile_horz = v.parse::<u8>()? != 0,
+                    "bg_tile_v" => room.backgrounds[i].tile_vert = v.parse::<u8>()? != 0,
+                    "bg_hspeed" => room.backgrounds[i].hspeed = v.parse()?,
+                    "bg_vspeed" => room.backgrounds[i].vspeed = v.parse()?,
+                    "bg_stretch" => room.backgrounds[i].stretch = v.parse::<u8>()? != 0,
+                    "view_visible" => room.views[i].visible = v.parse::<u8>()? != 0,
+                    "view_xview" => room.views[i].source_x = v.parse()?,
+                    "view_yview" => room.views[i].source_y = v.parse()?,
+                    "view_wview" => room.views[i].source_w = v.parse()?,
+                    "view_hview" => room.views[i].source_h = v.parse()?,
+                    "view_xport" => room.views[i].port_x = v.parse()?,
+                    "view_yport" => room.views[i].port_y = v.parse()?,
+                    "view_wport" => room.views[i].port_w = v.parse()?,
+                    "view_hport" => room.views[i].port_h = v.parse()?,
+                    "view_fol_hbord" => room.views[i].following_hborder = v.parse()?,
+                    "view_fol_vbord" => room.views[i].following_vborder = v.parse()?,
+                    "view_fol_hspeed" => {
+                        room.views[i].following_hspeed =
+                            if let Ok(s) = v.parse() { s } else { v.parse::<u32>()? as i32 };
+                    },
+                    "view_fol_vspeed" => {
+                        room.views[i].following_vspeed =
+                            if let Ok(s) = v.parse() { s } else { v.parse::<u32>()? as i32 };
+                    },
+                    "view_fol_target" => {
+                        room.views[i].following_target = if v.is_empty() {
+                            -1
+                        } else {
+                            *asset_maps.objects.map.get(v).ok_or_else(|| {
+                                Error::AssetNotFound(v.to_string(), "object", format!("room {room_name} view targets"))
+                            })? as _
+                        }
+                    },
+                    _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
+                }
+            },
+            "views_enabled" => room.views_enabled = v.parse::<u8>()? != 0,
+            // views
+            "remember" => room.remember_room_editor_info = v.parse::<u8>()? != 0,
+            "editor_width" => room.editor_width = v.parse()?,
+            "editor_height" => room.editor_height = v.parse()?,
+            "show_grid" => room.show_grid = v.parse::<u8>()? != 0,
+            "show_objects" => room.show_objects = v.parse::<u8>()? != 0,
+            "show_tiles" => room.show_tiles = v.parse::<u8>()? != 0,
+            "show_backgrounds" => room.show_backgrounds = v.parse::<u8>()? != 0,
+            "show_foregrounds" => room.show_foregrounds = v.parse::<u8>()? != 0,
+            "show_views" => room.show_views = v.parse::<u8>()? != 0,
+            "delete_underlying_objects" => room.delete_underlying_objects = v.parse::<u8>()? != 0,
+            "delete_underlying_tiles" => room.delete_underlying_tiles = v.parse::<u8>()? != 0,
+            "tab" => room.tab = v.parse()?, // i still don't know wtf this is
+            "editor_x" => room.x_position_scroll = v.parse()?,
+            "editor_y" => room.y_position_scroll = v.parse()?,
+            "timestamp" => *timestamp = v.parse()?, // legacy
+            _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
+        }
+        Ok(())
+    })?;
+    path.pop();
+    path.push("code.gml");
+    room.creation_code = load_gml(&read_file(&path)?);
+    path.pop();
+    load_instances(&mut room, path, &asset_maps.objects.map)?;
+    room.tiles = load_tiles(path, &asset_maps.backgrounds.map)?;
+    Ok(room)
+}
+
+// instance and tile names are unique across the whole project, tiles included, so this clears any name that's
+// taken already. that gets a new one from name_instances, along with anything that didn't have one
+pub fn reset_taken_names(room: &mut Room, taken: &mut HashSet<u32>) {
+    let instance_names = room.instances.iter_mut().map(|i| &mut i.extra.name);
+    for name in instance_names.chain(room.tiles.iter_mut().map(|t| &mut t.extra.name)) {
+        if *name != 0 && !taken.insert(*name) {
+            *name = 0;
+        }
+    }
+}
+
+fn load_constants(path: &mut PathBuf) -> Result<Vec<(String, String)>> {
+    path.push("constants.txt");
+    let s = read_file(&path)?;
+    let mut constants = Vec::new();
+    for (i, line) in s.lines().enumerate() {
+        // blank lines still take up a slot
+        let mut constant = (String::new(), String::new());
+        decode_line(path, i + 1, line, &mut |name, value| {
+            constant = (name.to_string(), value.to_string());
+            Ok(())
+        })?;
+        constants.push(constant);
+    }
+    path.pop();
+    Ok(constants)
+}
+
+// renamed has the names files go by on disk, if that's different to the index
+fn load_included_files(
+    path: &mut PathBuf,
+    asset_maps: &AssetMaps,
+    renamed: &HashMap<&str, &str>,
+) -> Result<Vec<IncludedFile>> {
+    path.push("datafiles");
+    path.push("index.yyd");
+    let index = read_file(&path)?;
+    path.pop();
+    let mut files = Vec::new();
+    for fname in index.lines() {
+        let fname = renamed.get(fname).copied().unwrap_or(fname);
+        let mut file = IncludedFile { file_name: fname.to_string(), ..Default::default() };
+        path.push(asset_maps.file_name(fname).into_owned() + ".txt");
+        read_txt(path, |k, v| {
+            match k {
+                "store" => file.stored_in_gmk = v.parse::<u8>()? != 0,
+                "free" => file.free_memory = v.parse::<u8>()? != 0,
+                "overwrite" => file.overwrite_file = v.parse::<u8>()? != 0,
+                "remove" => file.remove_at_end = v.parse::<u8>()? != 0,
+                "export" => file.export_setting = v.parse()?,
+                "export_folder" => file.export_custom_folder = v.to_string(),
+                _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
+            }
+            Ok(())
+        })?;
+        path.pop();
+        path.push("include");
+        path.push(&*asset_maps.file_name(fname));
+        file.source_path = path.to_string_lossy().into_owned();
+        file.source_length =
+            std::fs::metadata(&path).map_err(|e| Error::FileIoError(e, path.to_path_buf()))?.len() as _;
+        if file.stored_in_gmk {
+            verify_path(path)?;
+            file.data_exists = true;
+            let data = read_binary(&path)?;
+            file.source_length = data.len() as _;
+            file.data = Some(data);
+        }
+        path.pop();
+        path.pop();
+        files.push(file);
+    }
+    path.pop();
+    Ok(files)
+}
+
+fn load_game_information(path: &mut PathBuf) -> Result<GameInfo> {
+    let mut info = GameInfo::default();
+    path.push("game_information.txt");
+    read_txt(path, |k, v| {
+        match k {
+            "color" => info.colour = v.parse()?,
+            "new_window" => info.new_window = v.parse::<u8>()? != 0,
+            "caption" => info.caption = v.to_string(),
+            "left" => info.left = v.parse()?,
+            "top" => info.top = v.parse()?,
+            "width" => info.width = v.parse()?,
+            "height" => info.height = v.parse()?,
+            "border" => info.border = v.parse::<u8>()? != 0,
+            "resizable" => info.resizable = v.parse::<u8>()? != 0,
+            "window_on_top" => info.window_on_top = v.parse::<u8>()? != 0,
+            "freeze_game" => info.freeze_game = v.parse::<u8>()? != 0,
+            _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
+        }
+        Ok(())
+    })?;
+    path.set_extension("rtf");
+    verify_path(path)?;
+    info.rtf = read_binary(&path)?;
+    path.pop();
+    Ok(info)
+}
+
+// path is the settings folder
+// the IDE needs these before anything else, since they can add action libraries
+pub fn load_extensions(path: &mut PathBuf) -> Result<Vec<String>> {
+    path.push("extensions.txt");
+    let f = open_file(path)?;
+    let extensions = f.lines().collect::<std::io::Result<_>>()?;
+    path.pop();
+    Ok(extensions)
+}
+
+fn load_settings(project: &mut Project, path: &mut PathBuf) -> Result<()> {
+    let settings = &mut project.settings;
+    path.push("settings");
+    project.constants = load_constants(path)?;
+    let mut custom_load_bar = false;
+    let mut bar_bg = false;
+    let mut bar_fg = false;
+    let mut custom_load_bg = false;
+    path.push("settings.txt");
+    settings.vsync_and_force_cpu = 0; // bitwise or is used, so reset this first
+    read_txt(path, |k, v| {
+        match k {
+            "fullscreen" => settings.fullscreen = v.parse::<u8>()? != 0,
+            "interpolate_pixels" => settings.interpolate_pixels = v.parse::<u8>()? != 0,
+            "dont_draw_border" => settings.dont_draw_border = v.parse::<u8>()? != 0,
+            "display_cursor" => settings.display_cursor = v.parse::<u8>()? != 0,
+            "scaling" => settings.scaling = if let Ok(s) = v.parse() { s } else { v.parse::<u32>()? as i32 },
+            "allow_resize" => settings.allow_resize = v.parse::<u8>()? != 0,
+            "window_on_top" => settings.window_on_top = v.parse::<u8>()? != 0,
+            "clear_color" => settings.clear_colour = v.parse()?,
+            "set_resolution" => settings.set_resolution = v.parse::<u8>()? != 0,
+            "color_depth" => settings.colour_depth = v.parse()?,
+            "resolution" => settings.resolution = v.parse()?,
+            "frequency" => settings.frequency = v.parse()?,
+            "dont_show_buttons" => settings.dont_show_buttons = v.parse::<u8>()? != 0,
+            "vsync" => settings.vsync_and_force_cpu |= u32::from(v.parse::<u8>()? != 0),
+            "force_cpu_render" => (), // legacy, and saving didn't even work so
+            "swap_creation_events" => settings.vsync_and_force_cpu |= u32::from(v.parse::<u8>()? != 0) << 31,
+            "disable_screensaver" => settings.disable_screensaver = v.parse::<u8>()? != 0,
+            "f4_fullscreen_toggle" => settings.f4_fullscreen = v.parse::<u8>()? != 0,
+            "f1_help_menu" => settings.f1_help = v.parse::<u8>()? != 0,
+            "esc_close_game" => settings.esc_close = v.parse::<u8>()? != 0,
+            "f5_save_f6_load" => settings.f5_save_f6_load = v.parse::<u8>()? != 0,
+            "f9_screenshot" => settings.f9_screenshot = v.parse::<u8>()? != 0,
+            "treat_close_as_esc" => settings.treat_close_as_esc = v.parse::<u8>()? != 0,
+            "priority" => settings.priority = v.parse()?,
+            "freeze_on_lose_focus" => settings.freeze_on_lose_focus = v.parse::<u8>()? != 0,
+            "custom_loader" => {
+                custom_load_bg = v.parse::<u8>()? != 0;
+                settings.has_custom_load_image = custom_load_bg;
+            },
+            "custom_bar" => {
+                let bar = v.parse()?;
+                custom_load_bar = bar == 2;
+                settings.loading_bar = bar;
+            },
+            "bar_has_bg" => bar_bg = v.parse::<u8>()? != 0,
+            "bar_has_fg" => bar_fg = v.parse::<u8>()? != 0,
+            "transparent" => settings.loading_transparent = v.parse::<u8>()? != 0,
+            "translucency" => settings.loading_translucency = v.parse()?,
+            "scale_progress_bar" => settings.loading_progress_bar_scale = v.parse::<u8>()? != 0,
+            "show_error_messages" => settings.show_error_messages = v.parse::<u8>()? != 0,
+            "log_errors" => settings.log_errors = v.parse::<u8>()? != 0,
+            "always_abort" => settings.always_abort = v.parse::<u8>()? != 0,
+            "zero_uninitialized_vars" => settings.zero_uninitialized_vars = v.parse::<u8>()? != 0,
+            "error_on_uninitialized_args" => settings.error_on_uninitialized_args = v.parse::<u8>()? != 0,
+            _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
+        }
+        Ok(())
+    })?;
+    path.pop();
+    if custom_load_bar {
+        if bar_bg {
+            path.push("back.bmp");
+            verify_path(path)?;
+            settings.loading_background = Some(read_binary(&path)?);
+            path.pop();
+        }
+        if bar_fg {
+            path.push("front.bmp");
+            verify_path(path)?;
+            settings.loading_foreground = Some(read_binary(&path)?);
+            path.pop();
+        }
+    }
+    if custom_load_bg {
+        path.push("loader.bmp");
+        settings.custom_load_image = Some(read_binary(&path)?);
+        path.pop();
+    }
+    path.push("icon.ico");
+    verify_path(path)?;
+    settings.icon = read_binary(&path)?;
+    path.pop();
+    project.extensions = load_extensions(path)?;
+    project.game_info = load_game_information(path)?;
+    path.pop();
+    Ok(())
+}
+
+fn load_index(name: &str, has_any: bool, path: &mut PathBuf) -> Result<Assets> {
+    if !has_any {
+        return Ok(Assets { index: Vec::new(), map: HashMap::new() })
+    }
+    path.push(name);
+    path.push("index.yyd");
+    let text = read_file(&path)?;
+    let index: Vec<_> = text.par_lines().map(String::from).collect();
+    let map = index.par_iter().enumerate().filter_map(|(i, s)| (!s.is_empty()).then(|| (s.to_string(), i))).collect();
+    path.pop();
+    path.pop();
+    Ok(Assets { index, map })
+}
+
+// with the wrong case, everything after this would fail with an io error that says a lot less
+pub fn check_case(project: &std::path::Path) -> Result<()> {
+    let mut diagnostics = Diagnostics::new(true);
+    for m in casing::mismatches(project)?.iter().filter(|m| m.matters(project)) {
+        diagnostics.report(m.error(project))?;
+    }
+    diagnostics.finish()
+}
+
+// errors from loading a project, which can be saved up and reported together at the end
+// instead of stopping at the first one
+pub struct Diagnostics {
+    collect: bool,
+    errors: Vec<Error>,
+}
+
+impl Diagnostics {
+    pub fn new(collect: bool) -> Self {
+        Self { collect, errors: Vec::new() }
+    }
+
+    // gives the error back if loading should stop there
+    pub fn report(&mut self, error: Error) -> Result<()> {
+        if !self.collect {
+            return Err(error)
+        }
+        self.errors.push(error);
+        Ok(())
+    }
+
+    // None if it failed but loading can carry on without it
+    pub fn keep<T>(&mut self, result: Result<T>) -> Result<Option<T>> {
+        match result {
+            Ok(t) => Ok(Some(t)),
+            Err(e) => self.report(e).map(|()| None),
+        }
+    }
+
+    pub fn finish(mut self) -> Result<()> {
+        match self.errors.len() {
+            0 => Ok(()),
+            1 => Err(self.errors.remove(0)),
+            _ => Err(Error::Multiple(self.errors)),
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn load_assets<T: Send>(
+    name: &str,
+    load_asset: fn(&mut PathBuf, &AssetMaps, &mut f64) -> Result<T>,
+    the_assets: &mut AssetList<T>,
+    assets: &Assets,
+    (bar_start, bar_end, progress): (u32, u32, Progress),
+    path: &mut PathBuf,
+    asset_maps: &AssetMaps,
+    timestamps: &HashMap<String, f64>,
+    diagnostics: &mut Diagnostics,
+) -> Result<()> {
+    let kind = name;
+    path.push(name);
+    let names = &assets.index;
+    the_assets.alloc(names.len());
+    // blank object in 0th slot
+    if names.is_empty() && name == "objects" {
+        the_assets.alloc(1);
+    }
+    let count = names.len().max(1) as u32;
+    let loaded = AtomicU32::new(0);
+    // rooms are loaded in parallel here too, instance and tile ids get handed out afterwards
+    let results = (names, &mut the_assets.assets, &mut the_assets.names, &mut the_assets.timestamps)
+        .into_par_iter()
+        .map(|(name, asset, name_p, timestamp)| -> Result<()> {
+            if !name.is_empty() {
+                *name_p = name.clone();
+                let asset_path = path.join(&*asset_maps.file_name(name));
+                *asset = Some(
+                    load_asset(&mut asset_path.clone(), asset_maps, timestamp).map_err(|e| e.in_file(&asset_path))?,
+                );
+                if let Some(&time) = timestamps.get(&format!("{kind}/{name}")) {
+                    *timestamp = time;
+                } else if *timestamp == 0.0 {
+                    *timestamp = modified_time(&asset_path);
+                }
+            }
+            let loaded = loaded.fetch_add(1, Ordering::Relaxed) + 1;
+            progress(bar_start + loaded * (bar_end - bar_start) / count);
+            Ok(())
+        })
+        .collect::<Vec<_>>();
+    path.pop();
+    results.into_iter().try_for_each(|r| diagnostics.keep(r).map(drop))
+}
+
+// for assets with no saved timestamp, go by when their files were last changed
+pub fn modified_time(path: &std::path::Path) -> f64 {
+    let mtime = |p: &std::path::Path| p.metadata().and_then(|m| m.modified()).ok();
+    let newest = if path.is_dir() {
+        std::fs::read_dir(path).into_iter().flatten().filter_map(|e| mtime(&e.ok()?.path())).max()
+    } else {
+        ["txt", "gml"].iter().filter_map(|ext| mtime(&path.with_extension(ext))).max()
+    };
+    newest.map_or(0.0, delphi_time)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn load_asset_maps<'a>(
+    path: &mut PathBuf,
+    libs: &'a ActionLibraries,
+    has_triggers: bool,
+    has_sprites: bool,
+    has_sounds: bool,
+    has_backgrounds: bool,
+    has_paths: bool,
+    has_scripts: bool,
+    has_objects: bool,
+    has_fonts: bool,
+    has_timelines: bool,
+) -> Result<AssetMaps<'a>> {
+    Ok(AssetMaps {
+        triggers: load_index("triggers", has_triggers, path)?,
+        sprites: load_index("sprites", has_sprites, path)?,
+        sounds: load_index("sounds", has_sounds, path)?,
+        backgrounds: load_index("backgrounds", has_backgrounds, path)?,
+        paths: load_index("paths", has_paths, path)?,
+        scripts: load_index("scripts", has_scripts, path)?,
+        objects: load_index("objects", has_objects, path)?,
+        rooms: load_index("rooms", true, path)?,
+        fonts: load_index("fonts", has_fonts, path)?,
+        timelines: load_index("timelines", has_timelines, path)?,
+        libs,
+        version: GM82_VERSION,
+    })
+}
+
+// gives out instance and tile ids, room by room with each room's instances before its tiles
+// with preserve_ids, the saved ones (anything but 0) are checked to all be different and new ones go after them
+// the IDE gives out ids this way too, so both come up with the same ones
+pub fn assign_ids(
+    rooms: Vec<(&str, Vec<&mut usize>, Vec<&mut usize>)>,
+    preserve_ids: bool,
+    last_instance_id: &mut usize,
+    last_tile_id: &mut usize,
+    diagnostics: &mut Diagnostics,
+) -> Result<()> {
+    if preserve_ids {
+        let mut instance_ids = HashSet::new();
+        let mut tile_ids = HashSet::new();
+        for (name, instances, tiles) in &rooms {
+            for id in instances.iter().map(|id| **id).filter(|&id| id != 0) {
+                if !instance_ids.insert(id) {
+                    diagnostics.report(Error::DuplicateId("instance", id, name.to_string()))?;
+                }
+            }
+            for id in tiles.iter().map(|id| **id).filter(|&id| id != 0) {
+                if !tile_ids.insert(id) {
+                    diagnostics.report(Error::DuplicateId("tile", id, name.to_string()))?;
+                }
+            }
+        }
+        *last_instance_id = instance_ids.into_iter().fold(*last_instance_id, usize::max);
+        *last_tile_id = tile_ids.into_iter().fold(*last_tile_id, usize::max);
+    }
+    for (_, instances, tiles) in rooms {
+        for id in instances.into_iter().filter(|id| !preserve_ids || **id == 0) {
+            *last_instance_id += 1;
+            *id = *last_instance_id;
+        }
+        for id in tiles.into_iter().filter(|id| !preserve_ids || **id == 0) {
+            *last_tile_id += 1;
+            *id = *last_tile_id;
+        }
+    }
+    Ok(())
+}
+
+// the gm82_version a project was saved with, if it says
+pub fn read_version(path: &std::path::Path) -> Result<Option<u8>> {
+    let mut version = None;
+    read_txt(path, |k, v| {
+        if k == "gm82_version" {
+            version = Some(v.parse()?);
+        }
+        Ok(())
+    })?;
+    Ok(version)
+}
+
+// with all_errors, keeps going after something fails to load and reports every error at the end
+pub fn load_gmk(path: PathBuf, libs: &ActionLibraries, all_errors: bool) -> Result<Project> {
+    load_gmk_with_progress(path, libs, all_errors, &|_| ())
+}
+
+// puts text from before version 8 the way it's escaped now
+fn upgrade_text(project: &mut Project) {
+    fn tree(nodes: &mut [TreeNode]) {
+        for node in nodes {
+            if let TreeNode::Group(name, children) = node {
+                upgrade(name);
+                tree(children);
+            }
+        }
+    }
+    fn list<T>(list: &mut AssetList<T>, mut f: impl FnMut(&mut T)) {
+        list.names.iter_mut().for_each(upgrade);
+        list.assets.iter_mut().flatten().for_each(&mut f);
+        tree(&mut list.tree);
+    }
+    fn event(event: &mut Event) {
+        event.actions.iter_mut().flat_map(|a| &mut a.param_strings).for_each(upgrade);
+    }
+    let settings = &mut project.settings;
+    for s in [
+        &mut settings.info_author,
+        &mut settings.info_version,
+        &mut settings.info_information,
+        &mut settings.exe_company,
+        &mut settings.exe_product,
+        &mut settings.exe_copyright,
+        &mut settings.exe_description,
+        &mut project.game_info.caption,
+    ] {
+        upgrade(s);
+    }
+    project.constants.iter_mut().for_each(|(name, value)| [name, value].into_iter().for_each(upgrade));
+    project.extensions.iter_mut().for_each(upgrade);
+    for trigger in project.triggers.iter_mut().flatten() {
+        [&mut trigger.name, &mut trigger.condition, &mut trigger.constant_name].into_iter().for_each(upgrade);
+    }
+    list(&mut project.sounds, |s| [&mut s.extension, &mut s.source].into_iter().for_each(upgrade));
+    list(&mut project.sprites, |_| ());
+    list(&mut project.backgrounds, |_| ());
+    list(&mut project.paths, |_| ());
+    list(&mut project.scripts, |s| upgrade(&mut s.source));
+    list(&mut project.fonts, |f| upgrade(&mut f.sys_name));
+    list(&mut project.timelines, |t| t.moments.iter_mut().for_each(|(_, e)| event(e)));
+    list(&mut project.objects, |o| o.events.iter_mut().flat_map(|m| m.values_mut()).for_each(event));
+    list(&mut project.rooms, |r| {
+        upgrade(&mut r.caption);
+        upgrade(&mut r.creation_code);
+        r.instances.iter_mut().for_each(|i| upgrade(&mut i.creation_code));
+    });
+    for file in &mut project.included_files {
+        upgrade(&mut file.file_name);
+        upgrade(&mut file.export_custom_folder);
+    }
+}
+
+pub fn load_gmk_with_progress(
+    path: PathBuf,
+    libs: &ActionLibraries,
+    all_errors: bool,
+    progress: Progress,
+) -> Result<Project> {
+    load_project(path, libs, all_errors, progress, false)
+}
+
+// for when the files' case doesn't match the index, gives the assets the names their files have instead of
+// failing. saving it afterwards puts the index right
+pub fn load_gmk_with_names_on_disk(path: PathBuf, libs: &ActionLibraries) -> Result<Project> {
+    load_project(path, libs, false, &|_| (), true)
+}
+
+fn load_project(
+    mut path: PathBuf,
+    libs: &ActionLibraries,
+    all_errors: bool,
+    progress: Progress,
+    names_on_disk: bool,
+) -> Result<Project> {
+    if transaction::interrupted(&path) {
+        return Err(Error::InterruptedSave(path))
+    }
+    let renamed = if names_on_disk {
+        casing::names_on_disk(&path, &casing::mismatches(&path)?)?
+    } else {
+        check_case(&path)?;
+        HashMap::new()
+    };
+    let mut project = Project::default();
+    let mut has_backgrounds = true;
+    let mut has_datafiles = true;
+    let mut has_fonts = true;
+    let mut has_objects = true;
+    let mut has_paths = true;
+    let mut has_scripts = true;
+    let mut has_sounds = true;
+    let mut has_sprites = true;
+    let mut has_timelines = true;
+    let mut has_triggers = true;
+    let mut version = 0;
+    let settings = &mut project.settings;
+    read_txt(&path, |k, v| {
+        match k {
+            "gm82_version" => {
+                version = v.parse()?;
+                if version > GM82_VERSION {
+                    return Err(Error::OldGM82)
+                }
+            },
+            "gameid" => project.game_id = v.parse()?,
+            "info_author" => settings.info_author = v.to_string(),
+            "info_version" => settings.info_version = v.to_string(),
+            "info_timestamp" => settings.info_timestamp = v.parse()?, // legacy
+            "info_information" => settings.info_information = undelimit(v),
+            "exe_company" => settings.exe_company = v.to_string(),
+            "exe_product" => settings.exe_product = v.to_string(),
+            "exe_copyright" => settings.exe_copyright = v.to_string(),
+            "exe_description" => settings.exe_description = v.to_string(),
+            "exe_version" => {
+                let err = || Error::InvalidVersion(v.to_string());
+                let mut iter = v.split('.');
+                settings.version_major = iter.next().ok_or_else(err)?.parse()?;
+                settings.version_minor = iter.next().ok_or_else(err)?.parse()?;
+                settings.version_release = iter.next().ok_or_else(err)?.parse()?;
+                settings.version_build = iter.next().ok_or_else(err)?.parse()?;
+                if iter.next().is_some() {
+                    return Err(Error::InvalidVersion(v.to_string()))
+                }
+            },
+            "has_backgrounds" => has_backgrounds = v.parse::<u8>()? != 0,
+            "has_datafiles" => has_datafiles = v.parse::<u8>()? != 0,
+            "has_fonts" => has_fonts = v.parse::<u8>()? != 0,
+            "has_objects" => has_objects = v.parse::<u8>()? != 0,
+            "has_paths" => has_paths = v.parse::<u8>()? != 0,
+            "has_scripts" => has_scripts = v.parse::<u8>()? != 0,
+            "has_sounds" => has_sounds = v.parse::<u8>()? != 0,
+            "has_sprites" => has_sprites = v.parse::<u8>()? != 0,
+            "has_timelines" => has_timelines = v.parse::<u8>()? != 0,
+            "has_triggers" => has_triggers = v.parse::<u8>()? != 0,
+            "preserve_ids" => project.preserve_ids = v.parse::<u8>()? != 0,
+            "png_effort" => {
+                project.png_effort = PngEffort::from_u8(v.parse()?)
+                    .ok_or_else(|| Error::Other(format!("png_effort should be 0, 1 or 2, not {}", v)))?
+            },
+            "last_instance_id" => project.last_instance_id = v.parse()?,
+            "last_tile_id" => project.last_tile_id = v.parse()?,
+            _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
+        }
+        Ok(())
+    })?;
+    let timestamps = transaction::read_timestamps(&path);
+    path.pop();
+    progress(5);
+    let mut diagnostics = Diagnostics::new(all_errors);
+    diagnostics.keep(load_settings(&mut project, &mut path.clone()))?;
+    progress(10);
+    let mut asset_maps = load_asset_maps(
+        &mut path,
+        libs,
+        has_triggers,
+        has_sprites,
+        has_sounds,
+        has_backgrounds,
+        has_paths,
+        has_scripts,
+        has_objects,
+        has_fonts,
+        has_timelines,
+    )?;
+    asset_maps.version = version;
+    for ((dir, name), found) in &renamed {
+        if let Some(assets) = asset_maps.assets_mut(dir) {
+            assets.rename(name, found);
+        }
+    }
+    project.triggers = load_triggers(&asset_maps, &mut path, &mut diagnostics)?;
+    progress(15);
+    load_assets(
+        "sounds",
+        load_sound,
+        &mut project.sounds,
+        &asset_maps.sounds,
+        (15, 30, progress),
+        &mut path,
+        &asset_maps,
+        &timestamps,
+        &mut diagnostics,
+    )?;
+    load_assets(
+        "sprites",
+        load_sprite,
+        &mut project.sprites,
+        &asset_maps.sprites,
+        (30, 50, progress),
+        &mut path,
+        &asset_maps,
+        &timestamps,
+        &mut diagnostics,
+    )?;
+    load_assets(
+        "backgrounds",
+        load_background,
+        &mut project.backgrounds,
+        &asset_maps.backgrounds,
+        (50, 60, progress),
+        &mut path,
+        &asset_maps,
+        &timestamps,
+        &mut diagnostics,
+    )?;
+    load_assets(
+        "paths",
+        load_path,
+        &mut project.paths,
+        &asset_maps.paths,
+        (60, 65, progress),
+        &mut path,
+        &asset_maps,
+        &timestamps,
+        &mut diagnostics,
+    )?;
+    load_assets(
+        "scripts",
+        load_script,
+        &mut project.scripts,
+        &asset_maps.scripts,
+        (65, 70, progress),
+        &mut path,
+        &asset_maps,
+        &timestamps,
+        &mut diagnostics,
+    )?;
+    load_assets(
+        "fonts",
+        load_font,
+        &mut project.fonts,
+        &asset_maps.fonts,
+        (70, 75, progress),
+        &mut path,
+        &asset_maps,
+        &timestamps,
+        &mut diagnostics,
+    )?;
+    load_assets(
+        "timelines",
+        load_timeline,
+        &mut project.timelines,
+        &asset_maps.timelines,
+        (75, 80, progress),
+        &mut path,
+        &asset_maps,
+        &timestamps,
+        &mut diagnostics,
+    )?;
+    load_assets(
+        "objects",
+        load_object,
+        &mut project.objects,
+        &asset_maps.objects,
+        (80, 85, progress),
+        &mut path,
+        &asset_maps,
+        &timestamps,
+        &mut diagnostics,
+    )?;
+    load_assets(
+        "rooms",
+        load_room,
+        &mut project.rooms,
+        &asset_maps.rooms,
+        (85, 95, progress),
+        &mut path,
+        &asset_maps,
+        &timestamps,
+        &mut diagnostics,
+    )?;
+    let rooms = project.rooms.assets.iter_mut().zip(&project.rooms.names).filter_map(|(room, name)| {
+        let room = room.as_mut()?;
+        Some((
+            name.as_str(),
+            room.instances.iter_mut().map(|i| &mut i.id).collect(),
+            room.tiles.iter_mut().map(|t| &mut t.id).collect(),
+        ))
+    });
+    assign_ids(
+        rooms.collect(),
+        project.preserve_ids,
+        &mut project.last_instance_id,
+        &mut project.last_tile_id,
+        &mut diagnostics,
+    )?;
+    // rooms are in index order, so the first of two clashing names keeps it
+    let mut taken = HashSet::new();
+    project.rooms.assets.iter_mut().flatten().for_each(|room| reset_taken_names(room, &mut taken));
+    name_instances(&mut project);
+    if has_datafiles {
+        let renamed = renamed
+            .iter()
+            .filter(|((dir, _), _)| *dir == "datafiles")
+            .map(|((_, name), found)| (name.as_str(), found.as_str()))
+            .collect();
+        project.included_files =
+            diagnostics.keep(load_included_files(&mut path.clone(), &asset_maps, &renamed))?.unwrap_or_default();
+    }
+
+    let mut read_tree = |name, map| -> Result<Vec<TreeNode>> {
+        Ok(diagnostics.keep(read_resource_tree(name, map, &mut path.clone()))?.unwrap_or_default())
+    };
+    project.sounds.tree = read_tree("sounds", &asset_maps.sounds.map)?;
+    project.sprites.tree = read_tree("sprites", &asset_maps.sprites.map)?;
+    project.backgrounds.tree = read_tree("backgrounds", &asset_maps.backgrounds.map)?;
+    project.paths.tree = read_tree("paths", &asset_maps.paths.map)?;
+    project.scripts.tree = read_tree("scripts", &asset_maps.scripts.map)?;
+    project.fonts.tree = read_tree("fonts", &asset_maps.fonts.map)?;
+    project.timelines.tree = read_tree("timelines", &asset_maps.timelines.map)?;
+    project.objects.tree = read_tree("objects", &asset_maps.objects.map)?;
+    project.rooms.tree = read_tree("rooms", &asset_maps.rooms.map)?;
+    if version < 8 {
+        upgrade_text(&mut project);
+    }
+    diagnostics.finish()?;
+    progress(100);
+
+    Ok(project)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_ids_go_after_saved_ones() {
+        let (mut a, mut b) = ([0, 7, 0], [0, 3]);
+        let (mut ta, mut tb) = ([2, 0], [0]);
+        let rooms = vec![
+            ("a", a.iter_mut().collect(), ta.iter_mut().collect()),
+            ("b", b.iter_mut().collect(), tb.iter_mut().collect()),
+        ];
+        let (mut last_instance, mut last_tile) = (1, 5);
+        assign_ids(rooms, true, &mut last_instance, &mut last_tile, &mut Diagnostics::new(false)).unwrap();
+        assert_eq!((a, b, ta, tb), ([8, 7, 9], [10, 3], [2, 6], [7]));
+        assert_eq!((last_instance, last_tile), (10, 7));
+    }
+
+    #[test]
+    fn ids_are_all_new_without_preserve_ids() {
+        let (mut a, mut ta) = ([0, 7], [2]);
+        let rooms = vec![("a", a.iter_mut().collect(), ta.iter_mut().collect())];
+        let (mut last_instance, mut last_tile) = (100, 0);
+        assign_ids(rooms, false, &mut last_instance, &mut last_tile, &mut Diagnostics::new(false)).unwrap();
+        assert_eq!((a, ta), ([101, 102], [1]));
+    }
+
+    #[test]
+    fn saved_ids_must_be_different() {
+        let (mut a, mut b) = ([4], [4]);
+        let rooms = vec![("a", a.iter_mut().collect(), Vec::new()), ("b", b.iter_mut().collect(), Vec::new())];
+        let result = assign_ids(rooms, true, &mut 0, &mut 0, &mut Diagnostics::new(false));
+        assert!(matches!(result, Err(Error::DuplicateId("instance", 4, room)) if room == "b"));
+    }
+}
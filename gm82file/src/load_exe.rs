@@ -0,0 +1,955 @@
+use crate::{
+    asset::*,
+    library::decode_ansi,
+    save_exe::{GMK_MAGIC, GMK_VERSION},
+    Error, Result,
+};
+use byteorder::{ReadBytesExt, LE};
+use flate2::read::ZlibDecoder;
+use rayon::prelude::*;
+use std::{
+    collections::HashMap,
+    io::{self, Read},
+};
+
+// strings are in the system code page before 8.1, and utf-8 from then on
+#[derive(Clone, Copy)]
+pub struct Strings {
+    pub unicode: bool,
+}
+
+impl Strings {
+    pub fn for_version(version: u32) -> Self {
+        Self { unicode: version >= 810 }
+    }
+}
+
+fn bad_data(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+pub fn read_bool(f: &mut &[u8]) -> io::Result<bool> {
+    Ok(f.read_u32::<LE>()? != 0)
+}
+
+pub fn read_buffer<'a>(f: &mut &'a [u8]) -> io::Result<&'a [u8]> {
+    let len = f.read_u32::<LE>()? as usize;
+    if len > f.len() {
+        return Err(io::ErrorKind::UnexpectedEof.into())
+    }
+    let (buf, rest) = f.split_at(len);
+    *f = rest;
+    Ok(buf)
+}
+
+pub fn read_string(f: &mut &[u8], strings: Strings) -> io::Result<String> {
+    let buf = read_buffer(f)?;
+    Ok(if strings.unicode { String::from_utf8_lossy(buf).into_owned() } else { decode_ansi(buf) })
+}
+
+pub fn read_zlib(f: &mut &[u8]) -> io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    ZlibDecoder::new(read_buffer(f)?).read_to_end(&mut out)?;
+    Ok(out)
+}
+
+fn expect_version(f: &mut &[u8], what: &str, versions: &[u32]) -> io::Result<u32> {
+    let version = f.read_u32::<LE>()?;
+    if !versions.contains(&version) {
+        return Err(bad_data(format!("unsupported {} version {}", what, version)))
+    }
+    Ok(version)
+}
+
+pub trait GetAssetList: Sized + Send {
+    fn get_asset_list(project: &mut Project) -> &mut AssetList<Self>;
+    fn load(exe: bool, strings: Strings, f: &mut &[u8]) -> io::Result<Self>;
+    fn read_additional(_project: &mut Project, _f: &mut &[u8]) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn load_frame(f: &mut &[u8]) -> io::Result<Frame> {
+    expect_version(f, "frame", &[800])?;
+    let width = f.read_u32::<LE>()?;
+    let height = f.read_u32::<LE>()?;
+    let data = if width != 0 && height != 0 { read_buffer(f)?.to_vec() } else { Vec::new() };
+    if data.len() != width as usize * height as usize * 4 {
+        return Err(bad_data("frame size doesn't match its dimensions"))
+    }
+    Ok(Frame { width, height, data })
+}
+
+fn skip_mask(f: &mut &[u8]) -> io::Result<()> {
+    expect_version(f, "collision mask", &[800])?;
+    let width = f.read_u32::<LE>()? as usize;
+    let height = f.read_u32::<LE>()? as usize;
+    let len = (4 + width * height) * 4;
+    if len > f.len() {
+        return Err(io::ErrorKind::UnexpectedEof.into())
+    }
+    *f = &f[len..];
+    Ok(())
+}
+
+impl GetAssetList for Sprite {
+    fn get_asset_list(project: &mut Project) -> &mut AssetList<Self> {
+        &mut project.sprites
+    }
+
+    fn load(exe: bool, _strings: Strings, f: &mut &[u8]) -> io::Result<Self> {
+        expect_version(f, "sprite", &[800])?;
+        let mut sprite = Sprite { origin_x: f.read_i32::<LE>()?, origin_y: f.read_i32::<LE>()?, ..Default::default() };
+        let frame_count = f.read_u32::<LE>()?;
+        for _ in 0..frame_count {
+            sprite.frames.push(load_frame(f)?);
+        }
+        if !exe {
+            sprite.collision_shape = f.read_u32::<LE>()?;
+            sprite.alpha_tolerance = f.read_u32::<LE>()?;
+        }
+        sprite.per_frame_colliders = read_bool(f)?;
+        if !exe {
+            sprite.bbox_type = f.read_u32::<LE>()?;
+            sprite.bbox_left = f.read_i32::<LE>()?;
+            sprite.bbox_right = f.read_i32::<LE>()?;
+            sprite.bbox_bottom = f.read_i32::<LE>()?;
+            sprite.bbox_top = f.read_i32::<LE>()?;
+        } else if frame_count > 0 {
+            // masks are generated from the frames, nothing to keep here
+            let masks = if sprite.per_frame_colliders { frame_count } else { 1 };
+            for _ in 0..masks {
+                skip_mask(f)?;
+            }
+        }
+        Ok(sprite)
+    }
+}
+
+impl GetAssetList for Background {
+    fn get_asset_list(project: &mut Project) -> &mut AssetList<Self> {
+        &mut project.backgrounds
+    }
+
+    fn load(exe: bool, _strings: Strings, f: &mut &[u8]) -> io::Result<Self> {
+        expect_version(f, "background", &[710])?;
+        let mut back = Background::default();
+        if !exe {
+            back.is_tileset = read_bool(f)?;
+            back.tile_width = f.read_u32::<LE>()?;
+            back.tile_height = f.read_u32::<LE>()?;
+            back.h_offset = f.read_u32::<LE>()?;
+            back.v_offset = f.read_u32::<LE>()?;
+            back.h_sep = f.read_u32::<LE>()?;
+            back.v_sep = f.read_u32::<LE>()?;
+        }
+        back.frame = load_frame(f)?;
+        Ok(back)
+    }
+}
+
+impl GetAssetList for Path {
+    fn get_asset_list(project: &mut Project) -> &mut AssetList<Self> {
+        &mut project.paths
+    }
+
+    fn load(exe: bool, _strings: Strings, f: &mut &[u8]) -> io::Result<Self> {
+        expect_version(f, "path", &[530])?;
+        let mut path = Path {
+            connection: f.read_u32::<LE>()?,
+            closed: read_bool(f)?,
+            precision: f.read_u32::<LE>()?,
+            path_editor_room_background: -1,
+            ..Default::default()
+        };
+        if !exe {
+            path.path_editor_room_background = f.read_i32::<LE>()?;
+            path.snap_x = f.read_u32::<LE>()?;
+            path.snap_y = f.read_u32::<LE>()?;
+        }
+        let point_count = f.read_u32::<LE>()?;
+        for _ in 0..point_count {
+            path.points.push(PathPoint { x: f.read_f64::<LE>()?, y: f.read_f64::<LE>()?, speed: f.read_f64::<LE>()? });
+        }
+        Ok(path)
+    }
+}
+
+impl GetAssetList for Script {
+    fn get_asset_list(project: &mut Project) -> &mut AssetList<Self> {
+        &mut project.scripts
+    }
+
+    fn load(_exe: bool, strings: Strings, f: &mut &[u8]) -> io::Result<Self> {
+        expect_version(f, "script", &[800])?;
+        Ok(Script { source: read_string(f, strings)? })
+    }
+}
+
+fn read_event(strings: Strings, f: &mut &[u8]) -> io::Result<Event> {
+    expect_version(f, "event", &[400])?;
+    let action_count = f.read_u32::<LE>()?;
+    let mut event = Event::default();
+    for _ in 0..action_count {
+        expect_version(f, "action", &[440])?;
+        let mut action = Action {
+            lib_id: f.read_u32::<LE>()?,
+            id: f.read_u32::<LE>()?,
+            action_kind: f.read_u32::<LE>()?,
+            can_be_relative: read_bool(f)?,
+            is_condition: read_bool(f)?,
+            applies_to_something: read_bool(f)?,
+            execution_type: f.read_u32::<LE>()?,
+            fn_name: read_string(f, strings)?,
+            fn_code: read_string(f, strings)?,
+            param_count: f.read_u32::<LE>()?,
+            ..Default::default()
+        };
+        let type_count = f.read_u32::<LE>()? as usize;
+        for i in 0..type_count {
+            let ty = f.read_u32::<LE>()?;
+            if let Some(slot) = action.param_types.get_mut(i) {
+                *slot = ty;
+            }
+        }
+        action.applies_to = f.read_i32::<LE>()?;
+        action.is_relative = read_bool(f)?;
+        let param_count = f.read_u32::<LE>()? as usize;
+        for i in 0..param_count {
+            let param = read_string(f, strings)?;
+            if let Some(slot) = action.param_strings.get_mut(i) {
+                *slot = param;
+            }
+        }
+        action.invert_condition = read_bool(f)?;
+        event.actions.push(action);
+    }
+    Ok(event)
+}
+
+impl GetAssetList for Object {
+    fn get_asset_list(project: &mut Project) -> &mut AssetList<Self> {
+        &mut project.objects
+    }
+
+    fn load(_exe: bool, strings: Strings, f: &mut &[u8]) -> io::Result<Self> {
+        expect_version(f, "object", &[430])?;
+        let mut obj = Object {
+            sprite_index: f.read_i32::<LE>()?,
+            solid: read_bool(f)?,
+            visible: read_bool(f)?,
+            depth: f.read_i32::<LE>()?,
+            persistent: read_bool(f)?,
+            parent_index: f.read_i32::<LE>()?,
+            mask_index: f.read_i32::<LE>()?,
+            ..Default::default()
+        };
+        let max_event = f.read_u32::<LE>()? as usize;
+        for ev_type in 0..=max_event {
+            loop {
+                let ev_numb = f.read_i32::<LE>()?;
+                if ev_numb < 0 {
+                    break
+                }
+                let event = read_event(strings, f)?;
+                if let Some(group) = obj.events.get_mut(ev_type) {
+                    group.insert(ev_numb as usize, event);
+                }
+            }
+        }
+        Ok(obj)
+    }
+}
+
+impl GetAssetList for Timeline {
+    fn get_asset_list(project: &mut Project) -> &mut AssetList<Self> {
+        &mut project.timelines
+    }
+
+    fn load(_exe: bool, strings: Strings, f: &mut &[u8]) -> io::Result<Self> {
+        expect_version(f, "timeline", &[500])?;
+        let moment_count = f.read_u32::<LE>()?;
+        let mut tl = Timeline::default();
+        for _ in 0..moment_count {
+            let time = f.read_u32::<LE>()?;
+            tl.moments.push((time, read_event(strings, f)?));
+        }
+        Ok(tl)
+    }
+}
+
+impl GetAssetList for Sound {
+    fn get_asset_list(project: &mut Project) -> &mut AssetList<Self> {
+        &mut project.sounds
+    }
+
+    fn load(_exe: bool, strings: Strings, f: &mut &[u8]) -> io::Result<Self> {
+        expect_version(f, "sound", &[800])?;
+        let mut sound = Sound {
+            kind: f.read_u32::<LE>()?,
+            extension: read_string(f, strings)?,
+            source: read_string(f, strings)?,
+            ..Default::default()
+        };
+        if read_bool(f)? {
+            sound.data = Some(read_buffer(f)?.to_vec());
+        }
+        sound.effects = f.read_u32::<LE>()?;
+        sound.volume = f.read_f64::<LE>()?;
+        sound.pan = f.read_f64::<LE>()?;
+        sound.preload = read_bool(f)?;
+        Ok(sound)
+    }
+}
+
+impl GetAssetList for Font {
+    fn get_asset_list(project: &mut Project) -> &mut AssetList<Self> {
+        &mut project.fonts
+    }
+
+    fn load(exe: bool, strings: Strings, f: &mut &[u8]) -> io::Result<Self> {
+        expect_version(f, "font", &[800])?;
+        let mut font = Font {
+            sys_name: read_string(f, strings)?,
+            size: f.read_u32::<LE>()?,
+            bold: read_bool(f)?,
+            italic: read_bool(f)?,
+            ..Default::default()
+        };
+        // 8.1 packs the charset and aa level in with the range start
+        let packed = f.read_u32::<LE>()?;
+        font.range_start = packed & 0xffff;
+        font.charset = (packed >> 16) & 0xff;
+        font.aa_level = (packed >> 24).saturating_sub(1);
+        if font.charset == 0 {
+            font.charset = 1;
+        }
+        font.range_end = f.read_u32::<LE>()?;
+        if exe {
+            // pre-rendered glyphs, the IDE renders these again
+            let len = 256 * 6 * 4 + 8;
+            if len > f.len() {
+                return Err(io::ErrorKind::UnexpectedEof.into())
+            }
+            *f = &f[len..];
+            read_buffer(f)?;
+        }
+        Ok(font)
+    }
+}
+
+impl GetAssetList for Room {
+    fn get_asset_list(project: &mut Project) -> &mut AssetList<Self> {
+        &mut project.rooms
+    }
+
+    fn load(exe: bool, strings: Strings, f: &mut &[u8]) -> io::Result<Self> {
+        let version = expect_version(f, "room", &[541, 811])?;
+        let extra = version == 811;
+        let mut room = Room { caption: read_string(f, strings)?, ..Default::default() };
+        room.width = f.read_u32::<LE>()?;
+        room.height = f.read_u32::<LE>()?;
+        if !exe {
+            room.snap_x = f.read_u32::<LE>()?;
+            room.snap_y = f.read_u32::<LE>()?;
+            room.isometric = read_bool(f)?;
+        }
+        room.speed = f.read_u32::<LE>()?;
+        room.persistent = read_bool(f)?;
+        room.bg_colour = f.read_i32::<LE>()?;
+        let clear = f.read_u32::<LE>()?;
+        room.clear_screen = clear & 1 != 0;
+        room.clear_view = clear & 2 != 0;
+        room.creation_code = read_string(f, strings)?;
+        if f.read_u32::<LE>()? != 8 {
+            return Err(bad_data("rooms should have 8 backgrounds"))
+        }
+        for b in &mut room.backgrounds {
+            b.visible_on_start = read_bool(f)?;
+            b.is_foreground = read_bool(f)?;
+            b.source_bg = f.read_i32::<LE>()?;
+            b.xoffset = f.read_i32::<LE>()?;
+            b.yoffset = f.read_i32::<LE>()?;
+            b.tile_horz = read_bool(f)?;
+            b.tile_vert = read_bool(f)?;
+            b.hspeed = f.read_i32::<LE>()?;
+            b.vspeed = f.read_i32::<LE>()?;
+            b.stretch = read_bool(f)?;
+        }
+        room.views_enabled = read_bool(f)?;
+        if f.read_u32::<LE>()? != 8 {
+            return Err(bad_data("rooms should have 8 views"))
+        }
+        for v in &mut room.views {
+            v.visible = read_bool(f)?;
+            v.source_x = f.read_i32::<LE>()?;
+            v.source_y = f.read_i32::<LE>()?;
+            v.source_w = f.read_u32::<LE>()?;
+            v.source_h = f.read_u32::<LE>()?;
+            v.port_x = f.read_i32::<LE>()?;
+            v.port_y = f.read_i32::<LE>()?;
+            v.port_w = f.read_u32::<LE>()?;
+            v.port_h = f.read_u32::<LE>()?;
+            v.following_hborder = f.read_i32::<LE>()?;
+            v.following_vborder = f.read_i32::<LE>()?;
+            v.following_hspeed = f.read_i32::<LE>()?;
+            v.following_vspeed = f.read_i32::<LE>()?;
+            v.following_target = f.read_i32::<LE>()?;
+        }
+        let instance_count = f.read_u32::<LE>()?;
+        for _ in 0..instance_count {
+            let mut i = Instance {
+                x: f.read_i32::<LE>()?,
+                y: f.read_i32::<LE>()?,
+                object: f.read_i32::<LE>()?,
+                id: f.read_u32::<LE>()? as usize,
+                creation_code: read_string(f, strings)?,
+                ..Default::default()
+            };
+            if !exe {
+                i.locked = read_bool(f)?;
+            } else if extra {
+                i.extra.xscale = f.read_f64::<LE>()?;
+                i.extra.yscale = f.read_f64::<LE>()?;
+                i.extra.blend = f.read_u32::<LE>()?;
+                i.extra.angle = f.read_f64::<LE>()?;
+            }
+            room.instances.push(i);
+        }
+        let tile_count = f.read_u32::<LE>()?;
+        for _ in 0..tile_count {
+            let mut t = Tile {
+                x: f.read_i32::<LE>()?,
+                y: f.read_i32::<LE>()?,
+                source_bg: f.read_i32::<LE>()?,
+                u: f.read_i32::<LE>()?,
+                v: f.read_i32::<LE>()?,
+                width: f.read_i32::<LE>()?,
+                height: f.read_i32::<LE>()?,
+                depth: f.read_i32::<LE>()?,
+                id: f.read_u32::<LE>()? as usize,
+                ..Default::default()
+            };
+            if !exe {
+                t.locked = read_bool(f)?;
+            } else if extra {
+                t.extra.xscale = f.read_f64::<LE>()?;
+                t.extra.yscale = f.read_f64::<LE>()?;
+                t.extra.blend = f.read_u32::<LE>()?;
+            }
+            room.tiles.push(t);
+        }
+        if !exe {
+            room.remember_room_editor_info = read_bool(f)?;
+            room.editor_width = f.read_u32::<LE>()?;
+            room.editor_height = f.read_u32::<LE>()?;
+            room.show_grid = read_bool(f)?;
+            room.show_objects = read_bool(f)?;
+            room.show_tiles = read_bool(f)?;
+            room.show_backgrounds = read_bool(f)?;
+            room.show_foregrounds = read_bool(f)?;
+            room.show_views = read_bool(f)?;
+            room.delete_underlying_objects = read_bool(f)?;
+            room.delete_underlying_tiles = read_bool(f)?;
+            room.tab = f.read_u32::<LE>()?;
+            room.x_position_scroll = f.read_u32::<LE>()?;
+            room.y_position_scroll = f.read_u32::<LE>()?;
+        }
+        Ok(room)
+    }
+
+    fn read_additional(project: &mut Project, f: &mut &[u8]) -> io::Result<()> {
+        project.last_instance_id = f.read_u32::<LE>()? as usize;
+        project.last_tile_id = f.read_u32::<LE>()? as usize;
+        Ok(())
+    }
+}
+
+pub fn load_assets<T: GetAssetList>(project: &mut Project, exe: bool, strings: Strings, f: &mut &[u8]) -> Result<()> {
+    expect_version(f, "asset list", &[800])?;
+    let count = f.read_u32::<LE>()?;
+    let blocks = (0..count).map(|_| read_buffer(f)).collect::<io::Result<Vec<_>>>()?;
+    let assets = blocks
+        .into_par_iter()
+        .map(|block| -> io::Result<_> {
+            let mut data = Vec::new();
+            ZlibDecoder::new(block).read_to_end(&mut data)?;
+            let f = &mut data.as_slice();
+            if !read_bool(f)? {
+                return Ok(None)
+            }
+            let name = read_string(f, strings)?;
+            let timestamp = if exe { 0.0 } else { f.read_f64::<LE>()? };
+            Ok(Some((name, timestamp, T::load(exe, strings, f)?)))
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+    let list = T::get_asset_list(project);
+    list.alloc(assets.len());
+    for (i, asset) in assets.into_iter().enumerate() {
+        if let Some((name, timestamp, asset)) = asset {
+            list.names[i] = name;
+            list.timestamps[i] = timestamp;
+            list.assets[i] = Some(asset);
+        }
+    }
+    T::read_additional(project, f)?;
+    Ok(())
+}
+
+fn read_optional_image(f: &mut &[u8]) -> io::Result<Option<Vec<u8>>> {
+    Ok(if read_bool(f)? { Some(read_buffer(f)?.to_vec()) } else { None })
+}
+
+fn load_settings(project: &mut Project, exe: bool, strings: Strings, f: &mut &[u8]) -> Result<()> {
+    let version = expect_version(f, "settings", if exe { &[800, 825] } else { &[800, 810] })?;
+    let data = read_zlib(f)?;
+    let f = &mut data.as_slice();
+    let settings = &mut project.settings;
+    settings.fullscreen = read_bool(f)?;
+    settings.interpolate_pixels = read_bool(f)?;
+    settings.dont_draw_border = read_bool(f)?;
+    settings.display_cursor = read_bool(f)?;
+    settings.scaling = f.read_i32::<LE>()?;
+    settings.allow_resize = read_bool(f)?;
+    settings.window_on_top = read_bool(f)?;
+    settings.clear_colour = f.read_u32::<LE>()?;
+    settings.set_resolution = read_bool(f)?;
+    settings.colour_depth = f.read_u32::<LE>()?;
+    settings.resolution = f.read_u32::<LE>()?;
+    settings.frequency = f.read_u32::<LE>()?;
+    settings.dont_show_buttons = read_bool(f)?;
+    settings.vsync_and_force_cpu = if exe { read_bool(f)?.into() } else { f.read_u32::<LE>()? };
+    settings.disable_screensaver = read_bool(f)?;
+    settings.f4_fullscreen = read_bool(f)?;
+    settings.f1_help = read_bool(f)?;
+    settings.esc_close = read_bool(f)?;
+    settings.f5_save_f6_load = read_bool(f)?;
+    settings.f9_screenshot = read_bool(f)?;
+    settings.treat_close_as_esc = read_bool(f)?;
+    settings.priority = f.read_u32::<LE>()?;
+    settings.freeze_on_lose_focus = read_bool(f)?;
+    settings.loading_bar = f.read_u32::<LE>()?;
+    if settings.loading_bar == 2 {
+        settings.loading_background = read_optional_image(f)?;
+        settings.loading_foreground = read_optional_image(f)?;
+    }
+    settings.has_custom_load_image = read_bool(f)?;
+    if settings.has_custom_load_image {
+        settings.custom_load_image = read_optional_image(f)?;
+    }
+    settings.loading_transparent = read_bool(f)?;
+    settings.loading_translucency = f.read_u32::<LE>()?;
+    settings.loading_progress_bar_scale = read_bool(f)?;
+    if !exe {
+        settings.icon = read_buffer(f)?.to_vec();
+    }
+    settings.show_error_messages = read_bool(f)?;
+    settings.log_errors = read_bool(f)?;
+    settings.always_abort = read_bool(f)?;
+    let uninit = f.read_u32::<LE>()?;
+    settings.zero_uninitialized_vars = uninit & 1 != 0;
+    settings.error_on_uninitialized_args = uninit & 2 != 0;
+    if exe {
+        if version == 825 {
+            // webgl, then the creation code flag
+            f.read_u32::<LE>()?;
+            settings.vsync_and_force_cpu |= u32::from(read_bool(f)?) << 31;
+        }
+        return Ok(())
+    }
+    settings.info_author = read_string(f, strings)?;
+    settings.info_version = read_string(f, strings)?;
+    settings.info_timestamp = f.read_f64::<LE>()?;
+    settings.info_information = read_string(f, strings)?;
+    settings.version_major = f.read_u32::<LE>()?;
+    settings.version_minor = f.read_u32::<LE>()?;
+    settings.version_release = f.read_u32::<LE>()?;
+    settings.version_build = f.read_u32::<LE>()?;
+    settings.exe_company = read_string(f, strings)?;
+    settings.exe_product = read_string(f, strings)?;
+    settings.exe_copyright = read_string(f, strings)?;
+    settings.exe_description = read_string(f, strings)?;
+    Ok(())
+}
+
+fn load_triggers(project: &mut Project, exe: bool, strings: Strings, f: &mut &[u8]) -> Result<()> {
+    expect_version(f, "triggers", &[800])?;
+    let count = f.read_u32::<LE>()?;
+    for _ in 0..count {
+        let data = read_zlib(f)?;
+        let f = &mut data.as_slice();
+        let trigger = if read_bool(f)? {
+            expect_version(f, "trigger", &[800])?;
+            Some(Trigger {
+                name: read_string(f, strings)?,
+                condition: read_string(f, strings)?,
+                kind: f.read_u32::<LE>()?,
+                constant_name: read_string(f, strings)?,
+            })
+        } else {
+            None
+        };
+        project.triggers.push(trigger);
+    }
+    if !exe {
+        // last changed
+        f.read_f64::<LE>()?;
+    }
+    Ok(())
+}
+
+fn load_constants(project: &mut Project, exe: bool, strings: Strings, f: &mut &[u8]) -> Result<()> {
+    expect_version(f, "constants", &[800])?;
+    let count = f.read_u32::<LE>()?;
+    for _ in 0..count {
+        project.constants.push((read_string(f, strings)?, read_string(f, strings)?));
+    }
+    if !exe {
+        // last changed
+        f.read_f64::<LE>()?;
+    }
+    Ok(())
+}
+
+fn load_included_files(project: &mut Project, exe: bool, strings: Strings, f: &mut &[u8]) -> Result<()> {
+    expect_version(f, "included files", &[800])?;
+    let count = f.read_u32::<LE>()?;
+    for _ in 0..count {
+        let data = read_zlib(f)?;
+        let f = &mut data.as_slice();
+        if !exe {
+            // last changed
+            f.read_f64::<LE>()?;
+        }
+        expect_version(f, "included file", &[800])?;
+        let mut file = IncludedFile {
+            file_name: read_string(f, strings)?,
+            source_path: read_string(f, strings)?,
+            data_exists: read_bool(f)?,
+            source_length: f.read_u32::<LE>()?,
+            stored_in_gmk: read_bool(f)?,
+            ..Default::default()
+        };
+        if file.stored_in_gmk {
+            file.data = Some(read_buffer(f)?.to_vec());
+        }
+        file.export_setting = f.read_u32::<LE>()?;
+        file.export_custom_folder = read_string(f, strings)?;
+        file.overwrite_file = read_bool(f)?;
+        file.free_memory = read_bool(f)?;
+        file.remove_at_end = read_bool(f)?;
+        project.included_files.push(file);
+    }
+    Ok(())
+}
+
+fn load_extensions(project: &mut Project, strings: Strings, f: &mut &[u8]) -> Result<()> {
+    expect_version(f, "extensions", &[700])?;
+    let count = f.read_u32::<LE>()?;
+    for _ in 0..count {
+        project.extensions.push(read_string(f, strings)?);
+    }
+    Ok(())
+}
+
+fn load_game_information(project: &mut Project, exe: bool, strings: Strings, f: &mut &[u8]) -> Result<()> {
+    expect_version(f, "game information", &[800])?;
+    let data = read_zlib(f)?;
+    let f = &mut data.as_slice();
+    let info = &mut project.game_info;
+    info.colour = f.read_u32::<LE>()?;
+    info.new_window = read_bool(f)?;
+    info.caption = read_string(f, strings)?;
+    info.left = f.read_i32::<LE>()?;
+    info.top = f.read_i32::<LE>()?;
+    info.width = f.read_i32::<LE>()?;
+    info.height = f.read_i32::<LE>()?;
+    info.border = read_bool(f)?;
+    info.resizable = read_bool(f)?;
+    info.window_on_top = read_bool(f)?;
+    info.freeze_game = read_bool(f)?;
+    if !exe {
+        // last changed
+        f.read_f64::<LE>()?;
+    }
+    info.rtf = read_buffer(f)?.to_vec();
+    Ok(())
+}
+
+fn skip_library_init_code(f: &mut &[u8]) -> Result<()> {
+    expect_version(f, "library init code", &[500])?;
+    let count = f.read_u32::<LE>()?;
+    for _ in 0..count {
+        read_buffer(f)?;
+    }
+    Ok(())
+}
+
+fn read_room_order(f: &mut &[u8]) -> Result<Vec<usize>> {
+    expect_version(f, "room order", &[700])?;
+    let count = f.read_u32::<LE>()?;
+    (0..count).map(|_| Ok(f.read_u32::<LE>()? as usize)).collect()
+}
+
+fn read_tree_children(f: &mut &[u8], strings: Strings, count: u32) -> io::Result<Vec<TreeNode>> {
+    let mut nodes = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let status = f.read_u32::<LE>()?;
+        let _kind = f.read_u32::<LE>()?;
+        let index = f.read_u32::<LE>()?;
+        let name = read_string(f, strings)?;
+        let child_count = f.read_u32::<LE>()?;
+        let children = read_tree_children(f, strings, child_count)?;
+        nodes.push(match status {
+            3 => TreeNode::Asset(index as usize),
+            _ => TreeNode::Group(name, children),
+        });
+    }
+    Ok(nodes)
+}
+
+fn load_tree(project: &mut Project, strings: Strings, f: &mut &[u8]) -> Result<()> {
+    for _ in 0..12 {
+        let _status = f.read_u32::<LE>()?;
+        let kind = f.read_u32::<LE>()?;
+        let _index = f.read_u32::<LE>()?;
+        let _name = read_string(f, strings)?;
+        let child_count = f.read_u32::<LE>()?;
+        let children = read_tree_children(f, strings, child_count)?;
+        match kind {
+            1 => project.objects.tree = children,
+            2 => project.sprites.tree = children,
+            3 => project.sounds.tree = children,
+            4 => project.rooms.tree = children,
+            6 => project.backgrounds.tree = children,
+            7 => project.scripts.tree = children,
+            8 => project.paths.tree = children,
+            9 => project.fonts.tree = children,
+            12 => project.timelines.tree = children,
+            _ => (),
+        }
+    }
+    Ok(())
+}
+
+// reads an editable .gmk (8.0) or .gm81
+pub fn load_gm81(data: &[u8]) -> Result<Project> {
+    let f = &mut &data[..];
+    if f.read_u32::<LE>()? != GMK_MAGIC {
+        return Err(Error::Other("not a .gmk or .gm81 file".to_string()))
+    }
+    let version = f.read_u32::<LE>()?;
+    match version {
+        800 | 810 => (),
+        // 7.0 encrypts them, and everything before 8.0 lays most assets out differently
+        ..=799 => {
+            let fix = "open it in GameMaker 8 and save it from there first";
+            return Err(Error::Other(format!("this .gmk is from before GameMaker 8.0 (version {}), {}", version, fix)))
+        },
+        _ => return Err(Error::Other(format!("unsupported .gmk version {}", version))),
+    }
+    let strings = Strings::for_version(version);
+    let mut project = Project { game_id: f.read_u32::<LE>()?, ..Default::default() };
+    // guid
+    read_fixed(f, 16)?;
+    load_settings(&mut project, false, strings, f)?;
+    load_triggers(&mut project, false, strings, f)?;
+    load_constants(&mut project, false, strings, f)?;
+    load_assets::<Sound>(&mut project, false, strings, f)?;
+    load_assets::<Sprite>(&mut project, false, strings, f)?;
+    load_assets::<Background>(&mut project, false, strings, f)?;
+    load_assets::<Path>(&mut project, false, strings, f)?;
+    load_assets::<Script>(&mut project, false, strings, f)?;
+    load_assets::<Font>(&mut project, false, strings, f)?;
+    load_assets::<Timeline>(&mut project, false, strings, f)?;
+    load_assets::<Object>(&mut project, false, strings, f)?;
+    load_assets::<Room>(&mut project, false, strings, f)?;
+    load_included_files(&mut project, false, strings, f)?;
+    load_extensions(&mut project, strings, f)?;
+    load_game_information(&mut project, false, strings, f)?;
+    skip_library_init_code(f)?;
+    read_room_order(f)?;
+    load_tree(&mut project, strings, f)?;
+    Ok(project)
+}
+
+fn read_fixed<'a>(f: &mut &'a [u8], len: usize) -> io::Result<&'a [u8]> {
+    if len > f.len() {
+        return Err(io::ErrorKind::UnexpectedEof.into())
+    }
+    let (buf, rest) = f.split_at(len);
+    *f = rest;
+    Ok(buf)
+}
+
+// undoes the two scrambling passes, the inverse of save_exe::write_encrypted_gamedata
+pub fn decrypt_gamedata(f: &mut &[u8]) -> io::Result<Vec<u8>> {
+    let garbage1 = f.read_u32::<LE>()? as usize * 4;
+    let garbage2 = f.read_u32::<LE>()? as usize * 4;
+    read_fixed(f, garbage1)?;
+    let swap_table = read_fixed(f, 256)?;
+    read_fixed(f, garbage2)?;
+    let mut reverse_table = [0u8; 256];
+    for (i, &b) in swap_table.iter().enumerate() {
+        reverse_table[usize::from(b)] = i as u8;
+    }
+    let mut data = read_buffer(f)?.to_vec();
+    // second pass
+    for i in (1..data.len()).rev() {
+        data[i] = reverse_table[usize::from(data[i])].wrapping_sub(data[i - 1]).wrapping_sub(i as u8);
+    }
+    // first pass
+    for i in (0..data.len()).rev() {
+        data.swap(i, i.saturating_sub(usize::from(swap_table[i & 0xff])));
+    }
+    Ok(data)
+}
+
+fn skip_extensions(f: &mut &[u8]) -> Result<()> {
+    expect_version(f, "extensions", &[700])?;
+    if f.read_u32::<LE>()? != 0 {
+        return Err(Error::Other("can't unpack extension packages".to_string()))
+    }
+    Ok(())
+}
+
+// the builder adds a constant for every referenced instance, turn them back into instance names
+fn restore_instance_names(project: &mut Project) {
+    let mut names = HashMap::new();
+    for (_, room_name, room) in project.rooms.iter() {
+        for inst in &room.instances {
+            names.insert(inst.id.to_string(), (room_name.to_string(), inst.id));
+        }
+    }
+    let mut found = HashMap::new();
+    project.constants.retain(|(name, value)| {
+        let hex = names.get(value).and_then(|(room_name, id)| {
+            let hex = name.strip_prefix(room_name.as_str())?.strip_prefix('_')?;
+            let valid = hex.len() == 8 && hex.bytes().all(|c| c.is_ascii_digit() || (b'A'..=b'F').contains(&c));
+            Some((*id, u32::from_str_radix(hex, 16).ok().filter(|_| valid)?))
+        });
+        match hex {
+            Some((id, hex)) => {
+                found.insert(id, hex);
+                false
+            },
+            None => true,
+        }
+    });
+    for room in project.rooms.assets.iter_mut().flatten() {
+        for inst in &mut room.instances {
+            if let Some(&name) = found.get(&inst.id) {
+                inst.extra.name = name;
+            }
+        }
+    }
+}
+
+fn flat_tree<T>(list: &mut AssetList<T>) {
+    list.tree = (0..list.assets.len()).filter(|&i| list.assets[i].is_some()).map(TreeNode::Asset).collect();
+}
+
+fn load_gamedata(project: &mut Project, data: &[u8]) -> Result<()> {
+    let f = &mut &data[..];
+    let garbage = f.read_u32::<LE>()? as usize * 4;
+    read_fixed(f, garbage)?;
+    // pro flag
+    read_bool(f)?;
+    project.game_id = f.read_u32::<LE>()?;
+    // guid
+    read_fixed(f, 16)?;
+    let strings = Strings::for_version(GMK_VERSION);
+    skip_extensions(f)?;
+    load_triggers(project, true, strings, f)?;
+    load_constants(project, true, strings, f)?;
+    load_assets::<Sound>(project, true, strings, f)?;
+    load_assets::<Sprite>(project, true, strings, f)?;
+    load_assets::<Background>(project, true, strings, f)?;
+    load_assets::<Path>(project, true, strings, f)?;
+    load_assets::<Script>(project, true, strings, f)?;
+    load_assets::<Font>(project, true, strings, f)?;
+    load_assets::<Timeline>(project, true, strings, f)?;
+    load_assets::<Object>(project, true, strings, f)?;
+    load_assets::<Room>(project, true, strings, f)?;
+    load_included_files(project, true, strings, f)?;
+    load_game_information(project, true, strings, f)?;
+    skip_library_init_code(f)?;
+    let room_order = read_room_order(f)?;
+    restore_instance_names(project);
+    // exes don't have a resource tree, so everything goes in the root
+    flat_tree(&mut project.sounds);
+    flat_tree(&mut project.sprites);
+    flat_tree(&mut project.backgrounds);
+    flat_tree(&mut project.paths);
+    flat_tree(&mut project.scripts);
+    flat_tree(&mut project.fonts);
+    flat_tree(&mut project.timelines);
+    flat_tree(&mut project.objects);
+    project.rooms.tree = room_order.into_iter().map(TreeNode::Asset).collect();
+    Ok(())
+}
+
+fn load_exe_at(data: &[u8]) -> Result<Project> {
+    let f = &mut &data[..];
+    f.read_u32::<LE>()?;
+    f.read_u32::<LE>()?;
+    let mut project = Project::default();
+    load_settings(&mut project, true, Strings::for_version(GMK_VERSION), f)?;
+    let gamedata = decrypt_gamedata(f)?;
+    load_gamedata(&mut project, &gamedata)?;
+    Ok(project)
+}
+
+// reads a game built by save_exe back into a project, minus whatever the exe doesn't keep. games built by
+// GameMaker itself keep their gamedata differently and aren't read
+pub fn load_exe(data: &[u8]) -> Result<Project> {
+    // the runner is in front of the game, so look for the header
+    let mut header = Vec::with_capacity(8);
+    header.extend_from_slice(&GMK_MAGIC.to_le_bytes());
+    header.extend_from_slice(&GMK_VERSION.to_le_bytes());
+    let mut error = None;
+    for pos in (0..data.len().saturating_sub(7)).filter(|&i| data[i..i + 8] == header[..]) {
+        match load_exe_at(&data[pos..]) {
+            Ok(project) => return Ok(project),
+            Err(e) => error = error.or(Some(e)),
+        }
+    }
+    let not_ours = "only games built by gm82 exe can be unpacked, not ones built by GameMaker itself";
+    Err(match error {
+        Some(e) => Error::Other(format!("couldn't read the gamedata ({}), {}", e, not_ours)),
+        None => Error::Other(format!("couldn't find any gamedata, {}", not_ours)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        save::tests::{libs, project},
+        save_exe::save_exe,
+    };
+
+    #[test]
+    fn only_games_built_here_unpack() {
+        let mut project = project();
+        // these can't be built outside the IDE
+        project.fonts = Default::default();
+        project.extensions.clear();
+        let mut exe = Vec::new();
+        save_exe(&project, &libs(), b"runner", &mut exe).unwrap();
+        let unpacked = load_exe(&exe).unwrap();
+        assert_eq!(unpacked.scripts.names, project.scripts.names);
+        // what a game from GameMaker 8.0 starts its gamedata with
+        let mut other = b"runner".to_vec();
+        other.extend([1234321u32, 800, 0, 0].iter().flat_map(|n| n.to_le_bytes()));
+        assert!(load_exe(&other).is_err_and(|e| e.to_string().contains("gm82 exe")));
+        assert!(load_exe(&exe[..exe.len() / 2]).is_err_and(|e| e.to_string().contains("gm82 exe")));
+    }
+
+    #[test]
+    fn old_gmk_files_say_how_to_open_them() {
+        // a GameMaker 7 .gmk, the rest is encrypted
+        let gmk = [1234321u32, 702, 12345].iter().flat_map(|n| n.to_le_bytes()).collect::<Vec<_>>();
+        assert!(load_gm81(&gmk).is_err_and(|e| e.to_string().contains("save it from there")));
+    }
+}
@@ -0,0 +1,69 @@
+use crate::{Error, Result};
+use itertools::Itertools;
+use parking_lot::Mutex;
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    path::Path,
+};
+
+// a hash of what each asset wrote in the last save, so the next one can leave alone anything that'd come out
+// the same. it's kept in the work folder rather than with the project, since it only describes the files as
+// this machine last saw them, and anything that goes wrong just deletes it so the next save writes everything.
+// each entry also has the size and modified time of its files as the save left them, so anything that's
+// changed them since, like a git checkout, gets written again
+#[derive(Default)]
+pub struct Manifest {
+    old: HashMap<String, (u64, u64)>,
+    // and which files each one is, relative to the project folder
+    new: Mutex<HashMap<String, (u64, Vec<String>)>>,
+}
+
+// the size and modified time of some files in dir, missing ones included
+pub fn stamp(dir: &Path, files: &[String]) -> u64 {
+    let mut h = DefaultHasher::new();
+    for file in files {
+        let meta = dir.join(file).metadata().ok();
+        (file, meta.as_ref().map(|m| m.len()), meta.and_then(|m| m.modified().ok())).hash(&mut h);
+    }
+    h.finish()
+}
+
+impl Manifest {
+    // one "<hash> <stamp> <key>" per line. anything unreadable is just left out
+    pub fn read(path: &Path) -> Self {
+        let old = std::fs::read_to_string(path)
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|l| l.splitn(3, ' ').collect_tuple())
+            .filter_map(|(hash, stamp, key)| {
+                Some((key.to_string(), (u64::from_str_radix(hash, 16).ok()?, u64::from_str_radix(stamp, 16).ok()?)))
+            })
+            .collect();
+        Self { old, new: Mutex::new(HashMap::new()) }
+    }
+
+    // records the hash for this save and says whether it's what the last one wrote, with its files untouched
+    pub fn update(&self, key: String, hash: u64, dir: &Path, files: Vec<String>) -> bool {
+        let same = self.old.get(&key) == Some(&(hash, stamp(dir, &files)));
+        self.new.lock().insert(key, (hash, files));
+        same
+    }
+
+    // carries over what the last save had for something that wasn't looked at this time
+    pub fn keep(&self, key: String, files: Vec<String>) {
+        if let Some(&(hash, _)) = self.old.get(&key) {
+            self.new.lock().insert(key, (hash, files));
+        }
+    }
+
+    // dir is the project folder, once this save's files are in it
+    pub fn write(&self, path: &Path, dir: &Path) -> Result<()> {
+        let new = self.new.lock();
+        let mut out = String::with_capacity(new.len() * 48);
+        for (key, (hash, files)) in new.iter() {
+            out += &format!("{hash:016x} {:016x} {key}\n", stamp(dir, files));
+        }
+        std::fs::write(path, out).map_err(|e| Error::FileIoError(e, path.to_path_buf()))
+    }
+}
@@ -0,0 +1,159 @@
+use crate::{transaction, Error, Result};
+use png::{AdaptiveFilterType, Compression, EncodingError, FilterType};
+use rayon::prelude::*;
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+};
+
+// how hard to try making pngs small, set by png_effort in the .gm82 file. they all decode to the same pixels
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PngEffort {
+    Fast = 0,
+    #[default]
+    Default = 1,
+    // much slower, mostly worth it for a one-off recompress
+    Best = 2,
+}
+
+impl PngEffort {
+    pub fn from_u8(n: u8) -> Option<Self> {
+        match n {
+            0 => Some(Self::Fast),
+            1 => Some(Self::Default),
+            2 => Some(Self::Best),
+            _ => None,
+        }
+    }
+}
+
+enum Content<'a> {
+    Bytes(Vec<u8>),
+    Slice(&'a [u8]),
+    // BGRA8, written as a png
+    Image { width: u32, height: u32, data: &'a [u8] },
+}
+
+// everything one asset saves, kept in memory until it's known whether it's any different to last time.
+// images are only encoded if they get written, which is most of the time a save takes
+#[derive(Default)]
+pub struct Output<'a> {
+    files: Vec<(PathBuf, Content<'a>)>,
+}
+
+fn create_dirs(path: &Path) -> Result<()> {
+    std::fs::create_dir_all(path).map_err(|e| Error::DirIoError(e, path.to_path_buf()))
+}
+
+// data is BGRA8
+pub fn encode_png(
+    width: u32,
+    height: u32,
+    data: &[u8],
+    effort: PngEffort,
+) -> std::result::Result<Vec<u8>, EncodingError> {
+    let mut out = Vec::new();
+    // set up encoder
+    let mut encoder = png::Encoder::new(&mut out, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    match effort {
+        PngEffort::Fast => {
+            encoder.set_compression(Compression::Fast);
+            encoder.set_filter(FilterType::NoFilter);
+        },
+        PngEffort::Default => {
+            encoder.set_compression(Compression::Default);
+            encoder.set_filter(FilterType::NoFilter);
+        },
+        PngEffort::Best => {
+            encoder.set_compression(Compression::Best);
+            encoder.set_filter(FilterType::Paeth);
+            encoder.set_adaptive_filter(AdaptiveFilterType::Adaptive);
+        },
+    }
+    let mut writer = encoder.write_header()?;
+    // BGRA8 -> RGBA8
+    let mut pixels = data.to_vec();
+    pixels.par_chunks_exact_mut(4).for_each(|px| px.swap(0, 2));
+    writer.write_image_data(&pixels)?;
+    drop(writer);
+    Ok(out)
+}
+
+impl<'a> Output<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // a file to write into
+    pub fn file(&mut self, path: &Path) -> &mut Vec<u8> {
+        self.files.push((path.to_path_buf(), Content::Bytes(Vec::new())));
+        match self.files.last_mut() {
+            Some((_, Content::Bytes(data))) => data,
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn data(&mut self, path: &Path, data: &'a [u8]) {
+        self.files.push((path.to_path_buf(), Content::Slice(data)));
+    }
+
+    pub fn image(&mut self, path: &Path, width: u32, height: u32, data: &'a [u8]) {
+        self.files.push((path.to_path_buf(), Content::Image { width, height, data }));
+    }
+
+    // covers the files' names relative to dir and what's in them, so the same output hashes the same wherever
+    // it's going. this is only ever compared with hashes made on the same machine, so it doesn't matter that
+    // the hasher could change between rust versions, that just means one save writes everything
+    pub fn hash(&self, dir: &Path) -> u64 {
+        let mut h = DefaultHasher::new();
+        for (path, content) in &self.files {
+            path.strip_prefix(dir).unwrap_or(path).hash(&mut h);
+            match content {
+                Content::Bytes(data) => data.hash(&mut h),
+                Content::Slice(data) => data.hash(&mut h),
+                Content::Image { width, height, data } => (width, height, data).hash(&mut h),
+            }
+        }
+        h.finish()
+    }
+
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.files.iter().map(|(path, _)| path.as_path())
+    }
+
+    pub fn images(&self) -> impl Iterator<Item = &Path> {
+        self.files.iter().filter(|(_, c)| matches!(c, Content::Image { .. })).map(|(path, _)| path.as_path())
+    }
+
+    // whether all of it is already on disk, in the staging folder or the project
+    pub fn exists(&self) -> bool {
+        self.files.iter().all(|(path, _)| transaction::exists(path))
+    }
+
+    // keep is asked about each image with a hash of its pixels, and it isn't encoded again if that says so
+    pub fn write(&self, effort: PngEffort, keep: impl Fn(&Path, u64) -> bool) -> Result<()> {
+        for (path, content) in &self.files {
+            if let Some(parent) = path.parent() {
+                create_dirs(parent)?;
+            }
+            match content {
+                Content::Bytes(data) => std::fs::write(path, data),
+                Content::Slice(data) => std::fs::write(path, data),
+                Content::Image { width, height, data } => {
+                    let mut h = DefaultHasher::new();
+                    (width, height, data).hash(&mut h);
+                    if keep(path, h.finish()) {
+                        continue
+                    }
+                    let png = encode_png(*width, *height, data, effort)
+                        .map_err(|_| Error::Other(format!("failed to save frame {}", path.to_string_lossy())))?;
+                    std::fs::write(path, png)
+                },
+            }
+            .map_err(|e| Error::FileIoError(e, path.clone()))?;
+        }
+        Ok(())
+    }
+}
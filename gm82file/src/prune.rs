@@ -0,0 +1,189 @@
+use crate::{escape::file_name, Error, Result};
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+use unicase::UniCase;
+
+// finds files left behind by earlier saves: assets that were deleted or renamed, and files an asset doesn't
+// write anymore (frames, instance code, tile layers). only names that save could have written are considered,
+// so anything else people keep in the project folder is left alone
+
+enum Layout {
+    // <name>.<ext> for each asset, with the extensions save uses or any extension if there's a <name>.txt
+    Files(&'static [&'static str]),
+    // a folder for each asset, and which file names can go in it
+    Folders(fn(&str) -> bool),
+}
+
+const ASSET_DIRS: [(&str, Option<&str>, Layout); 10] = [
+    ("sounds", Some("has_sounds"), Layout::Files(&[])),
+    ("sprites", Some("has_sprites"), Layout::Folders(sprite_file)),
+    ("backgrounds", Some("has_backgrounds"), Layout::Files(&["png", "txt"])),
+    ("paths", Some("has_paths"), Layout::Folders(path_file)),
+    ("scripts", Some("has_scripts"), Layout::Files(&["gml"])),
+    ("fonts", Some("has_fonts"), Layout::Files(&["txt"])),
+    ("timelines", Some("has_timelines"), Layout::Files(&["gml"])),
+    ("objects", Some("has_objects"), Layout::Files(&["txt", "gml"])),
+    ("rooms", None, Layout::Folders(room_file)),
+    ("triggers", Some("has_triggers"), Layout::Files(&["txt", "gml"])),
+];
+
+fn name(s: &str) -> UniCase<String> {
+    UniCase::new(s.to_string())
+}
+
+fn is_number(s: &str) -> bool {
+    let s = s.strip_prefix('-').unwrap_or(s);
+    !s.is_empty() && s.bytes().all(|c| c.is_ascii_digit())
+}
+
+fn sprite_file(name: &str) -> bool {
+    name == "sprite.txt" || name.strip_suffix(".png").is_some_and(is_number)
+}
+
+fn path_file(name: &str) -> bool {
+    name == "path.txt" || name == "points.txt"
+}
+
+fn room_file(name: &str) -> bool {
+    matches!(name, "room.txt" | "code.gml" | "instances.txt" | "layers.txt")
+        || name.strip_suffix(".txt").is_some_and(is_number)
+        || name.strip_suffix(".gml").is_some_and(|n| n.len() == 8 && n.bytes().all(|c| c.is_ascii_hexdigit()))
+}
+
+// (name, is_dir) for everything in a folder, or nothing if it doesn't exist
+pub fn list_dir(path: &Path) -> Result<Vec<(String, bool)>> {
+    let entries = match std::fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(Error::DirIoError(e, path.to_path_buf())),
+    };
+    let mut out = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        // save can't have written anything that isn't unicode
+        if let Ok(name) = entry.file_name().into_string() {
+            out.push((name, entry.file_type()?.is_dir()));
+        }
+    }
+    Ok(out)
+}
+
+// file names for the names in the index that's about to be committed, or the one already there
+fn read_names(dir: &Path, staging: &Path, index: &str) -> Result<Option<HashSet<UniCase<String>>>> {
+    let path = if staging.join(index).exists() { staging.join(index) } else { dir.join(index) };
+    match std::fs::read_to_string(&path) {
+        Ok(s) => Ok(Some(s.lines().filter(|l| !l.is_empty()).map(|l| name(&file_name(l))).collect())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(Error::FileIoError(e, path)),
+    }
+}
+
+// the has_ keys that are turned off in the .gm82 file being committed
+fn disabled_types(staging: &Path, staged: &[String]) -> Result<HashSet<String>> {
+    let mut out = HashSet::new();
+    for file in staged.iter().filter(|f| !f.contains('/') && f.ends_with(".gm82")) {
+        let path = staging.join(file);
+        let s = std::fs::read_to_string(&path).map_err(|e| Error::FileIoError(e, path))?;
+        out.extend(s.lines().filter_map(|l| l.strip_suffix("=0")).map(String::from));
+    }
+    Ok(out)
+}
+
+// dir is the project folder, staging has the files in staged which are about to be moved into it
+pub fn orphaned_files(dir: &Path, staging: &Path, staged: &[String]) -> Result<Vec<String>> {
+    let staged_set = staged.iter().map(|s| UniCase::new(s.as_str())).collect::<HashSet<_>>();
+    let disabled = disabled_types(staging, staged)?;
+    let mut all_names = HashMap::new();
+    let mut out = Vec::new();
+    for (type_dir, has_key, layout) in &ASSET_DIRS {
+        let names = if has_key.is_some_and(|k| disabled.contains(k)) {
+            HashSet::new()
+        } else {
+            match read_names(dir, staging, &format!("{type_dir}/index.yyd"))? {
+                Some(names) => names,
+                None => continue,
+            }
+        };
+        // assets that were saved just now, so anything of theirs that wasn't written is stale
+        let rewritten = staged
+            .iter()
+            .filter_map(|f| f.strip_prefix(type_dir)?.strip_prefix('/'))
+            .map(|f| match layout {
+                Layout::Files(_) => f.rsplit_once('.').map_or(f, |(stem, _)| stem),
+                Layout::Folders(_) => f.split_once('/').map_or(f, |(folder, _)| folder),
+            })
+            .map(UniCase::new)
+            .collect::<HashSet<_>>();
+        let live_dir = dir.join(type_dir);
+        let entries = list_dir(&live_dir)?;
+        for (entry, is_dir) in &entries {
+            match layout {
+                Layout::Files(exts) => {
+                    // a sound with no extension is just its name
+                    if *is_dir || matches!(entry.as_str(), "index.yyd" | "tree.yyd") || names.contains(&name(entry)) {
+                        continue
+                    }
+                    let Some((stem, ext)) = entry.rsplit_once('.') else { continue };
+                    let owned = if exts.is_empty() {
+                        ext == "txt" || entries.iter().any(|(e, d)| !d && *e == format!("{stem}.txt"))
+                    } else {
+                        exts.contains(&ext)
+                    };
+                    let rel = format!("{type_dir}/{entry}");
+                    let stale = rewritten.contains(&UniCase::new(stem)) && !staged_set.contains(&UniCase::new(&*rel));
+                    if owned && (!names.contains(&name(stem)) || stale) {
+                        out.push(rel);
+                    }
+                },
+                Layout::Folders(owned) => {
+                    let exists = names.contains(&name(entry));
+                    if !is_dir || (exists && !rewritten.contains(&UniCase::new(entry.as_str()))) {
+                        continue
+                    }
+                    for (file, is_dir) in list_dir(&live_dir.join(entry))? {
+                        let rel = format!("{type_dir}/{entry}/{file}");
+                        if !is_dir && owned(&file) && (!exists || !staged_set.contains(&UniCase::new(rel.as_str()))) {
+                            out.push(rel);
+                        }
+                    }
+                },
+            }
+        }
+        all_names.insert(*type_dir, names);
+    }
+    // icon cache
+    for type_dir in ["sprites", "backgrounds"] {
+        let Some(names) = all_names.get(type_dir) else { continue };
+        for (entry, is_dir) in list_dir(&dir.join("cache").join(type_dir))? {
+            if let Some(stem) = entry.strip_suffix(".bmp").filter(|_| !is_dir) {
+                if !names.contains(&name(stem)) {
+                    out.push(format!("cache/{type_dir}/{entry}"));
+                }
+            }
+        }
+    }
+    // included files, with their settings next to the index and the data in a folder beside that
+    let names = if disabled.contains("has_datafiles") {
+        Some(HashSet::new())
+    } else {
+        read_names(dir, staging, "datafiles/index.yyd")?
+    };
+    if let Some(names) = names {
+        let meta_dir = dir.join("datafiles");
+        for (entry, is_dir) in list_dir(&meta_dir)? {
+            if let Some(stem) = entry.strip_suffix(".txt").filter(|_| !is_dir) {
+                if !names.contains(&name(stem)) {
+                    out.push(format!("datafiles/{entry}"));
+                }
+            }
+        }
+        for (entry, is_dir) in list_dir(&meta_dir.join("include"))? {
+            if !is_dir && !names.contains(&name(&entry)) {
+                out.push(format!("datafiles/include/{entry}"));
+            }
+        }
+    }
+    Ok(out)
+}
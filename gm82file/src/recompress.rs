@@ -0,0 +1,67 @@
+use crate::{
+    load::load_frame,
+    output::{encode_png, PngEffort},
+    prune::list_dir,
+    transaction::{interrupted, project_dir},
+    Error, Result,
+};
+use rayon::prelude::*;
+use std::path::Path;
+
+// re-encodes every sprite frame and background as small as it'll go, whatever png_effort is. each one has to load
+// back as exactly the same pixels before it replaces the old file, and anything that wouldn't shrink is left alone
+
+pub struct Recompressed {
+    // relative to the project
+    pub file: String,
+    pub old_size: u64,
+    pub new_size: u64,
+}
+
+fn pngs(dir: &Path) -> Result<Vec<String>> {
+    let is_png = |(name, is_dir): &(String, bool)| !is_dir && name.ends_with(".png");
+    let mut out = Vec::new();
+    for (sprite, is_dir) in list_dir(&dir.join("sprites"))? {
+        if is_dir {
+            let frames = list_dir(&dir.join("sprites").join(&sprite))?;
+            out.extend(frames.iter().filter(|e| is_png(e)).map(|(name, _)| format!("sprites/{sprite}/{name}")));
+        }
+    }
+    let backgrounds = list_dir(&dir.join("backgrounds"))?;
+    out.extend(backgrounds.iter().filter(|e| is_png(e)).map(|(name, _)| format!("backgrounds/{name}")));
+    out.sort();
+    Ok(out)
+}
+
+fn recompress_file(path: &Path) -> Result<Option<(u64, u64)>> {
+    let old_size = path.metadata().map_err(|e| Error::FileIoError(e, path.to_path_buf()))?.len();
+    let frame = load_frame(path)?;
+    let png = encode_png(frame.width, frame.height, &frame.data, PngEffort::Best)
+        .map_err(|_| Error::Other(format!("failed to save frame {}", path.to_string_lossy())))?;
+    if png.len() as u64 >= old_size {
+        return Ok(None)
+    }
+    let temp = path.with_extension("png.gm82tmp");
+    std::fs::write(&temp, &png).map_err(|e| Error::FileIoError(e, temp.clone()))?;
+    if !load_frame(&temp).is_ok_and(|f| f == frame) {
+        let _ = std::fs::remove_file(&temp);
+        return Err(Error::Other(format!("recompressing {} would change its pixels", path.to_string_lossy())))
+    }
+    std::fs::rename(&temp, path).map_err(|e| Error::FileIoError(e, temp))?;
+    Ok(Some((old_size, png.len() as u64)))
+}
+
+// project is the path to the .gm82 file
+pub fn recompress(project: &Path) -> Result<Vec<Recompressed>> {
+    if interrupted(project) {
+        return Err(Error::InterruptedSave(project.to_path_buf()))
+    }
+    let dir = project_dir(project);
+    let out = pngs(&dir)?
+        .into_par_iter()
+        .map(|file| {
+            Ok(recompress_file(&dir.join(&file))?.map(|(old_size, new_size)| Recompressed { file, old_size, new_size }))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(out.into_iter().flatten().collect())
+}
@@ -0,0 +1,1293 @@
+use crate::{
+    asset::*,
+    escape::{delimit, file_name},
+    events, filename, now,
+    output::{Output, PngEffort},
+    transaction::{self, Transaction},
+    Error, GMLLines, Namer, Progress, Result, ACTION_TOKEN, GM82_VERSION, INSTANCE_COLUMNS, TILE_COLUMNS,
+};
+use rayon::prelude::*;
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs::File,
+    io::{BufWriter, Write},
+    path::PathBuf,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+fn make_unicase(s: String) -> unicase::UniCase<String> {
+    if s.is_ascii() {
+        unicase::UniCase::ascii(s)
+    } else {
+        unicase::UniCase::unicode(s)
+    }
+}
+
+fn create_dirs(path: &std::path::Path) -> Result<()> {
+    std::fs::create_dir_all(path).map_err(|e| Error::DirIoError(e, path.to_path_buf()))
+}
+
+fn open_file(path: &std::path::Path) -> Result<BufWriter<File>> {
+    Ok(BufWriter::new(File::create(path).map_err(|e| Error::FileIoError(e, path.to_path_buf()))?))
+}
+
+fn write_gml<F: Write>(f: &mut F, code: &str) -> Result<()> {
+    for line in GMLLines::new(code.trim_end().lines()) {
+        writeln!(f, "{}", line)?;
+    }
+    Ok(())
+}
+
+fn save_frame<'a>(frame: &'a Frame, path: &std::path::Path, out: &mut Output<'a>) {
+    out.image(path, frame.width, frame.height, frame.get_data());
+}
+
+// keys other tools added to an asset's file, written back as they were
+fn write_extra_keys(f: &mut impl Write, keys: &[(String, String)]) -> Result<()> {
+    for (k, v) in keys {
+        writeln!(f, "{}={}", k, v)?;
+    }
+    Ok(())
+}
+
+fn save_sound<'a>(sound: &'a Sound, path: &mut PathBuf, _project: &Project, out: &mut Output<'a>) -> Result<()> {
+    path.set_extension(sound.extension.trim_matches('.'));
+    if let Some(data) = sound.data.as_ref() {
+        out.data(path, data);
+    }
+    path.set_extension("txt");
+    let f = out.file(path);
+    writeln!(f, "extension={}", sound.extension)?;
+    writeln!(f, "exists={}", u8::from(sound.data.is_some()))?;
+    writeln!(f, "source={}", sound.source)?;
+    writeln!(f, "kind={}", sound.kind)?;
+    writeln!(f, "effects={}", sound.effects)?;
+    writeln!(f, "volume={}", sound.volume)?;
+    writeln!(f, "pan={}", sound.pan)?;
+    writeln!(f, "preload={}", u8::from(sound.preload))?;
+    write_extra_keys(f, &sound.extra_keys)?;
+    Ok(())
+}
+
+fn save_sprite<'a>(sprite: &'a Sprite, path: &mut PathBuf, _project: &Project, out: &mut Output<'a>) -> Result<()> {
+    for (i, frame) in sprite.frames.iter().enumerate() {
+        path.push(format!("{}.png", i));
+        save_frame(frame, path, out);
+        path.pop();
+    }
+    path.push("sprite.txt");
+    let f = out.file(path);
+    writeln!(f, "frames={}", sprite.frames.len())?;
+    writeln!(f, "origin_x={}", sprite.origin_x)?;
+    writeln!(f, "origin_y={}", sprite.origin_y)?;
+    writeln!(f, "collision_shape={}", sprite.collision_shape)?;
+    writeln!(f, "alpha_tolerance={}", sprite.alpha_tolerance)?;
+    writeln!(f, "per_frame_colliders={}", sprite.per_frame_colliders as u8)?;
+    writeln!(f, "bbox_type={}", sprite.bbox_type)?;
+    writeln!(f, "bbox_left={}", sprite.bbox_left)?;
+    writeln!(f, "bbox_top={}", sprite.bbox_top)?;
+    writeln!(f, "bbox_right={}", sprite.bbox_right)?;
+    writeln!(f, "bbox_bottom={}", sprite.bbox_bottom)?;
+    write_extra_keys(f, &sprite.extra_keys)?;
+    path.pop();
+    Ok(())
+}
+
+fn save_background<'a>(
+    back: &'a Background,
+    path: &mut PathBuf,
+    _project: &Project,
+    out: &mut Output<'a>,
+) -> Result<()> {
+    path.set_extension("png");
+    let frame = &back.frame;
+    if frame.width != 0 && frame.height != 0 {
+        save_frame(frame, path, out);
+    }
+    path.set_extension("txt");
+    let f = out.file(path);
+    writeln!(f, "exists={}", u8::from(frame.width != 0 && frame.height != 0))?;
+    writeln!(f, "tileset={}", back.is_tileset as u8)?;
+    writeln!(f, "tile_width={}", back.tile_width)?;
+    writeln!(f, "tile_height={}", back.tile_height)?;
+    writeln!(f, "tile_hoffset={}", back.h_offset)?;
+    writeln!(f, "tile_voffset={}", back.v_offset)?;
+    writeln!(f, "tile_hsep={}", back.h_sep)?;
+    writeln!(f, "tile_vsep={}", back.v_sep)?;
+    write_extra_keys(f, &back.extra_keys)?;
+    Ok(())
+}
+
+fn save_path(path: &Path, file_path: &mut PathBuf, project: &Project, out: &mut Output) -> Result<()> {
+    file_path.push("path.txt");
+    let f = out.file(file_path);
+    writeln!(f, "connection={}", path.connection)?;
+    writeln!(f, "closed={}", path.closed as u8)?;
+    writeln!(f, "precision={}", path.precision)?;
+    writeln!(f, "background={}", project.rooms.name(path.path_editor_room_background))?;
+    writeln!(f, "snap_x={}", path.snap_x)?;
+    writeln!(f, "snap_y={}", path.snap_y)?;
+    write_extra_keys(f, &path.extra_keys)?;
+    file_path.pop();
+    file_path.push("points.txt");
+    let f = out.file(file_path);
+    for p in &path.points {
+        writeln!(f, "{},{},{}", p.x, p.y, p.speed)?;
+    }
+    file_path.pop();
+    Ok(())
+}
+
+fn save_script(script: &Script, path: &mut PathBuf, _project: &Project, out: &mut Output) -> Result<()> {
+    path.set_extension("gml");
+    write_gml(out.file(path), &script.source)?;
+    Ok(())
+}
+
+fn save_font(font: &Font, path: &mut PathBuf, _project: &Project, out: &mut Output) -> Result<()> {
+    path.set_extension("txt");
+    let f = out.file(path);
+    writeln!(f, "name={}", font.sys_name)?;
+    writeln!(f, "size={}", font.size)?;
+    writeln!(f, "bold={}", font.bold as u8)?;
+    writeln!(f, "italic={}", font.italic as u8)?;
+    writeln!(f, "charset={}", font.charset)?;
+    writeln!(f, "aa_level={}", font.aa_level)?; // DOES NOT CORRESPOND TO .GMK OR .EXE
+    writeln!(f, "range_start={}", font.range_start)?;
+    writeln!(f, "range_end={}", font.range_end)?;
+    write_extra_keys(f, &font.extra_keys)?;
+    Ok(())
+}
+
+fn save_event<F: Write>(ev: &Event, name: &str, file: &mut F, project: &Project) -> Result<()> {
+    writeln!(file, "#define {}", name)?;
+    for action in &ev.actions {
+        writeln!(file, "{}", ACTION_TOKEN)?;
+        writeln!(file, "lib_id={}", action.lib_id)?;
+        writeln!(file, "action_id={}", action.id)?;
+        if action.can_be_relative {
+            writeln!(file, "relative={}", u8::from(action.is_relative))?;
+        }
+        if action.applies_to_something {
+            match action.applies_to {
+                -2 => writeln!(file, "applies_to=other")?,
+                -1 => writeln!(file, "applies_to=self")?,
+                i => writeln!(file, "applies_to={}", project.objects.name(i))?,
+            }
+        }
+        match action.action_kind {
+            0 => {
+                // normal
+                writeln!(file, "invert={}", u8::from(action.invert_condition))?;
+                for i in 0..action.param_count as usize {
+                    let param = &action.param_strings[i];
+                    writeln!(file, "arg{}={}", i, match action.param_types[i] {
+                        5 => project.sprites.name(param.parse()?).to_string(),
+                        6 => project.sounds.name(param.parse()?).to_string(),
+                        7 => project.backgrounds.name(param.parse()?).to_string(),
+                        8 => project.paths.name(param.parse()?).to_string(),
+                        9 => project.scripts.name(param.parse()?).to_string(),
+                        10 => project.objects.name(param.parse()?).to_string(),
+                        11 => project.rooms.name(param.parse()?).to_string(),
+                        12 => project.fonts.name(param.parse()?).to_string(),
+                        14 => project.timelines.name(param.parse()?).to_string(),
+                        // params can have newlines so delimit
+                        _ => delimit(param),
+                    })?;
+                }
+            },
+            5 => {
+                // repeat
+                writeln!(file, "repeats={}", action.param_strings[0])?;
+            },
+            6 => {
+                // variable
+                writeln!(file, "var_name={}", action.param_strings[0])?;
+                writeln!(file, "var_value={}", action.param_strings[1])?;
+            },
+            _ => (),
+        }
+        writeln!(file, "*/")?;
+        if action.action_kind == 7 {
+            // code
+            let code = &action.param_strings[0];
+            if code.starts_with("#define") || code.contains("\n#define") {
+                return Err(Error::Other("events should not contain #define".to_string()))
+            }
+            write_gml(file, code)?;
+        }
+    }
+    Ok(())
+}
+
+fn save_timeline(tl: &Timeline, path: &mut PathBuf, project: &Project, out: &mut Output) -> Result<()> {
+    path.set_extension("gml");
+    let f = out.file(path);
+    for (time, event) in &tl.moments {
+        if !event.actions.is_empty() {
+            save_event(event, &time.to_string(), f, project)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn event_name(ev_type: usize, ev_numb: usize, project: &Project) -> String {
+    match ev_type {
+        events::EV_COLLISION => format!("{}_{}", events::EVENT_NAMES[ev_type], project.objects.name(ev_numb as _)),
+        events::EV_TRIGGER => format!(
+            "{}_{}",
+            events::EVENT_NAMES[ev_type],
+            project.triggers.get(ev_numb).and_then(Option::as_ref).map(|t| t.name.as_str()).unwrap_or_default()
+        ),
+        _ => format!("{}_{}", events::EVENT_NAMES[ev_type], ev_numb),
+    }
+}
+
+fn save_object(obj: &Object, path: &mut PathBuf, project: &Project, out: &mut Output) -> Result<()> {
+    path.set_extension("txt");
+    {
+        let f = out.file(path);
+        writeln!(f, "sprite={}", project.sprites.name(obj.sprite_index))?;
+        writeln!(f, "visible={}", u8::from(obj.visible))?;
+        writeln!(f, "solid={}", u8::from(obj.solid))?;
+        writeln!(f, "persistent={}", u8::from(obj.persistent))?;
+        writeln!(f, "depth={}", obj.depth)?;
+        writeln!(f, "parent={}", project.objects.name(obj.parent_index))?;
+        writeln!(f, "mask={}", project.sprites.name(obj.mask_index))?;
+        write_extra_keys(f, &obj.extra_keys)?;
+    }
+    path.set_extension("gml");
+    {
+        let f = out.file(path);
+        for (ev_type, event_group) in obj.events.iter().enumerate() {
+            for (&ev_numb, ev) in event_group {
+                if !ev.actions.is_empty() {
+                    if (ev_type == events::EV_COLLISION && project.objects.get(ev_numb as _).is_none())
+                        || (ev_type == events::EV_TRIGGER
+                            && project.triggers.get(ev_numb).and_then(Option::as_ref).is_none())
+                    {
+                        continue
+                    }
+                    let name = event_name(ev_type, ev_numb, project);
+                    save_event(ev, &name, f, project)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn save_tiles(tiles: &[Tile], path: &mut PathBuf, project: &Project, out: &mut Output) -> Result<()> {
+    let mut layers = BTreeMap::new();
+    // id is the last column
+    let columns = if project.preserve_ids { &TILE_COLUMNS[..] } else { &TILE_COLUMNS[..TILE_COLUMNS.len() - 1] };
+    for tile in tiles {
+        let f = layers.entry(tile.depth).or_insert_with(|| format!("{}\n", columns.join(",")).into_bytes());
+        let TileExtra { name, xscale, yscale, blend } = &tile.extra;
+        write!(
+            f,
+            "{},{},{},{},{},{},{},{},{},{},{},{:08X}",
+            project.backgrounds.name(tile.source_bg),
+            tile.x,
+            tile.y,
+            tile.u,
+            tile.v,
+            tile.width,
+            tile.height,
+            u8::from(tile.locked),
+            xscale,
+            yscale,
+            blend,
+            name,
+        )?;
+        if project.preserve_ids {
+            write!(f, ",{}", tile.id)?;
+        }
+        writeln!(f)?;
+    }
+    path.push("layers.txt");
+    let f = out.file(path);
+    for depth in layers.keys() {
+        writeln!(f, "{}", depth)?;
+    }
+    path.pop();
+    for (depth, layer) in layers {
+        path.push(format!("{}.txt", depth));
+        *out.file(path) = layer;
+        path.pop();
+    }
+    Ok(())
+}
+
+fn save_instances(instances: &[Instance], path: &mut PathBuf, project: &Project, out: &mut Output) -> Result<()> {
+    let mut f = Vec::new();
+    let columns =
+        if project.preserve_ids { &INSTANCE_COLUMNS[..] } else { &INSTANCE_COLUMNS[..INSTANCE_COLUMNS.len() - 1] };
+    writeln!(f, "{}", columns.join(","))?;
+
+    for instance in instances {
+        let mut code = Vec::with_capacity(instance.creation_code.len());
+        write_gml(&mut code, &instance.creation_code)?;
+        let InstanceExtra { name, xscale, yscale, blend, angle } = &instance.extra;
+        let fname = format!("{:08X}", name);
+        let has_code = !code.is_empty();
+        if has_code {
+            path.push(&fname);
+            path.set_extension("gml");
+            *out.file(path) = code;
+            path.pop();
+        }
+        write!(
+            f,
+            "{},{},{},{},{},{},{},{},{},{}",
+            project.objects.name(instance.object),
+            instance.x,
+            instance.y,
+            fname,
+            u8::from(instance.locked),
+            xscale,
+            yscale,
+            blend,
+            angle,
+            u8::from(has_code),
+        )?;
+        if project.preserve_ids {
+            write!(f, ",{}", instance.id)?;
+        }
+        writeln!(f)?;
+    }
+    path.push("instances.txt");
+    *out.file(path) = f;
+    path.pop();
+    Ok(())
+}
+
+fn save_room(room: &Room, path: &mut PathBuf, project: &Project, out: &mut Output) -> Result<()> {
+    path.push("room.txt");
+    {
+        let f = out.file(path);
+        writeln!(f, "caption={}", room.caption)?;
+        writeln!(f, "width={}", room.width)?;
+        writeln!(f, "height={}", room.height)?;
+        writeln!(f, "snap_x={}", room.snap_x)?;
+        writeln!(f, "snap_y={}", room.snap_y)?;
+        writeln!(f, "isometric={}", u8::from(room.isometric))?;
+        writeln!(f, "roomspeed={}", room.speed)?;
+        writeln!(f, "roompersistent={}", u8::from(room.persistent))?;
+        writeln!(f, "bg_color={}", room.bg_colour)?;
+        writeln!(f, "clear_screen={}", u8::from(room.clear_screen))?;
+        writeln!(f, "clear_view={}", u8::from(room.clear_view))?;
+        writeln!(f)?;
+        for (i, bg) in room.backgrounds.iter().enumerate() {
+            writeln!(f, "bg_visible{}={}", i, u8::from(bg.visible_on_start))?;
+            writeln!(f, "bg_is_foreground{}={}", i, u8::from(bg.is_foreground))?;
+            writeln!(f, "bg_source{}={}", i, project.backgrounds.name(bg.source_bg))?;
+            writeln!(f, "bg_xoffset{}={}", i, bg.xoffset)?;
+            writeln!(f, "bg_yoffset{}={}", i, bg.yoffset)?;
+            writeln!(f, "bg_tile_h{}={}", i, u8::from(bg.tile_horz))?;
+            writeln!(f, "bg_tile_v{}={}", i, u8::from(bg.tile_vert))?;
+            writeln!(f, "bg_hspeed{}={}", i, bg.hspeed)?;
+            writeln!(f, "bg_vspeed{}={}", i, bg.vspeed)?;
+            writeln!(f, "bg_stretch{}={}", i, u8::from(bg.stretch))?;
+        }
+        writeln!(f)?;
+        writeln!(f, "views_enabled={}", u8::from(room.views_enabled))?;
+        for (i, view) in room.views.iter().enumerate() {
+            writeln!(f, "view_visible{}={}", i, u8::from(view.visible))?;
+            writeln!(f, "view_xview{}={}", i, view.source_x)?;
+            writeln!(f, "view_yview{}={}", i, view.source_y)?;
+            writeln!(f, "view_wview{}={}", i, view.source_w)?;
+            writeln!(f, "view_hview{}={}", i, view.source_h)?;
+            writeln!(f, "view_xport{}={}", i, view.port_x)?;
+            writeln!(f, "view_yport{}={}", i, view.port_y)?;
+            writeln!(f, "view_wport{}={}", i, view.port_w)?;
+            writeln!(f, "view_hport{}={}", i, view.port_h)?;
+            writeln!(f, "view_fol_hbord{}={}", i, view.following_hborder)?;
+            writeln!(f, "view_fol_vbord{}={}", i, view.following_vborder)?;
+            writeln!(f, "view_fol_hspeed{}={}", i, view.following_hspeed)?;
+            writeln!(f, "view_fol_vspeed{}={}", i, view.following_vspeed)?;
+            writeln!(f, "view_fol_target{}={}", i, project.objects.name(view.following_target))?;
+        }
+        writeln!(f)?;
+        writeln!(f, "remember={}", u8::from(room.remember_room_editor_info))?;
+        writeln!(f, "editor_width={}", room.editor_width)?;
+        writeln!(f, "editor_height={}", room.editor_height)?;
+        writeln!(f, "show_grid={}", u8::from(room.show_grid))?;
+        writeln!(f, "show_objects={}", u8::from(room.show_objects))?;
+        writeln!(f, "show_tiles={}", u8::from(room.show_tiles))?;
+        writeln!(f, "show_backgrounds={}", u8::from(room.show_backgrounds))?;
+        writeln!(f, "show_foregrounds={}", u8::from(room.show_foregrounds))?;
+        writeln!(f, "show_views={}", u8::from(room.show_views))?;
+        writeln!(f, "delete_underlying_objects={}", u8::from(room.delete_underlying_objects))?;
+        writeln!(f, "delete_underlying_tiles={}", u8::from(room.delete_underlying_tiles))?;
+        writeln!(f, "tab={}", room.tab)?; // wtf is this
+        writeln!(f, "editor_x={}", room.x_position_scroll)?;
+        writeln!(f, "editor_y={}", room.y_position_scroll)?;
+        write_extra_keys(f, &room.extra_keys)?;
+    }
+    path.pop();
+    {
+        path.push("code.gml");
+        write_gml(out.file(path), &room.creation_code)?;
+        path.pop();
+    }
+
+    save_tiles(&room.tiles, path, project, out)?;
+
+    save_instances(&room.instances, path, project, out)?;
+    Ok(())
+}
+
+// a file that's its own thing as far as the manifest is concerned
+fn save_file(path: &std::path::Path, data: &[u8], transaction: &Transaction) -> Result<()> {
+    let mut out = Output::new();
+    out.data(path, data);
+    transaction.write(path, &out)
+}
+
+fn save_constants(project: &Project, path: &mut PathBuf, transaction: &Transaction) -> Result<()> {
+    path.push("constants.txt");
+    let mut out = Output::new();
+    let f = out.file(path);
+    for (name, value) in &project.constants {
+        writeln!(f, "{}={}", name, value)?;
+    }
+    transaction.write(path, &out)?;
+    path.pop();
+    Ok(())
+}
+
+fn save_settings(project: &Project, path: &mut PathBuf, transaction: &Transaction) -> Result<()> {
+    let settings = &project.settings;
+    path.push("settings");
+    save_constants(project, path, transaction)?;
+    // not the usual behaviour, but i don't feel like adding more flags than necessary
+    let has_custom_load_image = settings.has_custom_load_image && settings.custom_load_image.is_some();
+    {
+        path.push("settings.txt");
+        let mut out = Output::new();
+        let f = out.file(path);
+        writeln!(f, "fullscreen={}", u8::from(settings.fullscreen))?;
+        writeln!(f, "interpolate_pixels={}", u8::from(settings.interpolate_pixels))?;
+        writeln!(f, "dont_draw_border={}", u8::from(settings.dont_draw_border))?;
+        writeln!(f, "display_cursor={}", u8::from(settings.display_cursor))?;
+        writeln!(f, "scaling={}", settings.scaling)?;
+        writeln!(f, "allow_resize={}", u8::from(settings.allow_resize))?;
+        writeln!(f, "window_on_top={}", u8::from(settings.window_on_top))?;
+        writeln!(f, "clear_color={}", settings.clear_colour)?;
+        writeln!(f, "set_resolution={}", u8::from(settings.set_resolution))?;
+        writeln!(f, "color_depth={}", settings.colour_depth)?;
+        writeln!(f, "resolution={}", settings.resolution)?;
+        writeln!(f, "frequency={}", settings.frequency)?;
+        writeln!(f, "dont_show_buttons={}", u8::from(settings.dont_show_buttons))?;
+        writeln!(f, "vsync={}", settings.vsync_and_force_cpu & 1)?;
+        writeln!(f, "swap_creation_events={}", u8::from(settings.vsync_and_force_cpu & (1 << 31) != 0))?;
+        writeln!(f, "disable_screensaver={}", u8::from(settings.disable_screensaver))?;
+        writeln!(f, "f4_fullscreen_toggle={}", u8::from(settings.f4_fullscreen))?;
+        writeln!(f, "f1_help_menu={}", u8::from(settings.f1_help))?;
+        writeln!(f, "esc_close_game={}", u8::from(settings.esc_close))?;
+        writeln!(f, "f5_save_f6_load={}", u8::from(settings.f5_save_f6_load))?;
+        writeln!(f, "f9_screenshot={}", u8::from(settings.f9_screenshot))?;
+        writeln!(f, "treat_close_as_esc={}", u8::from(settings.treat_close_as_esc))?;
+        writeln!(f, "priority={}", settings.priority)?;
+        writeln!(f, "freeze_on_lose_focus={}", u8::from(settings.freeze_on_lose_focus))?;
+        writeln!(f, "custom_loader={}", u8::from(has_custom_load_image))?;
+        writeln!(f, "custom_bar={}", settings.loading_bar)?;
+        writeln!(f, "bar_has_bg={}", u8::from(settings.loading_background.is_some()))?;
+        writeln!(f, "bar_has_fg={}", u8::from(settings.loading_foreground.is_some()))?;
+        writeln!(f, "transparent={}", u8::from(settings.loading_transparent))?;
+        writeln!(f, "translucency={}", settings.loading_translucency)?;
+        writeln!(f, "scale_progress_bar={}", u8::from(settings.loading_progress_bar_scale))?;
+        writeln!(f, "show_error_messages={}", u8::from(settings.show_error_messages))?;
+        writeln!(f, "log_errors={}", u8::from(settings.log_errors))?;
+        writeln!(f, "always_abort={}", u8::from(settings.always_abort))?;
+        writeln!(f, "zero_uninitialized_vars={}", u8::from(settings.zero_uninitialized_vars))?;
+        writeln!(f, "error_on_uninitialized_args={}", u8::from(settings.error_on_uninitialized_args))?;
+        transaction.write(path, &out)?;
+        path.pop();
+    }
+    if settings.loading_bar == 2 {
+        if let Some(bg) = settings.loading_background.as_ref() {
+            path.push("back.bmp");
+            save_file(path, bg, transaction)?;
+            path.pop();
+        }
+        if let Some(fg) = settings.loading_foreground.as_ref() {
+            path.push("front.bmp");
+            save_file(path, fg, transaction)?;
+            path.pop();
+        }
+    }
+    if let Some(im) = settings.custom_load_image.as_ref().filter(|_| has_custom_load_image) {
+        path.push("loader.bmp");
+        save_file(path, im, transaction)?;
+        path.pop();
+    }
+    path.push("icon.ico");
+    save_file(path, &settings.icon, transaction)?;
+    path.pop();
+    {
+        path.push("extensions.txt");
+        let mut out = Output::new();
+        let f = out.file(path);
+        for name in &project.extensions {
+            writeln!(f, "{}", name)?;
+        }
+        transaction.write(path, &out)?;
+        path.pop();
+    }
+    save_game_information(&project.game_info, path, transaction)?;
+    path.pop();
+    Ok(())
+}
+
+fn save_triggers(project: &Project, path: &mut PathBuf, transaction: &Transaction) -> Result<()> {
+    path.push("triggers");
+    let triggers = &project.triggers;
+    {
+        let mut index = Vec::with_capacity(triggers.len());
+        let mut name_set = HashSet::with_capacity(triggers.len());
+        for trigger in triggers {
+            if let Some(trigger) = trigger.as_ref() {
+                let name = &trigger.name;
+                writeln!(index, "{}", name)?;
+                if let Err(p) = filename::check(name) {
+                    return Err(Error::BadTriggerName(name.clone(), p))
+                }
+                if !name_set.insert(make_unicase(name.clone())) {
+                    return Err(Error::DuplicateTrigger(name.clone()))
+                }
+            } else {
+                writeln!(index)?;
+            }
+        }
+        path.push("index.yyd");
+        save_file(path, &index, transaction)?;
+        path.pop();
+    }
+    for trigger in triggers.iter().flatten() {
+        path.push(&*file_name(&trigger.name));
+        let mut out = Output::new();
+        let mut p = path.with_extension("txt");
+        {
+            let f = out.file(&p);
+            writeln!(f, "constant={}", trigger.constant_name)?;
+            writeln!(f, "kind={}", trigger.kind)?;
+        }
+        p.set_extension("gml");
+        write_gml(out.file(&p), &trigger.condition)?;
+        transaction.write(path, &out)?;
+        path.pop();
+    }
+    path.pop();
+    Ok(())
+}
+
+// every name has to be usable as a filename, and unique ignoring case
+pub fn check_names(names: &[String]) -> Result<()> {
+    let mut name_set = HashSet::with_capacity(names.len());
+    for name in names {
+        if !name.is_empty() {
+            if let Err(p) = filename::check(name) {
+                return Err(Error::BadAssetName(name.clone(), p))
+            }
+            if !name_set.insert(make_unicase(name.clone())) {
+                return Err(Error::DuplicateAsset(name.clone()))
+            }
+        }
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn save_assets<T: Sync>(
+    bar_start: u32,
+    bar_end: u32,
+    name: &str,
+    assets: &AssetList<T>,
+    save_func: for<'a> fn(&'a T, &mut PathBuf, &Project, &mut Output<'a>) -> Result<()>,
+    project: &Project,
+    transaction: &Transaction,
+    progress: Progress,
+    path: &mut PathBuf,
+) -> Result<()> {
+    path.push(name);
+    check_names(assets.names())?;
+    {
+        let mut index = Vec::with_capacity(assets.names().len());
+        for name in assets.names() {
+            writeln!(&mut index, "{}", name)?;
+        }
+        path.push("index.yyd");
+        save_file(path, &index, transaction)?;
+        path.pop();
+    }
+    let timestamps = assets.timestamps().iter().copied().chain(std::iter::repeat(f64::MAX));
+    let timestamps = timestamps.take(assets.assets().len()).collect::<Vec<_>>();
+    let count = assets.iter().count().max(1) as u32;
+    let saved = AtomicU32::new(0);
+    let kind = name;
+    (assets.assets(), assets.names(), &timestamps).into_par_iter().try_for_each(
+        |(asset, name, &timestamp)| -> Result<()> {
+            if let Some(asset) = asset {
+                let p = path.join(&*file_name(name));
+                // assets that never had a timestamp count as changed now
+                let timestamp = if timestamp == f64::MAX || timestamp == 0.0 { now() } else { timestamp };
+                transaction.timestamp(kind, name, timestamp);
+                let mut out = Output::new();
+                save_func(asset, &mut p.clone(), project, &mut out)?;
+                transaction.write(&p, &out)?;
+                let saved = saved.fetch_add(1, Ordering::Relaxed) + 1;
+                progress(bar_start + saved * (bar_end - bar_start) / count);
+            }
+            Ok(())
+        },
+    )?;
+    path.push("tree.yyd");
+    {
+        let mut out = Output::new();
+        write_tree_children(&assets.tree, &assets.names, &mut String::new(), out.file(path))?;
+        transaction.write(path, &out)?;
+    }
+    path.pop();
+    path.pop();
+    Ok(())
+}
+
+fn save_included_files(project: &Project, path: &mut PathBuf, transaction: &Transaction) -> Result<()> {
+    path.push("datafiles");
+    let files = &project.included_files;
+    {
+        let mut index = Vec::with_capacity(files.len());
+        let mut names_set = HashSet::with_capacity(files.len());
+        for file in files {
+            let name = &file.file_name;
+            writeln!(index, "{}", name)?;
+
+            if let Err(p) = filename::check(name) {
+                return Err(Error::BadIncludedFileName(name.clone(), p))
+            }
+            if !names_set.insert(name) {
+                return Err(Error::DuplicateIncludedFile(name.clone()))
+            }
+        }
+        path.push("index.yyd");
+        save_file(path, &index, transaction)?;
+        path.pop();
+    }
+    for file in files {
+        let mut out = Output::new();
+        if file.data_exists {
+            path.push("include");
+            path.push(&*file_name(&file.file_name));
+            if let Some(data) = file.data.as_ref().filter(|_| file.stored_in_gmk) {
+                out.data(path, data);
+            } else if !transaction::exists(path) {
+                // try to copy it to gmk dir if not already done
+                create_dirs(path.parent().unwrap())?;
+                std::fs::copy(&file.source_path, &path).map_err(|e| Error::FileIoError(e, path.to_path_buf()))?;
+            }
+            path.pop();
+            path.pop();
+        }
+        path.push(file_name(&file.file_name).into_owned() + ".txt");
+        let f = out.file(path);
+        writeln!(f, "store={}", u8::from(file.stored_in_gmk))?;
+        writeln!(f, "free={}", u8::from(file.free_memory))?;
+        writeln!(f, "overwrite={}", u8::from(file.overwrite_file))?;
+        writeln!(f, "remove={}", u8::from(file.remove_at_end))?;
+        writeln!(f, "export={}", file.export_setting)?;
+        if file.export_setting == 3 {
+            writeln!(f, "export_folder={}", file.export_custom_folder)?;
+        }
+        path.pop();
+        transaction.write(&path.join(&*file_name(&file.file_name)), &out)?;
+    }
+    path.pop();
+    Ok(())
+}
+
+fn save_game_information(info: &GameInfo, path: &mut PathBuf, transaction: &Transaction) -> Result<()> {
+    path.push("game_information.txt");
+    let mut out = Output::new();
+    {
+        let f = out.file(path);
+        writeln!(f, "color={}", info.colour)?;
+        writeln!(f, "new_window={}", u8::from(info.new_window))?;
+        writeln!(f, "caption={}", info.caption)?;
+        writeln!(f, "left={}", info.left)?;
+        writeln!(f, "top={}", info.top)?;
+        writeln!(f, "width={}", info.width)?;
+        writeln!(f, "height={}", info.height)?;
+        writeln!(f, "border={}", u8::from(info.border))?;
+        writeln!(f, "resizable={}", u8::from(info.resizable))?;
+        writeln!(f, "window_on_top={}", u8::from(info.window_on_top))?;
+        writeln!(f, "freeze_game={}", u8::from(info.freeze_game))?;
+    }
+    path.set_extension("rtf");
+    out.data(path, &info.rtf);
+    path.set_extension("");
+    transaction.write(path, &out)?;
+    path.pop();
+    Ok(())
+}
+
+fn write_tree_children<F: Write>(nodes: &[TreeNode], names: &[String], tabs: &mut String, f: &mut F) -> Result<()> {
+    for node in nodes {
+        match node {
+            TreeNode::Group(name, children) => {
+                writeln!(f, "{}+{}", tabs, name)?;
+                tabs.push('\t');
+                write_tree_children(children, names, tabs, f)?;
+                tabs.pop();
+            },
+            TreeNode::Asset(index) => writeln!(
+                f,
+                "{}|{}",
+                tabs,
+                names.get(*index).ok_or_else(|| Error::Other(format!("failed to save resource tree {}", index)))?
+            )?,
+        }
+    }
+    Ok(())
+}
+
+fn save_icon_cache(project: &Project, path: &mut PathBuf, transaction: &Transaction) -> Result<()> {
+    const BMP_HEADER: &[u8] = include_bytes!("../../assets/thumb_header.dat");
+    const BMP_SIZE: usize = 16 * 16 * 4 + BMP_HEADER.len();
+    fn save_frame(frame: &Frame, name: &str, path: &std::path::Path, transaction: &Transaction) -> Result<()> {
+        if frame.width == 0 || frame.height == 0 {
+            return Ok(())
+        }
+        let mut path = path.join(&*file_name(name));
+        path.set_extension("bmp");
+        let mut out = vec![0; BMP_SIZE];
+        out[..BMP_HEADER.len()].copy_from_slice(BMP_HEADER);
+        frame.thumb(&mut out[BMP_HEADER.len()..], true, [255, 255, 255]);
+        save_file(&path, &out, transaction)
+    }
+    path.push("cache");
+    path.push("sprites");
+    (&project.sprites.assets, &project.sprites.names).into_par_iter().try_for_each(|(sprite, name)| -> Result<()> {
+        if let Some(frame) = sprite.as_ref().and_then(|s| s.frames.first()) {
+            save_frame(frame, name, path, transaction)?;
+        }
+        Ok(())
+    })?;
+    path.pop();
+    path.push("backgrounds");
+    (&project.backgrounds.assets, &project.backgrounds.names).into_par_iter().try_for_each(
+        |(bg, name)| -> Result<()> {
+            if let Some(bg) = bg {
+                save_frame(&bg.frame, name, path, transaction)?;
+            }
+            Ok(())
+        },
+    )?;
+    path.pop();
+    path.pop();
+    Ok(())
+}
+
+// give instances and tiles names if they don't already have one, unique across the whole project
+// rooms that got new names count as changed
+pub fn name_instances(project: &mut Project) {
+    let mut namer = Namer::new(
+        project
+            .rooms
+            .assets
+            .iter()
+            .flatten()
+            .flat_map(|r| r.instances.iter().map(|i| i.extra.name).chain(r.tiles.iter().map(|t| t.extra.name)))
+            .collect(),
+    );
+    let (objects, backgrounds) = (&project.objects, &project.backgrounds);
+    let rooms = &mut project.rooms;
+    for ((room, name), timestamp) in rooms.assets.iter_mut().zip(&rooms.names).zip(&mut rooms.timestamps) {
+        let Some(room) = room else { continue };
+        namer.room(name);
+        for inst in room.instances.iter_mut().filter(|i| i.extra.name == 0) {
+            *timestamp = now();
+            inst.extra.name = namer.name(objects.name(inst.object), inst.x, inst.y);
+        }
+        for tile in room.tiles.iter_mut().filter(|t| t.extra.name == 0) {
+            *timestamp = now();
+            tile.extra.name = namer.name(backgrounds.name(tile.source_bg), tile.x, tile.y);
+        }
+    }
+}
+
+pub fn save_gmk(project: &mut Project, path: &mut PathBuf) -> Result<()> {
+    save_project(project, path, false)
+}
+
+// only rewrites assets whose files would come out different to the last save's, or that something else has
+// changed on disk since then
+pub fn smart_save_gmk(project: &mut Project, path: &mut PathBuf) -> Result<()> {
+    save_project(project, path, true)
+}
+
+// everything gets written to a staging folder first so a failed save leaves the project as it was
+fn save_project(project: &mut Project, path: &mut PathBuf, smart_save: bool) -> Result<()> {
+    let transaction = Transaction::begin(path, smart_save, project.png_effort)?;
+    write_project(project, &mut transaction.stage(path), &transaction, &|_| ())?;
+    transaction.commit()?;
+    path.pop();
+    Ok(())
+}
+
+// writes the project into a transaction that's already begun, path being where the .gm82 goes in its stage
+pub fn write_project(
+    project: &mut Project,
+    path: &mut PathBuf,
+    transaction: &Transaction,
+    progress: Progress,
+) -> Result<()> {
+    name_instances(project);
+    let project = &*project;
+    let settings = &project.settings;
+    // check if we have any assets to save
+    let has_backgrounds = project.backgrounds.any();
+    let has_datafiles = !project.included_files.is_empty();
+    let has_fonts = project.fonts.any();
+    let has_objects = project.objects.any();
+    let has_paths = project.paths.any();
+    let has_scripts = project.scripts.any();
+    let has_sounds = project.sounds.any();
+    let has_sprites = project.sprites.any();
+    let has_timelines = project.timelines.any();
+    let has_triggers = !project.triggers.is_empty();
+    {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            create_dirs(parent)?;
+        }
+        // some stuff to go in the main gmk
+        let mut f = open_file(path)?;
+        writeln!(f, "gm82_version={}", GM82_VERSION)?;
+        writeln!(f, "gameid={}", project.game_id)?;
+        writeln!(f)?;
+        writeln!(f, "info_author={}", settings.info_author)?;
+        writeln!(f, "info_version={}", settings.info_version)?;
+        writeln!(f, "info_information={}", delimit(&settings.info_information))?;
+        writeln!(f)?;
+        writeln!(f, "exe_company={}", settings.exe_company)?;
+        writeln!(f, "exe_product={}", settings.exe_product)?;
+        writeln!(f, "exe_copyright={}", settings.exe_copyright)?;
+        writeln!(f, "exe_description={}", settings.exe_description)?;
+        writeln!(
+            f,
+            "exe_version={}.{}.{}.{}",
+            settings.version_major, settings.version_minor, settings.version_release, settings.version_build
+        )?;
+        writeln!(f)?;
+        writeln!(f, "has_backgrounds={}", u8::from(has_backgrounds))?;
+        writeln!(f, "has_datafiles={}", u8::from(has_datafiles))?;
+        writeln!(f, "has_fonts={}", u8::from(has_fonts))?;
+        writeln!(f, "has_objects={}", u8::from(has_objects))?;
+        writeln!(f, "has_paths={}", u8::from(has_paths))?;
+        writeln!(f, "has_scripts={}", u8::from(has_scripts))?;
+        writeln!(f, "has_sounds={}", u8::from(has_sounds))?;
+        writeln!(f, "has_sprites={}", u8::from(has_sprites))?;
+        writeln!(f, "has_timelines={}", u8::from(has_timelines))?;
+        writeln!(f, "has_triggers={}", u8::from(has_triggers))?;
+        if project.png_effort != PngEffort::Default {
+            writeln!(f)?;
+            writeln!(f, "png_effort={}", project.png_effort as u8)?;
+        }
+        if project.preserve_ids {
+            writeln!(f)?;
+            writeln!(f, "preserve_ids=1")?;
+            writeln!(f, "last_instance_id={}", project.last_instance_id)?;
+            writeln!(f, "last_tile_id={}", project.last_tile_id)?;
+        }
+        f.flush()?;
+    }
+    path.pop();
+    progress(5);
+    save_settings(project, path, transaction)?;
+    progress(10);
+    if has_triggers {
+        save_triggers(project, path, transaction)?;
+    }
+    progress(15);
+    if has_sounds {
+        save_assets(15, 30, "sounds", &project.sounds, save_sound, project, transaction, progress, path)?;
+    }
+    if has_sprites {
+        save_assets(30, 55, "sprites", &project.sprites, save_sprite, project, transaction, progress, path)?;
+    }
+    if has_backgrounds {
+        save_assets(
+            55,
+            65,
+            "backgrounds",
+            &project.backgrounds,
+            save_background,
+            project,
+            transaction,
+            progress,
+            path,
+        )?;
+    }
+    if has_paths {
+        save_assets(65, 70, "paths", &project.paths, save_path, project, transaction, progress, path)?;
+    }
+    if has_scripts {
+        save_assets(70, 75, "scripts", &project.scripts, save_script, project, transaction, progress, path)?;
+    }
+    if has_fonts {
+        save_assets(75, 80, "fonts", &project.fonts, save_font, project, transaction, progress, path)?;
+    }
+    if has_timelines {
+        save_assets(80, 85, "timelines", &project.timelines, save_timeline, project, transaction, progress, path)?;
+    }
+    if has_objects {
+        save_assets(85, 90, "objects", &project.objects, save_object, project, transaction, progress, path)?;
+    }
+    save_assets(90, 95, "rooms", &project.rooms, save_room, project, transaction, progress, path)?;
+    if has_datafiles {
+        save_included_files(project, path, transaction)?;
+    }
+    progress(95);
+    save_icon_cache(project, path, transaction)?;
+    progress(100);
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{
+        library::{ActionDefinition, ActionLibraries, ActionLibrary},
+        load::{load_gmk, load_gmk_with_names_on_disk},
+        prune::list_dir,
+    };
+
+    // a folder of its own for each test, since they run at the same time
+    pub fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("gm82file-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    // a code action and one with a sprite and a string parameter
+    pub fn libs() -> ActionLibraries {
+        let code = ActionDefinition { id: 603, kind: 7, apply_to: true, execution_type: 2, ..Default::default() };
+        let mut set_sprite =
+            ActionDefinition { id: 541, apply_to: true, arg_count: 2, execution_type: 1, ..Default::default() };
+        set_sprite.function_name = "action_sprite_set".into();
+        set_sprite.arg_types[0] = 5;
+        ActionLibraries(vec![ActionLibrary { id: 1, actions: vec![code, set_sprite], ..Default::default() }])
+    }
+
+    fn action(libs: &ActionLibraries, id: u32, params: &[&str]) -> Action {
+        let mut action = Action { lib_id: 1, id, ..Default::default() };
+        action.fill_in(libs.get(1, id).unwrap());
+        for (param, s) in action.param_strings.iter_mut().zip(params) {
+            *param = s.to_string();
+        }
+        action
+    }
+
+    fn list<T>(assets: Vec<T>, names: &[&str]) -> AssetList<T> {
+        AssetList {
+            tree: (0..assets.len()).map(TreeNode::Asset).collect(),
+            assets: assets.into_iter().map(Some).collect(),
+            names: names.iter().map(|s| s.to_string()).collect(),
+            timestamps: (0..names.len()).map(|i| 45000.0 + i as f64).collect(),
+        }
+    }
+
+    pub fn project() -> Project {
+        let libs = libs();
+        let frame = Frame { width: 2, height: 1, data: vec![1, 2, 3, 255, 4, 5, 6, 0] };
+        let mut object = Object::default();
+        object.events[0].insert(0, Event { actions: vec![action(&libs, 603, &["x = 1\r\ny = \"two\"\r\n"])] });
+        object.events[4].insert(0, Event { actions: vec![action(&libs, 541, &["0", "a\nb\\c"])] });
+        let room = Room {
+            caption: "first room".into(),
+            instances: vec![Instance {
+                x: 16,
+                y: 32,
+                id: 100001,
+                creation_code: "z = 3\r\n".into(),
+                ..Default::default()
+            }],
+            tiles: vec![Tile { source_bg: 0, width: 2, height: 1, depth: 1000000, id: 10000001, ..Default::default() }],
+            ..Default::default()
+        };
+        let mut project = Project {
+            game_id: 1234,
+            constants: vec![("LIMIT".into(), "10".into())],
+            extensions: vec!["some extension".into()],
+            triggers: vec![Some(Trigger {
+                name: "on_thing".into(),
+                condition: "return 1\r\n".into(),
+                ..Default::default()
+            })],
+            sounds: list(
+                vec![Sound { extension: ".wav".into(), volume: 0.5, data: Some(vec![0, 1, 2]), ..Default::default() }],
+                &["snd"],
+            ),
+            sprites: list(vec![Sprite { frames: vec![frame.clone()], bbox_right: 1, ..Default::default() }], &["spr"]),
+            backgrounds: list(vec![Background { frame, ..Default::default() }], &["bg"]),
+            paths: list(
+                vec![Path {
+                    points: vec![PathPoint { x: 0.0, y: 0.5, speed: 100.0 }, PathPoint { x: 8.0, y: 8.0, speed: 50.0 }],
+                    path_editor_room_background: 0,
+                    extra_keys: vec![("x_tool".into(), "kept".into())],
+                    ..Default::default()
+                }],
+                &["pth"],
+            ),
+            scripts: list(vec![Script { source: "return argument0\r\n".into() }], &["scr"]),
+            fonts: list(vec![Font { sys_name: "Arial".into(), size: 12, ..Default::default() }], &["fnt"]),
+            timelines: list(
+                vec![Timeline { moments: vec![(5, Event { actions: vec![action(&libs, 603, &["a = 1\r\n"])] })] }],
+                &["tl"],
+            ),
+            objects: list(vec![object], &["obj"]),
+            rooms: list(vec![room], &["rm"]),
+            included_files: vec![IncludedFile {
+                file_name: "data.txt".into(),
+                data_exists: true,
+                source_length: 5,
+                stored_in_gmk: true,
+                data: Some(b"hello".to_vec()),
+                ..Default::default()
+            }],
+            // the ids the IDE would have given out so far
+            last_instance_id: 100001,
+            last_tile_id: 10000001,
+            preserve_ids: true,
+            ..Default::default()
+        };
+        project.settings.icon = vec![0, 0, 1, 0];
+        project.settings.info_information = "line one\r\nline two".into();
+        project.game_info.caption = "About".into();
+        project.game_info.rtf = b"{\\rtf1 hi}".to_vec();
+        project.sprites.tree = vec![TreeNode::Group("characters".into(), vec![TreeNode::Asset(0)])];
+        project
+    }
+
+    fn round_trip(project: &mut Project, name: &str) -> Project {
+        let path = test_dir(name).join("game.gm82");
+        save_gmk(project, &mut path.clone()).unwrap();
+        let mut loaded = load_gmk(path.clone(), &libs(), false).unwrap();
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+        // included files say where they were loaded from
+        for file in &mut loaded.included_files {
+            file.source_path.clear();
+        }
+        loaded
+    }
+
+    #[test]
+    fn project_survives_a_round_trip() {
+        let mut project = project();
+        let loaded = round_trip(&mut project, "round-trip");
+        assert_eq!(loaded, project);
+    }
+
+    #[test]
+    fn saving_names_new_instances() {
+        let mut project = project();
+        assert_eq!(project.rooms.assets[0].as_ref().unwrap().instances[0].extra.name, 0);
+        let loaded = round_trip(&mut project, "names");
+        let room = loaded.rooms.assets[0].as_ref().unwrap();
+        assert_ne!(room.instances[0].extra.name, 0);
+        assert_ne!(room.tiles[0].extra.name, room.instances[0].extra.name);
+    }
+
+    #[test]
+    fn names_clashing_across_rooms_get_new_ones() {
+        let mut project = project();
+        name_instances(&mut project);
+        let mut room = project.rooms.assets[0].clone().unwrap();
+        room.instances[0].id = 100002;
+        room.tiles[0].id = 10000002;
+        let name = room.instances[0].extra.name;
+        project.rooms.assets.push(Some(room));
+        project.rooms.names.push("rm2".into());
+        project.rooms.timestamps.push(45000.0);
+        project.rooms.tree.push(TreeNode::Asset(1));
+        (project.last_instance_id, project.last_tile_id) = (100002, 10000002);
+        let loaded = round_trip(&mut project, "clashing-names");
+        let rooms = loaded.rooms.assets.iter().flatten().collect::<Vec<_>>();
+        assert_eq!(rooms[0].instances[0].extra.name, name);
+        assert_ne!(rooms[1].instances[0].extra.name, name);
+        assert_ne!(rooms[1].tiles[0].extra.name, rooms[0].tiles[0].extra.name);
+    }
+
+    #[test]
+    fn loaded_project_saves_the_same() {
+        let mut project = project();
+        let mut loaded = round_trip(&mut project, "stable-1");
+        let reloaded = round_trip(&mut loaded, "stable-2");
+        assert_eq!(reloaded, loaded);
+    }
+
+    #[test]
+    fn older_projects_load_text_as_it_was() {
+        let mut project = project();
+        // version 7 wrote these as they are, now they'd be read as escapes
+        project.scripts.names[0] = "scr%u{0025}".into();
+        project.scripts.assets[0].as_mut().unwrap().source = "a = \"\\u{D800}\"\r\n".into();
+        let path = test_dir("version-7").join("game.gm82");
+        save_gmk(&mut project, &mut path.clone()).unwrap();
+        let gm82 = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, gm82.replace(&format!("gm82_version={GM82_VERSION}"), "gm82_version=7")).unwrap();
+        let scripts = path.with_file_name("scripts");
+        std::fs::rename(scripts.join("scr%u{0025}u{0025}.gml"), scripts.join("scr%u{0025}.gml")).unwrap();
+        let loaded = load_gmk(path.clone(), &libs(), false).unwrap();
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+        assert_eq!(loaded.scripts.names[0], "scr%u{0025}");
+        assert_eq!(loaded.scripts.assets[0].as_ref().unwrap().source, "a = \"\\u{005C}u{D800}\"\r\n");
+    }
+
+    #[test]
+    fn smart_save_only_skips_files_left_as_they_were() {
+        let mut project = project();
+        let path = test_dir("smart-save").join("game.gm82");
+        save_gmk(&mut project, &mut path.clone()).unwrap();
+        let (script, frame) =
+            (path.with_file_name("scripts").join("scr.gml"), path.with_file_name("sprites/spr/0.png"));
+        let saved = std::fs::read(&script).unwrap();
+        let modified = |p: &std::path::Path| p.metadata().unwrap().modified().unwrap();
+        let frame_modified = modified(&frame);
+        std::fs::write(&script, "changed").unwrap();
+        smart_save_gmk(&mut project, &mut path.clone()).unwrap();
+        let (after, frame_after) = (std::fs::read(&script).unwrap(), modified(&frame));
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+        assert_eq!(after, saved);
+        assert_eq!(frame_after, frame_modified);
+    }
+
+    #[test]
+    fn smart_save_rewrites_what_refers_to_a_renamed_asset() {
+        let mut project = project();
+        let mut set_sprite = action(&libs(), 541, &["0", ""]);
+        set_sprite.applies_to = 0;
+        project.timelines.assets[0].as_mut().unwrap().moments[0].1.actions.push(set_sprite);
+        project.objects.assets[0].as_mut().unwrap().sprite_index = 0;
+        project.rooms.assets[0].as_mut().unwrap().instances[0].object = 0;
+        let path = test_dir("dependencies").join("game.gm82");
+        save_gmk(&mut project, &mut path.clone()).unwrap();
+        let renamed = |project: &mut Project, files: &[&str]| {
+            smart_save_gmk(project, &mut path.clone()).unwrap();
+            files.iter().map(|f| std::fs::read_to_string(path.with_file_name(f)).unwrap()).collect::<Vec<_>>()
+        };
+        // paths name their room
+        project.rooms.names[0] = "rm2".into();
+        let [path_txt] = &renamed(&mut project, &["paths/pth/path.txt"])[..] else { unreachable!() };
+        // objects and actions in events and moments name sprites
+        project.sprites.names[0] = "spr2".into();
+        let [obj_txt, obj_gml, tl_gml] =
+            &renamed(&mut project, &["objects/obj.txt", "objects/obj.gml", "timelines/tl.gml"])[..]
+        else {
+            unreachable!()
+        };
+        // actions apply to objects and rooms place them
+        project.objects.names[0] = "obj2".into();
+        let [tl_applies, instances] = &renamed(&mut project, &["timelines/tl.gml", "rooms/rm2/instances.txt"])[..]
+        else {
+            unreachable!()
+        };
+        // and tile their backgrounds
+        project.backgrounds.names[0] = "bg2".into();
+        let [tiles] = &renamed(&mut project, &["rooms/rm2/1000000.txt"])[..] else { unreachable!() };
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+        assert!(path_txt.contains("background=rm2\n"));
+        assert!(obj_txt.contains("sprite=spr2\n"));
+        assert!(obj_gml.contains("spr2"));
+        assert!(tl_gml.contains("spr2"));
+        assert!(tl_applies.contains("applies_to=obj2\n"));
+        assert!(instances.contains("\nobj2,"));
+        assert!(tiles.contains("\nbg2,"));
+    }
+
+    #[test]
+    fn assets_can_take_the_names_their_files_have() {
+        let mut project = project();
+        project.objects.assets[0].as_mut().unwrap().sprite_index = 0;
+        project.rooms.assets[0].as_mut().unwrap().instances[0].object = 0;
+        let path = test_dir("names-on-disk").join("game.gm82");
+        save_gmk(&mut project, &mut path.clone()).unwrap();
+        let dir = path.parent().unwrap();
+        let rename = |from: &str, to: &str| std::fs::rename(dir.join(from), dir.join(to)).unwrap();
+        rename("sprites/spr", "sprites/Spr");
+        rename("objects/obj.txt", "objects/Obj.txt");
+        // the object's files don't agree yet
+        let disagreed = load_gmk_with_names_on_disk(path.clone(), &libs()).is_err();
+        rename("objects/obj.gml", "objects/Obj.gml");
+        let failed = load_gmk(path.clone(), &libs(), false).is_err();
+        let mut loaded = load_gmk_with_names_on_disk(path.clone(), &libs()).unwrap();
+        save_gmk(&mut loaded, &mut path.clone()).unwrap();
+        let reloaded = load_gmk(path.clone(), &libs(), false).unwrap();
+        let objects = list_dir(&dir.join("objects")).unwrap().into_iter().map(|(e, _)| e).collect::<HashSet<_>>();
+        let _ = std::fs::remove_dir_all(dir);
+        assert!(disagreed && failed);
+        assert_eq!((&reloaded.sprites.names[0][..], &reloaded.objects.names[0][..]), ("Spr", "Obj"));
+        assert_eq!(
+            reloaded.objects.assets[0].as_ref().unwrap().events,
+            project.objects.assets[0].as_ref().unwrap().events
+        );
+        assert_eq!(reloaded.rooms.assets[0].as_ref().unwrap().instances[0].object, 0);
+        assert_eq!(objects, ["Obj.txt", "Obj.gml", "index.yyd", "tree.yyd"].map(String::from).into());
+    }
+
+    #[test]
+    fn smart_save_after_loading_skips_what_didnt_change() {
+        let mut project = project();
+        let path = test_dir("load-then-smart-save").join("game.gm82");
+        save_gmk(&mut project, &mut path.clone()).unwrap();
+        let files = ["scripts/scr.gml", "objects/obj.txt", "sprites/spr/0.png", "rooms/rm/instances.txt"]
+            .map(|f| path.with_file_name(f));
+        let modified = |p: &std::path::Path| p.metadata().unwrap().modified().unwrap();
+        let before = files.iter().map(|f| modified(f)).collect::<Vec<_>>();
+        let mut loaded = load_gmk(path.clone(), &libs(), false).unwrap();
+        smart_save_gmk(&mut loaded, &mut path.clone()).unwrap();
+        let after = files.iter().map(|f| modified(f)).collect::<Vec<_>>();
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+        assert_eq!(after, before);
+    }
+
+    #[test]
+    fn failed_save_keeps_what_the_last_one_left() {
+        let mut project = project();
+        let path = test_dir("failed-save").join("game.gm82");
+        save_gmk(&mut project, &mut path.clone()).unwrap();
+        let work_dir = path.with_file_name(".gm82save");
+        let manifest = std::fs::read(work_dir.join("manifest")).unwrap();
+        let timestamps = transaction::read_timestamps(&path);
+        // included files go after the assets, so this fails partway through
+        project.included_files.push(project.included_files[0].clone());
+        project.sprites.timestamps[0] += 1.0;
+        let failed = save_gmk(&mut project, &mut path.clone()).is_err();
+        let (manifest_after, timestamps_after) =
+            (std::fs::read(work_dir.join("manifest")).ok(), transaction::read_timestamps(&path));
+        let left = list_dir(&work_dir).unwrap().into_iter().map(|(e, _)| e).collect::<HashSet<_>>();
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+        assert!(failed);
+        assert_eq!(manifest_after, Some(manifest));
+        assert_eq!(timestamps_after, timestamps);
+        assert_eq!(left, [".gitignore", "manifest", "timestamps"].map(String::from).into());
+    }
+
+    #[test]
+    fn timestamps_stay_out_of_the_project_files() {
+        let mut project = project();
+        let path = test_dir("timestamps").join("game.gm82");
+        save_gmk(&mut project, &mut path.clone()).unwrap();
+        project.sprites.timestamps[0] += 1.0;
+        project.scripts.timestamps[0] += 1.0;
+        let before = std::fs::read(path.with_file_name("sprites").join("spr").join("sprite.txt")).unwrap();
+        save_gmk(&mut project, &mut path.clone()).unwrap();
+        let after = std::fs::read(path.with_file_name("sprites").join("spr").join("sprite.txt")).unwrap();
+        let loaded = load_gmk(path.clone(), &libs(), false).unwrap();
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+        assert_eq!(before, after);
+        assert_eq!(loaded.sprites.timestamps, project.sprites.timestamps);
+        assert_eq!(loaded.scripts.timestamps, project.scripts.timestamps);
+    }
+}
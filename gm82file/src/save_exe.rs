@@ -0,0 +1,980 @@
+use crate::{asset::*, library::ActionLibraries, Error, Result};
+use byteorder::{WriteBytesExt, LE};
+use flate2::{write::ZlibEncoder, Compression};
+use rayon::prelude::*;
+use std::{collections::HashSet, io, io::Write};
+
+pub const GMK_MAGIC: u32 = 1234321;
+pub const GMK_VERSION: u32 = 810;
+
+pub trait GetAssetList: Sized + Sync {
+    fn get_asset_list(project: &Project) -> &AssetList<Self>;
+    fn save(&self, exe: bool, project: &Project, libs: &ActionLibraries, out: impl Write) -> io::Result<()>;
+    fn write_additional(_project: &Project, _out: impl Write) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl GetAssetList for Sprite {
+    fn get_asset_list(project: &Project) -> &AssetList<Self> {
+        &project.sprites
+    }
+
+    fn save(&self, exe: bool, _: &Project, _: &ActionLibraries, mut out: impl Write) -> io::Result<()> {
+        out.write_u32::<LE>(800)?;
+        out.write_i32::<LE>(self.origin_x)?;
+        out.write_i32::<LE>(self.origin_y)?;
+        out.write_u32::<LE>(self.frames.len() as u32)?;
+        for frame in &self.frames {
+            save_frame(frame, &mut out)?;
+        }
+        if !exe {
+            out.write_u32::<LE>(self.collision_shape)?;
+            out.write_u32::<LE>(self.alpha_tolerance)?;
+        }
+        out.write_u32::<LE>(self.per_frame_colliders.into())?;
+        if !exe {
+            out.write_u32::<LE>(self.bbox_type)?;
+            out.write_i32::<LE>(self.bbox_left)?;
+            out.write_i32::<LE>(self.bbox_right)?;
+            out.write_i32::<LE>(self.bbox_bottom)?;
+            out.write_i32::<LE>(self.bbox_top)?;
+        } else if !self.frames.is_empty() {
+            if !self.per_frame_colliders {
+                write_mask(&make_mask(self, &self.frames), &mut out)?;
+            } else {
+                for frame in &self.frames {
+                    write_mask(&make_mask(self, std::slice::from_ref(frame)), &mut out)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Mask {
+    width: u32,
+    height: u32,
+    // left, top, right, bottom
+    bbox: [i32; 4],
+    data: Vec<bool>,
+}
+
+fn in_shape(shape: u32, bbox: [i32; 4], x: i32, y: i32) -> bool {
+    let [left, top, right, bottom] = bbox;
+    if x < left || x > right || y < top || y > bottom {
+        return false
+    }
+    let xc = f64::from(left + right) / 2.0;
+    let yc = f64::from(top + bottom) / 2.0;
+    let a = f64::from(right - left + 1) / 2.0;
+    let b = f64::from(bottom - top + 1) / 2.0;
+    let (dx, dy) = ((f64::from(x) - xc) / a, (f64::from(y) - yc) / b);
+    match shape {
+        // disk
+        2 => dx * dx + dy * dy <= 1.0,
+        // diamond
+        3 => dx.abs() + dy.abs() <= 1.0,
+        _ => true,
+    }
+}
+
+// merges every frame into one collision mask, like the IDE does when building
+fn make_mask(sprite: &Sprite, frames: &[Frame]) -> Mask {
+    let (width, height) = (frames[0].width, frames[0].height);
+    let (w, h) = (width as usize, height as usize);
+    let mut solid = vec![false; w * h];
+    for frame in frames {
+        let data = frame.get_data();
+        for y in 0..h.min(frame.height as usize) {
+            for x in 0..w.min(frame.width as usize) {
+                let alpha = data[(y * frame.width as usize + x) * 4 + 3];
+                solid[y * w + x] |= u32::from(alpha) > sprite.alpha_tolerance;
+            }
+        }
+    }
+    let full = [0, 0, width as i32 - 1, height as i32 - 1];
+    let bbox = match sprite.bbox_type {
+        // automatic
+        0 => solid.iter().enumerate().filter(|(_, s)| **s).fold(None, |bbox: Option<[i32; 4]>, (i, _)| {
+            let (x, y) = ((i % w) as i32, (i / w) as i32);
+            Some(match bbox {
+                Some([l, t, r, b]) => [x.min(l), y.min(t), x.max(r), y.max(b)],
+                None => [x, y, x, y],
+            })
+        }),
+        // manual
+        2 => Some([
+            sprite.bbox_left.clamp(0, full[2]),
+            sprite.bbox_top.clamp(0, full[3]),
+            sprite.bbox_right.clamp(0, full[2]),
+            sprite.bbox_bottom.clamp(0, full[3]),
+        ]),
+        _ => None,
+    }
+    .unwrap_or(full);
+    let data = solid
+        .iter()
+        .enumerate()
+        .map(|(i, &s)| {
+            let (x, y) = ((i % w) as i32, (i / w) as i32);
+            (sprite.collision_shape != 0 || s) && in_shape(sprite.collision_shape, bbox, x, y)
+        })
+        .collect();
+    Mask { width, height, bbox, data }
+}
+
+fn write_mask(mask: &Mask, mut out: impl Write) -> io::Result<()> {
+    out.write_u32::<LE>(800)?;
+    out.write_u32::<LE>(mask.width)?;
+    out.write_u32::<LE>(mask.height)?;
+    out.write_i32::<LE>(mask.bbox[0])?;
+    out.write_i32::<LE>(mask.bbox[2])?;
+    out.write_i32::<LE>(mask.bbox[3])?;
+    out.write_i32::<LE>(mask.bbox[1])?;
+    for &solid in &mask.data {
+        out.write_u32::<LE>(solid.into())?;
+    }
+    Ok(())
+}
+
+impl GetAssetList for Background {
+    fn get_asset_list(project: &Project) -> &AssetList<Self> {
+        &project.backgrounds
+    }
+
+    fn save(&self, exe: bool, _: &Project, _: &ActionLibraries, mut out: impl Write) -> io::Result<()> {
+        out.write_u32::<LE>(710)?;
+        if !exe {
+            out.write_u32::<LE>(self.is_tileset.into())?;
+            out.write_u32::<LE>(self.tile_width)?;
+            out.write_u32::<LE>(self.tile_height)?;
+            out.write_u32::<LE>(self.h_offset)?;
+            out.write_u32::<LE>(self.v_offset)?;
+            out.write_u32::<LE>(self.h_sep)?;
+            out.write_u32::<LE>(self.v_sep)?;
+        }
+        save_frame(&self.frame, out)?;
+        Ok(())
+    }
+}
+
+impl GetAssetList for Path {
+    fn get_asset_list(project: &Project) -> &AssetList<Self> {
+        &project.paths
+    }
+
+    fn save(&self, exe: bool, _: &Project, _: &ActionLibraries, mut out: impl Write) -> io::Result<()> {
+        out.write_u32::<LE>(530)?;
+        out.write_u32::<LE>(self.connection)?;
+        out.write_u32::<LE>(self.closed.into())?;
+        out.write_u32::<LE>(self.precision)?;
+        if !exe {
+            out.write_u32::<LE>(self.path_editor_room_background as _)?;
+            out.write_u32::<LE>(self.snap_x)?;
+            out.write_u32::<LE>(self.snap_y)?;
+        }
+        out.write_u32::<LE>(self.points.len() as _)?;
+        for p in &self.points {
+            out.write_f64::<LE>(p.x)?;
+            out.write_f64::<LE>(p.y)?;
+            out.write_f64::<LE>(p.speed)?;
+        }
+        Ok(())
+    }
+}
+
+impl GetAssetList for Script {
+    fn get_asset_list(project: &Project) -> &AssetList<Self> {
+        &project.scripts
+    }
+
+    fn save(&self, _exe: bool, _: &Project, _: &ActionLibraries, mut out: impl Write) -> io::Result<()> {
+        out.write_u32::<LE>(800)?;
+        write_string(&self.source, out)?;
+        Ok(())
+    }
+}
+
+fn write_event(event: &Event, libs: &ActionLibraries, mut out: impl Write) -> io::Result<()> {
+    out.write_u32::<LE>(400)?;
+    out.write_u32::<LE>(event.actions.len() as _)?;
+    for action in &event.actions {
+        // define action from library, which check_actions made sure has it
+        let mut defined;
+        let action = match libs.get(action.lib_id, action.id) {
+            Some(def) => {
+                defined = action.clone();
+                defined.fill_in(def);
+                &defined
+            },
+            None => action,
+        };
+        out.write_u32::<LE>(440)?;
+        out.write_u32::<LE>(action.lib_id)?;
+        out.write_u32::<LE>(action.id)?;
+        out.write_u32::<LE>(action.action_kind)?;
+        out.write_u32::<LE>(action.can_be_relative.into())?;
+        out.write_u32::<LE>(action.is_condition.into())?;
+        out.write_u32::<LE>(action.applies_to_something.into())?;
+        out.write_u32::<LE>(action.execution_type)?;
+        write_string(&action.fn_name, &mut out)?;
+        write_string(&action.fn_code, &mut out)?;
+        out.write_u32::<LE>(action.param_count)?;
+        out.write_u32::<LE>(8)?;
+        for ty in action.param_types {
+            out.write_u32::<LE>(ty)?;
+        }
+        out.write_i32::<LE>(action.applies_to)?;
+        out.write_u32::<LE>(action.is_relative.into())?;
+        out.write_u32::<LE>(8)?;
+        for param in &action.param_strings {
+            write_string(param, &mut out)?;
+        }
+        out.write_u32::<LE>(action.invert_condition.into())?;
+    }
+    Ok(())
+}
+
+impl GetAssetList for Object {
+    fn get_asset_list(project: &Project) -> &AssetList<Self> {
+        &project.objects
+    }
+
+    fn save(&self, _exe: bool, _: &Project, libs: &ActionLibraries, mut out: impl Write) -> io::Result<()> {
+        out.write_u32::<LE>(430)?;
+        out.write_i32::<LE>(self.sprite_index)?;
+        out.write_u32::<LE>(self.solid.into())?;
+        out.write_u32::<LE>(self.visible.into())?;
+        out.write_i32::<LE>(self.depth)?;
+        out.write_u32::<LE>(self.persistent.into())?;
+        out.write_i32::<LE>(self.parent_index)?;
+        out.write_i32::<LE>(self.mask_index)?;
+        out.write_u32::<LE>(11)?;
+        for events in &self.events {
+            // note: gm saves them backwards, might as well replicate lol
+            for (&i, event) in events.iter().rev() {
+                if !event.actions.is_empty() {
+                    out.write_u32::<LE>(i as _)?;
+                    write_event(event, libs, &mut out)?;
+                }
+            }
+            out.write_i32::<LE>(-1)?;
+        }
+        Ok(())
+    }
+}
+
+impl GetAssetList for Timeline {
+    fn get_asset_list(project: &Project) -> &AssetList<Self> {
+        &project.timelines
+    }
+
+    fn save(&self, _exe: bool, _: &Project, libs: &ActionLibraries, mut out: impl Write) -> io::Result<()> {
+        out.write_u32::<LE>(500)?;
+        out.write_u32::<LE>(self.moments.len() as _)?;
+        for (time, event) in &self.moments {
+            out.write_u32::<LE>(*time)?;
+            write_event(event, libs, &mut out)?;
+        }
+        Ok(())
+    }
+}
+
+impl GetAssetList for Sound {
+    fn get_asset_list(project: &Project) -> &AssetList<Self> {
+        &project.sounds
+    }
+
+    fn save(&self, _exe: bool, _: &Project, _: &ActionLibraries, mut out: impl Write) -> io::Result<()> {
+        out.write_u32::<LE>(800)?;
+        out.write_u32::<LE>(self.kind)?;
+        write_string(&self.extension, &mut out)?;
+        write_string(&self.source, &mut out)?;
+        out.write_u32::<LE>(self.data.is_some().into())?;
+        if let Some(data) = self.data.as_ref() {
+            write_buffer(data, &mut out)?;
+        }
+        out.write_u32::<LE>(self.effects)?;
+        out.write_f64::<LE>(self.volume)?;
+        out.write_f64::<LE>(self.pan)?;
+        out.write_u32::<LE>(self.preload.into())?;
+        Ok(())
+    }
+}
+
+impl GetAssetList for Font {
+    fn get_asset_list(project: &Project) -> &AssetList<Self> {
+        &project.fonts
+    }
+
+    fn save(&self, _exe: bool, _: &Project, _: &ActionLibraries, mut out: impl Write) -> io::Result<()> {
+        out.write_u32::<LE>(800)?;
+        write_string(&self.sys_name, &mut out)?;
+        out.write_u32::<LE>(self.size)?;
+        out.write_u32::<LE>(self.bold.into())?;
+        out.write_u32::<LE>(self.italic.into())?;
+        let charset = if self.charset == 1 { 0 } else { self.charset };
+        out.write_u32::<LE>((self.range_start & 0xffff) | (charset << 16) | ((self.aa_level + 1) << 24))?;
+        out.write_u32::<LE>(self.range_end)?;
+        Ok(())
+    }
+}
+
+impl GetAssetList for Room {
+    fn get_asset_list(project: &Project) -> &AssetList<Self> {
+        &project.rooms
+    }
+
+    fn save(&self, exe: bool, _: &Project, _: &ActionLibraries, mut out: impl Write) -> io::Result<()> {
+        let version: u32 = if exe { 811 } else { 541 };
+        out.write_u32::<LE>(version)?;
+        write_string(&self.caption, &mut out)?;
+        out.write_u32::<LE>(self.width)?;
+        out.write_u32::<LE>(self.height)?;
+        if !exe {
+            out.write_u32::<LE>(self.snap_x)?;
+            out.write_u32::<LE>(self.snap_y)?;
+            out.write_u32::<LE>(self.isometric.into())?;
+        }
+        out.write_u32::<LE>(self.speed)?;
+        out.write_u32::<LE>(self.persistent.into())?;
+        out.write_u32::<LE>(self.bg_colour as _)?;
+        out.write_u32::<LE>(u32::from(self.clear_screen) | (u32::from(self.clear_view) << 1))?;
+        write_string(&self.creation_code, &mut out)?;
+        out.write_u32::<LE>(8)?;
+        for b in &self.backgrounds {
+            out.write_u32::<LE>(b.visible_on_start.into())?;
+            out.write_u32::<LE>(b.is_foreground.into())?;
+            out.write_u32::<LE>(b.source_bg as _)?;
+            out.write_u32::<LE>(b.xoffset as _)?;
+            out.write_u32::<LE>(b.yoffset as _)?;
+            out.write_u32::<LE>(b.tile_horz as _)?;
+            out.write_u32::<LE>(b.tile_vert as _)?;
+            out.write_u32::<LE>(b.hspeed as _)?;
+            out.write_u32::<LE>(b.vspeed as _)?;
+            out.write_u32::<LE>(b.stretch as _)?;
+        }
+        out.write_u32::<LE>(self.views_enabled.into())?;
+        out.write_u32::<LE>(8)?;
+        for v in &self.views {
+            out.write_u32::<LE>(v.visible as _)?;
+            out.write_u32::<LE>(v.source_x as _)?;
+            out.write_u32::<LE>(v.source_y as _)?;
+            out.write_u32::<LE>(v.source_w as _)?;
+            out.write_u32::<LE>(v.source_h as _)?;
+            out.write_u32::<LE>(v.port_x as _)?;
+            out.write_u32::<LE>(v.port_y as _)?;
+            out.write_u32::<LE>(v.port_w as _)?;
+            out.write_u32::<LE>(v.port_h as _)?;
+            out.write_u32::<LE>(v.following_hborder as _)?;
+            out.write_u32::<LE>(v.following_vborder as _)?;
+            out.write_u32::<LE>(v.following_hspeed as _)?;
+            out.write_u32::<LE>(v.following_vspeed as _)?;
+            out.write_u32::<LE>(v.following_target as _)?;
+        }
+        out.write_u32::<LE>(self.instances.len() as _)?;
+        for i in &self.instances {
+            out.write_u32::<LE>(i.x as _)?;
+            out.write_u32::<LE>(i.y as _)?;
+            out.write_u32::<LE>(i.object as _)?;
+            out.write_u32::<LE>(i.id as _)?;
+            write_string(&i.creation_code, &mut out)?;
+            if !exe {
+                out.write_u32::<LE>(i.locked as _)?;
+            } else {
+                out.write_f64::<LE>(i.extra.xscale)?;
+                out.write_f64::<LE>(i.extra.yscale)?;
+                out.write_u32::<LE>(i.extra.blend as _)?;
+                out.write_f64::<LE>(i.extra.angle)?;
+            }
+        }
+        out.write_u32::<LE>(self.tiles.len() as _)?;
+        for t in &self.tiles {
+            out.write_u32::<LE>(t.x as _)?;
+            out.write_u32::<LE>(t.y as _)?;
+            out.write_u32::<LE>(t.source_bg as _)?;
+            out.write_u32::<LE>(t.u as _)?;
+            out.write_u32::<LE>(t.v as _)?;
+            out.write_u32::<LE>(t.width as _)?;
+            out.write_u32::<LE>(t.height as _)?;
+            out.write_u32::<LE>(t.depth as _)?;
+            out.write_u32::<LE>(t.id as _)?;
+            if !exe {
+                out.write_u32::<LE>(t.locked as _)?;
+            } else {
+                out.write_f64::<LE>(t.extra.xscale)?;
+                out.write_f64::<LE>(t.extra.yscale)?;
+                out.write_u32::<LE>(t.extra.blend as _)?;
+            }
+        }
+        if !exe {
+            out.write_u32::<LE>(self.remember_room_editor_info as _)?;
+            out.write_u32::<LE>(self.editor_width as _)?;
+            out.write_u32::<LE>(self.editor_height as _)?;
+            out.write_u32::<LE>(self.show_grid as _)?;
+            out.write_u32::<LE>(self.show_objects as _)?;
+            out.write_u32::<LE>(self.show_tiles as _)?;
+            out.write_u32::<LE>(self.show_backgrounds as _)?;
+            out.write_u32::<LE>(self.show_foregrounds as _)?;
+            out.write_u32::<LE>(self.show_views as _)?;
+            out.write_u32::<LE>(self.delete_underlying_objects as _)?;
+            out.write_u32::<LE>(self.delete_underlying_tiles as _)?;
+            out.write_u32::<LE>(self.tab as _)?;
+            out.write_u32::<LE>(self.x_position_scroll as _)?;
+            out.write_u32::<LE>(self.y_position_scroll as _)?;
+        }
+        Ok(())
+    }
+
+    fn write_additional(project: &Project, mut out: impl Write) -> io::Result<()> {
+        out.write_u32::<LE>(project.last_instance_id as _)?;
+        out.write_u32::<LE>(project.last_tile_id as _)?;
+        Ok(())
+    }
+}
+
+pub fn write_string(s: &str, out: impl Write) -> io::Result<()> {
+    write_buffer(s.as_bytes(), out)
+}
+
+pub fn write_buffer(buf: &[u8], mut out: impl Write) -> io::Result<()> {
+    out.write_u32::<LE>(buf.len() as u32)?;
+    out.write_all(buf)
+}
+
+fn save_frame(frame: &Frame, mut out: impl Write) -> io::Result<()> {
+    out.write_u32::<LE>(800)?;
+    out.write_u32::<LE>(frame.width)?;
+    out.write_u32::<LE>(frame.height)?;
+    let data = frame.get_data();
+    if !data.is_empty() {
+        write_buffer(data, &mut out)?;
+    }
+    Ok(())
+}
+
+// writes the closure's output as a length-prefixed zlib block
+fn write_zlib(mut out: impl Write, f: impl FnOnce(&mut ZlibEncoder<Vec<u8>>) -> io::Result<()>) -> io::Result<()> {
+    let mut enc = ZlibEncoder::new(Vec::new(), Compression::default());
+    f(&mut enc)?;
+    write_buffer(&enc.finish()?, &mut out)
+}
+
+pub fn save_assets<T: GetAssetList>(
+    project: &Project,
+    libs: &ActionLibraries,
+    exe: bool,
+    mut out: impl Write,
+) -> io::Result<()> {
+    let asset_list = T::get_asset_list(project);
+    out.write_u32::<LE>(800)?;
+    out.write_u32::<LE>(asset_list.assets.len() as _)?;
+    let timestamps = asset_list.timestamps.iter().copied().chain(std::iter::repeat(0.0));
+    let timestamps = timestamps.take(asset_list.assets.len()).collect::<Vec<_>>();
+    (&asset_list.assets, &asset_list.names, &timestamps)
+        .into_par_iter()
+        .map(|(asset, name, timestamp)| {
+            let mut out = ZlibEncoder::new(Vec::new(), Compression::default());
+            out.write_u32::<LE>(asset.is_some().into())?;
+            if let Some(asset) = asset.as_ref() {
+                write_string(name, &mut out)?;
+                if !exe {
+                    out.write_f64::<LE>(*timestamp)?;
+                }
+                asset.save(exe, project, libs, &mut out)?;
+            }
+            out.finish()
+        })
+        .collect::<io::Result<Vec<_>>>()?
+        .into_iter()
+        .try_for_each(|buf| write_buffer(&buf, &mut out))?;
+    T::write_additional(project, &mut out)?;
+    Ok(())
+}
+
+fn write_optional_image(image: Option<&Vec<u8>>, mut out: impl Write) -> io::Result<()> {
+    out.write_u32::<LE>(image.is_some().into())?;
+    if let Some(image) = image {
+        write_buffer(image, &mut out)?;
+    }
+    Ok(())
+}
+
+fn save_settings(project: &Project, exe: bool, mut out: impl Write) -> io::Result<()> {
+    let settings = &project.settings;
+    // 825 tells the runner to look for the creation code flag
+    let swap_creation_events = settings.vsync_and_force_cpu & (1 << 31) != 0;
+    let version = if exe && swap_creation_events { 825 } else { 800 };
+    out.write_u32::<LE>(version)?;
+    write_zlib(out, |out| {
+        out.write_u32::<LE>(settings.fullscreen.into())?;
+        out.write_u32::<LE>(settings.interpolate_pixels.into())?;
+        out.write_u32::<LE>(settings.dont_draw_border.into())?;
+        out.write_u32::<LE>(settings.display_cursor.into())?;
+        out.write_i32::<LE>(settings.scaling)?;
+        out.write_u32::<LE>(settings.allow_resize.into())?;
+        out.write_u32::<LE>(settings.window_on_top.into())?;
+        out.write_u32::<LE>(settings.clear_colour)?;
+        out.write_u32::<LE>(settings.set_resolution.into())?;
+        out.write_u32::<LE>(settings.colour_depth)?;
+        out.write_u32::<LE>(settings.resolution)?;
+        out.write_u32::<LE>(settings.frequency)?;
+        out.write_u32::<LE>(settings.dont_show_buttons.into())?;
+        if !exe {
+            out.write_u32::<LE>(settings.vsync_and_force_cpu)?;
+        } else {
+            out.write_u32::<LE>(u32::from(settings.vsync_and_force_cpu as u8 != 0))?;
+        }
+        out.write_u32::<LE>(settings.disable_screensaver.into())?;
+        out.write_u32::<LE>(settings.f4_fullscreen.into())?;
+        out.write_u32::<LE>(settings.f1_help.into())?;
+        out.write_u32::<LE>(settings.esc_close.into())?;
+        out.write_u32::<LE>(settings.f5_save_f6_load.into())?;
+        out.write_u32::<LE>(settings.f9_screenshot.into())?;
+        out.write_u32::<LE>(settings.treat_close_as_esc.into())?;
+        out.write_u32::<LE>(settings.priority)?;
+        out.write_u32::<LE>(settings.freeze_on_lose_focus.into())?;
+        out.write_u32::<LE>(settings.loading_bar)?;
+        if settings.loading_bar == 2 {
+            write_optional_image(settings.loading_background.as_ref(), &mut *out)?;
+            write_optional_image(settings.loading_foreground.as_ref(), &mut *out)?;
+        }
+        out.write_u32::<LE>(settings.has_custom_load_image.into())?;
+        if settings.has_custom_load_image {
+            write_optional_image(settings.custom_load_image.as_ref(), &mut *out)?;
+        }
+        out.write_u32::<LE>(settings.loading_transparent.into())?;
+        out.write_u32::<LE>(settings.loading_translucency)?;
+        out.write_u32::<LE>(settings.loading_progress_bar_scale.into())?;
+        if !exe {
+            write_buffer(&settings.icon, &mut *out)?;
+        }
+        out.write_u32::<LE>(settings.show_error_messages.into())?;
+        out.write_u32::<LE>(settings.log_errors.into())?;
+        out.write_u32::<LE>(settings.always_abort.into())?;
+        out.write_u32::<LE>(
+            u32::from(settings.zero_uninitialized_vars) | (u32::from(settings.error_on_uninitialized_args) << 1),
+        )?;
+        if exe {
+            if version == 825 {
+                // webgl, then the creation code flag
+                out.write_u32::<LE>(0)?;
+                out.write_u32::<LE>(1)?;
+            }
+            return Ok(())
+        }
+        write_string(&settings.info_author, &mut *out)?;
+        write_string(&settings.info_version, &mut *out)?;
+        out.write_f64::<LE>(settings.info_timestamp)?;
+        write_string(&settings.info_information, &mut *out)?;
+        out.write_u32::<LE>(settings.version_major)?;
+        out.write_u32::<LE>(settings.version_minor)?;
+        out.write_u32::<LE>(settings.version_release)?;
+        out.write_u32::<LE>(settings.version_build)?;
+        write_string(&settings.exe_company, &mut *out)?;
+        write_string(&settings.exe_product, &mut *out)?;
+        write_string(&settings.exe_copyright, &mut *out)?;
+        write_string(&settings.exe_description, &mut *out)?;
+        out.write_f64::<LE>(settings.info_timestamp)?;
+        Ok(())
+    })
+}
+
+fn save_triggers(project: &Project, exe: bool, mut out: impl Write) -> io::Result<()> {
+    out.write_u32::<LE>(800)?;
+    out.write_u32::<LE>(project.triggers.len() as _)?;
+    for trigger in &project.triggers {
+        write_zlib(&mut out, |out| {
+            out.write_u32::<LE>(trigger.is_some().into())?;
+            if let Some(trigger) = trigger {
+                out.write_u32::<LE>(800)?;
+                write_string(&trigger.name, &mut *out)?;
+                write_string(&trigger.condition, &mut *out)?;
+                out.write_u32::<LE>(trigger.kind)?;
+                write_string(&trigger.constant_name, &mut *out)?;
+            }
+            Ok(())
+        })?;
+    }
+    if !exe {
+        // last changed
+        out.write_f64::<LE>(0.0)?;
+    }
+    Ok(())
+}
+
+// finds the hex part of every room_XXXXXXXX style identifier in some code
+fn find_instance_names(code: &str, out: &mut HashSet<u32>) {
+    let is_ident = |c: u8| c.is_ascii_alphanumeric() || c == b'_';
+    let is_hex = |c: &u8| c.is_ascii_digit() || (b'A'..=b'F').contains(c);
+    for word in code.as_bytes().split(|c| !is_ident(*c)) {
+        let mut word = word;
+        // rightmost match first, then keep going with whatever is left after it
+        while let Some(i) =
+            (1..word.len().saturating_sub(8)).rev().find(|&i| word[i] == b'_' && word[i + 1..i + 9].iter().all(is_hex))
+        {
+            if let Ok(name) = u32::from_str_radix(std::str::from_utf8(&word[i + 1..i + 9]).unwrap(), 16) {
+                out.insert(name);
+            }
+            word = &word[i + 9..];
+        }
+    }
+}
+
+// constants for every named instance or tile that's actually referenced somewhere
+fn instance_constants(project: &Project) -> Vec<(String, String)> {
+    let mut names = HashSet::new();
+    let actions = |events: &mut dyn Iterator<Item = &Event>, names: &mut HashSet<u32>| {
+        for param in events.flat_map(|e| &e.actions).flat_map(|a| &a.param_strings) {
+            find_instance_names(param, names);
+        }
+    };
+    for (_, _, room) in project.rooms.iter() {
+        find_instance_names(&room.creation_code, &mut names);
+        for inst in &room.instances {
+            find_instance_names(&inst.creation_code, &mut names);
+        }
+    }
+    for (_, _, obj) in project.objects.iter() {
+        actions(&mut obj.events.iter().flat_map(|e| e.values()), &mut names);
+    }
+    for (_, _, tl) in project.timelines.iter() {
+        actions(&mut tl.moments.iter().map(|(_, e)| e), &mut names);
+    }
+    for (_, _, script) in project.scripts.iter() {
+        find_instance_names(&script.source, &mut names);
+    }
+    for trigger in project.triggers.iter().flatten() {
+        find_instance_names(&trigger.condition, &mut names);
+    }
+    for (_, value) in &project.constants {
+        find_instance_names(value, &mut names);
+    }
+    let mut constants = Vec::new();
+    for (_, room_name, room) in project.rooms.iter() {
+        for inst in &room.instances {
+            if inst.id != 0 && names.contains(&inst.extra.name) {
+                constants.push((format!("{}_{:08X}", room_name, inst.extra.name), inst.id.to_string()));
+            }
+        }
+        for tile in &room.tiles {
+            if tile.id != 0 && names.contains(&tile.extra.name) {
+                constants.push((format!("{}_{:08X}", room_name, tile.extra.name), tile.id.to_string()));
+            }
+        }
+    }
+    constants
+}
+
+fn save_constants(project: &Project, exe: bool, mut out: impl Write) -> io::Result<()> {
+    let instances = if exe { instance_constants(project) } else { Vec::new() };
+    out.write_u32::<LE>(800)?;
+    out.write_u32::<LE>((instances.len() + project.constants.len()) as _)?;
+    for (name, value) in instances.iter().chain(&project.constants) {
+        write_string(name, &mut out)?;
+        write_string(value, &mut out)?;
+    }
+    if !exe {
+        // last changed
+        out.write_f64::<LE>(0.0)?;
+    }
+    Ok(())
+}
+
+fn save_included_files(project: &Project, exe: bool, mut out: impl Write) -> io::Result<()> {
+    out.write_u32::<LE>(800)?;
+    out.write_u32::<LE>(project.included_files.len() as _)?;
+    for file in &project.included_files {
+        write_zlib(&mut out, |out| {
+            if !exe {
+                // last changed
+                out.write_f64::<LE>(0.0)?;
+            }
+            out.write_u32::<LE>(800)?;
+            write_string(&file.file_name, &mut *out)?;
+            write_string(&file.source_path, &mut *out)?;
+            out.write_u32::<LE>(file.data_exists.into())?;
+            out.write_u32::<LE>(file.source_length)?;
+            let data = file.data.as_ref().filter(|_| file.stored_in_gmk);
+            out.write_u32::<LE>(data.is_some().into())?;
+            if let Some(data) = data {
+                write_buffer(data, &mut *out)?;
+            }
+            out.write_u32::<LE>(file.export_setting)?;
+            write_string(&file.export_custom_folder, &mut *out)?;
+            out.write_u32::<LE>(file.overwrite_file.into())?;
+            out.write_u32::<LE>(file.free_memory.into())?;
+            out.write_u32::<LE>(file.remove_at_end.into())?;
+            Ok(())
+        })?;
+    }
+    Ok(())
+}
+
+fn save_extensions(project: &Project, mut out: impl Write) -> io::Result<()> {
+    out.write_u32::<LE>(700)?;
+    out.write_u32::<LE>(project.extensions.len() as _)?;
+    for name in &project.extensions {
+        write_string(name, &mut out)?;
+    }
+    Ok(())
+}
+
+fn save_game_information(project: &Project, exe: bool, mut out: impl Write) -> io::Result<()> {
+    let info = &project.game_info;
+    out.write_u32::<LE>(800)?;
+    write_zlib(out, |out| {
+        out.write_u32::<LE>(info.colour)?;
+        out.write_u32::<LE>(info.new_window.into())?;
+        write_string(&info.caption, &mut *out)?;
+        out.write_i32::<LE>(info.left)?;
+        out.write_i32::<LE>(info.top)?;
+        out.write_i32::<LE>(info.width)?;
+        out.write_i32::<LE>(info.height)?;
+        out.write_u32::<LE>(info.border.into())?;
+        out.write_u32::<LE>(info.resizable.into())?;
+        out.write_u32::<LE>(info.window_on_top.into())?;
+        out.write_u32::<LE>(info.freeze_game.into())?;
+        if !exe {
+            // last changed
+            out.write_f64::<LE>(0.0)?;
+        }
+        write_buffer(&info.rtf, &mut *out)?;
+        Ok(())
+    })
+}
+
+fn save_library_init_code(libs: &ActionLibraries, mut out: impl Write) -> io::Result<()> {
+    out.write_u32::<LE>(500)?;
+    out.write_u32::<LE>(libs.0.len() as _)?;
+    for lib in &libs.0 {
+        write_string(&lib.init_code, &mut out)?;
+    }
+    Ok(())
+}
+
+fn tree_assets(nodes: &[TreeNode], out: &mut Vec<usize>) {
+    for node in nodes {
+        match node {
+            TreeNode::Group(_, children) => tree_assets(children, out),
+            TreeNode::Asset(i) => out.push(*i),
+        }
+    }
+}
+
+// rooms run in the order they show up in the tree
+pub fn room_order(project: &Project) -> Vec<usize> {
+    let mut order = Vec::with_capacity(project.rooms.assets.len());
+    tree_assets(&project.rooms.tree, &mut order);
+    order
+}
+
+fn save_room_order(project: &Project, mut out: impl Write) -> io::Result<()> {
+    let order = room_order(project);
+    out.write_u32::<LE>(700)?;
+    out.write_u32::<LE>(order.len() as _)?;
+    for i in order {
+        out.write_u32::<LE>(i as _)?;
+    }
+    Ok(())
+}
+
+fn write_tree_node<W: Write>(kind: u32, node: &TreeNode, names: &[String], out: &mut W) -> io::Result<()> {
+    match node {
+        TreeNode::Group(name, children) => {
+            out.write_u32::<LE>(2)?;
+            out.write_u32::<LE>(kind)?;
+            out.write_u32::<LE>(0)?;
+            write_string(name, &mut *out)?;
+            out.write_u32::<LE>(children.len() as _)?;
+            for child in children {
+                write_tree_node(kind, child, names, out)?;
+            }
+        },
+        TreeNode::Asset(i) => {
+            out.write_u32::<LE>(3)?;
+            out.write_u32::<LE>(kind)?;
+            out.write_u32::<LE>(*i as _)?;
+            write_string(names.get(*i).map_or("", String::as_str), &mut *out)?;
+            out.write_u32::<LE>(0)?;
+        },
+    }
+    Ok(())
+}
+
+fn save_tree(project: &Project, mut out: impl Write) -> io::Result<()> {
+    let roots: [(&str, u32, &[TreeNode], &[String]); 12] = [
+        ("Sprites", 2, &project.sprites.tree, &project.sprites.names),
+        ("Sounds", 3, &project.sounds.tree, &project.sounds.names),
+        ("Backgrounds", 6, &project.backgrounds.tree, &project.backgrounds.names),
+        ("Paths", 8, &project.paths.tree, &project.paths.names),
+        ("Scripts", 7, &project.scripts.tree, &project.scripts.names),
+        ("Fonts", 9, &project.fonts.tree, &project.fonts.names),
+        ("Time Lines", 12, &project.timelines.tree, &project.timelines.names),
+        ("Objects", 1, &project.objects.tree, &project.objects.names),
+        ("Rooms", 4, &project.rooms.tree, &project.rooms.names),
+        ("Game Information", 10, &[], &[]),
+        ("Global Game Settings", 11, &[], &[]),
+        ("Extension Packages", 13, &[], &[]),
+    ];
+    for (name, kind, children, names) in roots {
+        out.write_u32::<LE>(1)?;
+        out.write_u32::<LE>(kind)?;
+        out.write_u32::<LE>(0)?;
+        write_string(name, &mut out)?;
+        out.write_u32::<LE>(children.len() as _)?;
+        for child in children {
+            write_tree_node(kind, child, names, &mut out)?;
+        }
+    }
+    Ok(())
+}
+
+// actions only get written properly from their definitions, and the project needs to have been loaded with the
+// same libraries for their resource arguments to be ids rather than names
+fn check_actions(project: &Project, libs: &ActionLibraries) -> Result<()> {
+    for action in project.actions() {
+        let Some(def) = libs.get(action.lib_id, action.id) else {
+            if libs.is_empty() {
+                return Err(Error::Other("can't write actions without the action libraries".into()))
+            }
+            return Err(Error::UnknownAction(action.lib_id, action.id))
+        };
+        let is_resource = |t: &u32| (5..=14).contains(t) && *t != 13;
+        let args = def.arg_types.iter().zip(&action.param_strings).take(def.arg_count as usize);
+        if let Some((_, name)) = args.filter(|(t, _)| is_resource(t)).find(|(_, s)| s.parse::<i32>().is_err()) {
+            return Err(Error::Other(format!("action argument {} was loaded as a name rather than an id", name)))
+        }
+    }
+    Ok(())
+}
+
+// writes a whole editable .gm81, the same way the IDE would
+pub fn save_gm81(project: &Project, libs: &ActionLibraries, mut out: impl Write) -> Result<()> {
+    check_actions(project, libs)?;
+    out.write_u32::<LE>(GMK_MAGIC)?;
+    out.write_u32::<LE>(GMK_VERSION)?;
+    out.write_u32::<LE>(project.game_id)?;
+    // the guid isn't kept in .gm82 projects
+    out.write_all(&[0; 16])?;
+    save_settings(project, false, &mut out)?;
+    save_triggers(project, false, &mut out)?;
+    save_constants(project, false, &mut out)?;
+    save_assets::<Sound>(project, libs, false, &mut out)?;
+    save_assets::<Sprite>(project, libs, false, &mut out)?;
+    save_assets::<Background>(project, libs, false, &mut out)?;
+    save_assets::<Path>(project, libs, false, &mut out)?;
+    save_assets::<Script>(project, libs, false, &mut out)?;
+    save_assets::<Font>(project, libs, false, &mut out)?;
+    save_assets::<Timeline>(project, libs, false, &mut out)?;
+    save_assets::<Object>(project, libs, false, &mut out)?;
+    save_assets::<Room>(project, libs, false, &mut out)?;
+    save_included_files(project, false, &mut out)?;
+    save_extensions(project, &mut out)?;
+    save_game_information(project, false, &mut out)?;
+    save_library_init_code(libs, &mut out)?;
+    save_room_order(project, &mut out)?;
+    save_tree(project, &mut out)?;
+    Ok(())
+}
+
+// everything the runner reads once it's decrypted the gamedata
+fn save_gamedata(project: &Project, libs: &ActionLibraries, mut out: impl Write) -> Result<()> {
+    // these need the IDE: fonts are rendered with gdi and extensions are packed from the installed .ged files
+    if let Some((_, name, _)) = project.fonts.iter().next() {
+        return Err(Error::Other(format!("can't render font {} outside the IDE, build this project from there", name)))
+    }
+    if let Some(name) = project.extensions.first() {
+        return Err(Error::Other(format!(
+            "can't pack extension {} outside the IDE, build this project from there",
+            name
+        )))
+    }
+    check_actions(project, libs)?;
+    out.write_u32::<LE>(project.game_id)?;
+    out.write_all(&[0; 16])?;
+    save_extensions(project, &mut out)?;
+    save_triggers(project, true, &mut out)?;
+    save_constants(project, true, &mut out)?;
+    save_assets::<Sound>(project, libs, true, &mut out)?;
+    save_assets::<Sprite>(project, libs, true, &mut out)?;
+    save_assets::<Background>(project, libs, true, &mut out)?;
+    save_assets::<Path>(project, libs, true, &mut out)?;
+    save_assets::<Script>(project, libs, true, &mut out)?;
+    save_assets::<Font>(project, libs, true, &mut out)?;
+    save_assets::<Timeline>(project, libs, true, &mut out)?;
+    save_assets::<Object>(project, libs, true, &mut out)?;
+    save_assets::<Room>(project, libs, true, &mut out)?;
+    save_included_files(project, true, &mut out)?;
+    save_game_information(project, true, &mut out)?;
+    save_library_init_code(libs, &mut out)?;
+    save_room_order(project, &mut out)?;
+    Ok(())
+}
+
+pub fn write_encrypted_gamedata(project: &Project, libs: &ActionLibraries, mut out: impl Write) -> Result<()> {
+    // write encryption headers
+    // no garbage data
+    out.write_u32::<LE>(0)?;
+    out.write_u32::<LE>(0)?;
+    // no swap table: it's just 0,1,2,etc
+    for i in 0..=255 {
+        out.write_u8(i)?;
+    }
+    // encrypted data start
+    // no garbage data
+    let mut data = vec![0, 0, 0, 0];
+    data.write_u32::<LE>(1)?;
+    save_gamedata(project, libs, &mut data)?;
+    // write a few null bytes so i don't have to figure out how to fix the decompiler
+    data.extend_from_slice(&[0; 16]);
+    out.write_u32::<LE>(data.len() as u32)?;
+    // first pass: swap bytes around
+    for i in 0..data.len() {
+        data.swap(i, i & !0xff);
+    }
+    // second pass
+    for i in 1..data.len() {
+        data[i] = data[i].wrapping_add(data[i - 1]).wrapping_add(i as u8);
+    }
+    out.write_all(&data)?;
+    Ok(())
+}
+
+// builds a game: the runner template is copied as-is and the game goes right after it
+pub fn save_exe(project: &Project, libs: &ActionLibraries, runner: &[u8], mut out: impl Write) -> Result<()> {
+    out.write_all(runner)?;
+    out.write_u32::<LE>(GMK_MAGIC)?;
+    out.write_u32::<LE>(GMK_VERSION)?;
+    save_settings(project, true, &mut out)?;
+    write_encrypted_gamedata(project, libs, &mut out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        load::load_gmk,
+        save::{
+            save_gmk,
+            tests::{libs, project, test_dir},
+        },
+    };
+
+    #[test]
+    fn binary_output_needs_every_action_defined() {
+        let mut project = project();
+        let mut other = libs();
+        other.0[0].actions.pop();
+        assert!(save_gm81(&project, &ActionLibraries::default(), &mut Vec::new()).is_err());
+        assert!(save_gm81(&project, &other, &mut Vec::new()).is_err());
+        assert!(save_gm81(&project, &libs(), &mut Vec::new()).is_ok());
+        // loaded without the libraries, the sprite argument is still a name
+        let path = test_dir("binary-output").join("game.gm82");
+        save_gmk(&mut project, &mut path.clone()).unwrap();
+        let guessed = load_gmk(path.clone(), &ActionLibraries::default(), false).unwrap();
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+        assert!(save_gm81(&guessed, &libs(), &mut Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,306 @@
+use crate::{
+    manifest::Manifest,
+    output::{Output, PngEffort},
+    prune, Error, Result,
+};
+use itertools::Itertools;
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+// saves are written to a staging folder next to the project files and only moved into place once everything
+// has been written. the journal lists every staged file and every orphaned file being removed while that
+// happens, so if it gets interrupted the next load can either finish the job or put the old files back.
+// the manifest and the asset timestamps are all that's left in there between saves, with a .gitignore so they
+// stay out of version control
+const WORK_DIR: &str = ".gm82save";
+const STAGING_DIR: &str = "staging";
+const BACKUP_DIR: &str = "backup";
+const JOURNAL: &str = "journal";
+const MANIFEST: &str = "manifest";
+const TIMESTAMPS: &str = "timestamps";
+
+fn create_dirs(path: &Path) -> Result<()> {
+    std::fs::create_dir_all(path).map_err(|e| Error::DirIoError(e, path.to_path_buf()))
+}
+
+fn rename(from: &Path, to: &Path) -> Result<()> {
+    if let Some(parent) = to.parent() {
+        create_dirs(parent)?;
+    }
+    std::fs::rename(from, to).map_err(|e| Error::FileIoError(e, from.to_path_buf()))
+}
+
+fn remove_dir(path: &Path) -> Result<()> {
+    match std::fs::remove_dir_all(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(Error::DirIoError(e, path.to_path_buf())),
+        _ => Ok(()),
+    }
+}
+
+// everything a save leaves in the work folder while it's going, but not what's kept between saves.
+// the journal goes first, so if this gets interrupted it's not taken for an interrupted save
+fn clear_work_dir(work_dir: &Path) -> Result<()> {
+    let journal = work_dir.join(JOURNAL);
+    match std::fs::remove_file(&journal) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(Error::FileIoError(e, journal)),
+        _ => (),
+    }
+    remove_dir(&work_dir.join(STAGING_DIR))?;
+    remove_dir(&work_dir.join(BACKUP_DIR))
+}
+
+fn write_manifest(work_dir: &Path, manifest: &Manifest, timestamps: &[(String, f64)]) -> Result<()> {
+    create_dirs(work_dir)?;
+    let ignore = work_dir.join(".gitignore");
+    std::fs::write(&ignore, "*\n").map_err(|e| Error::FileIoError(e, ignore))?;
+    let path = work_dir.join(TIMESTAMPS);
+    let out = timestamps.iter().map(|(key, time)| format!("{time} {key}\n")).collect::<String>();
+    std::fs::write(&path, out).map_err(|e| Error::FileIoError(e, path))?;
+    manifest.write(&work_dir.join(MANIFEST), work_dir.parent().unwrap_or(work_dir))
+}
+
+// when each asset was last changed, as of the last save on this machine, keyed by "<kind>/<name>" like
+// "sprites/spr_player". timestamps churn too much to go in the project files, and losing them isn't a big deal
+pub fn read_timestamps(project: &Path) -> HashMap<String, f64> {
+    std::fs::read_to_string(project_dir(project).join(WORK_DIR).join(TIMESTAMPS))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|l| l.split_once(' '))
+        .filter_map(|(time, key)| Some((key.to_string(), time.parse().ok()?)))
+        .collect()
+}
+
+// the folder the project's files live in
+pub fn project_dir(project: &Path) -> PathBuf {
+    project.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new(".")).to_path_buf()
+}
+
+pub struct Transaction {
+    dir: PathBuf,
+    done: bool,
+    smart_save: bool,
+    png_effort: PngEffort,
+    manifest: Manifest,
+    // files of staged assets that were left as they are, so prune knows they're still wanted
+    kept: Mutex<Vec<String>>,
+    timestamps: Mutex<Vec<(String, f64)>>,
+}
+
+impl Transaction {
+    // project is the path to the .gm82 file. a smart save skips assets that come out the same as in the last
+    // save, as long as their files are still the way that save left them
+    pub fn begin(project: &Path, smart_save: bool, png_effort: PngEffort) -> Result<Self> {
+        let dir = project_dir(project);
+        if interrupted(project) {
+            return Err(Error::InterruptedSave(project.to_path_buf()))
+        }
+        let work_dir = dir.join(WORK_DIR);
+        let manifest = if smart_save { Manifest::read(&work_dir.join(MANIFEST)) } else { Manifest::default() };
+        // anything else in here is from a save that failed before it was committed
+        clear_work_dir(&work_dir)?;
+        create_dirs(&work_dir.join(STAGING_DIR))?;
+        Ok(Self {
+            dir,
+            done: false,
+            smart_save,
+            png_effort,
+            manifest,
+            kept: Mutex::new(Vec::new()),
+            timestamps: Mutex::new(Vec::new()),
+        })
+    }
+
+    // where to write the .gm82 file, the rest of the project goes next to it
+    pub fn stage(&self, project: &Path) -> PathBuf {
+        let mut path = self.dir.join(WORK_DIR).join(STAGING_DIR);
+        if let Some(name) = project.file_name() {
+            path.push(name);
+        }
+        path
+    }
+
+    // stages what an asset saves, unless the last save wrote exactly that and it's still there.
+    // asset is the staged path that stands for it, like <staging>/sprites/spr_player
+    pub fn write(&self, asset: &Path, output: &Output) -> Result<()> {
+        let staging = self.dir.join(WORK_DIR).join(STAGING_DIR);
+        let key = |p: &Path| p.strip_prefix(&staging).unwrap_or(p).iter().map(|c| c.to_string_lossy()).join("/");
+        let files = output.paths().map(key).collect();
+        let same = self.manifest.update(key(asset), output.hash(&staging), &self.dir, files);
+        if self.smart_save && same && output.exists() {
+            output.images().for_each(|p| self.manifest.keep(key(p), vec![key(p)]));
+            return Ok(())
+        }
+        // images are slow to encode, so any that are the same as last time stay as they are
+        output.write(self.png_effort, |path, hash| {
+            let key = key(path);
+            let keep = self.manifest.update(key.clone(), hash, &self.dir, vec![key.clone()])
+                && self.smart_save
+                && exists(path);
+            if keep {
+                self.kept.lock().push(key);
+            }
+            keep
+        })
+    }
+
+    // kind is the folder the asset's in, like "sprites"
+    pub fn timestamp(&self, kind: &str, name: &str, time: f64) {
+        self.timestamps.lock().push((format!("{kind}/{name}"), time));
+    }
+
+    pub fn commit(mut self) -> Result<()> {
+        self.done = true;
+        let work_dir = self.dir.join(WORK_DIR);
+        let mut files = Vec::new();
+        list_files(&work_dir.join(STAGING_DIR), "", &mut files)?;
+        let written = files.iter().cloned().chain(self.kept.lock().drain(..)).collect::<Vec<_>>();
+        let removed = prune::orphaned_files(&self.dir, &work_dir.join(STAGING_DIR), &written)?;
+        let journal = work_dir.join(JOURNAL);
+        {
+            let f = File::create(&journal).map_err(|e| Error::FileIoError(e, journal.clone()))?;
+            let mut w = BufWriter::new(f);
+            for file in &removed {
+                writeln!(w, "- {}", file)?;
+            }
+            for file in &files {
+                writeln!(w, "+ {}", file)?;
+            }
+            w.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        }
+        if let Err(e) = move_files(&self.dir, &files, &removed) {
+            // if even this fails the journal stays, and the next load will ask what to do
+            undo_move(&self.dir, &files, &removed)?;
+            clear_work_dir(&work_dir)?;
+            return Err(e)
+        }
+        clear_work_dir(&work_dir)?;
+        // the save's done either way, without this the next one just writes everything
+        if write_manifest(&work_dir, &self.manifest, &self.timestamps.lock()).is_err() {
+            let _ = remove_dir(&work_dir);
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Transaction {
+    fn drop(&mut self) {
+        // the save failed before anything was moved, so the project is untouched
+        if !self.done {
+            let _ = clear_work_dir(&self.dir.join(WORK_DIR));
+        }
+    }
+}
+
+// relative paths of every file in a folder, with / as the separator
+fn list_files(path: &Path, prefix: &str, out: &mut Vec<String>) -> Result<()> {
+    for entry in std::fs::read_dir(path).map_err(|e| Error::DirIoError(e, path.to_path_buf()))? {
+        let entry = entry?;
+        let name = entry.file_name().into_string().map_err(|s| Error::UnicodeError(s.to_string_lossy().into()))?;
+        let name = format!("{prefix}{name}");
+        if entry.file_type()?.is_dir() {
+            list_files(&entry.path(), &format!("{name}/"), out)?;
+        } else {
+            out.push(name);
+        }
+    }
+    Ok(())
+}
+
+fn move_files(dir: &Path, files: &[String], removed: &[String]) -> Result<()> {
+    let work_dir = dir.join(WORK_DIR);
+    for file in removed {
+        let live = dir.join(file);
+        let backup = work_dir.join(BACKUP_DIR).join(file);
+        if live.exists() && !backup.exists() {
+            rename(&live, &backup)?;
+            // drop the folder too if that was the last thing in it, this fails harmlessly if it wasn't
+            if let Some(parent) = live.parent() {
+                let _ = std::fs::remove_dir(parent);
+            }
+        }
+    }
+    for file in files {
+        let staged = work_dir.join(STAGING_DIR).join(file);
+        if !staged.exists() {
+            continue
+        }
+        let live = dir.join(file);
+        let backup = work_dir.join(BACKUP_DIR).join(file);
+        if live.exists() && !backup.exists() {
+            rename(&live, &backup)?;
+        }
+        rename(&staged, &live)?;
+    }
+    Ok(())
+}
+
+fn undo_move(dir: &Path, files: &[String], removed: &[String]) -> Result<()> {
+    let work_dir = dir.join(WORK_DIR);
+    for file in removed {
+        let backup = work_dir.join(BACKUP_DIR).join(file);
+        if backup.exists() {
+            rename(&backup, &dir.join(file))?;
+        }
+    }
+    for file in files {
+        let live = dir.join(file);
+        let backup = work_dir.join(BACKUP_DIR).join(file);
+        if backup.exists() {
+            rename(&backup, &live)?;
+        } else if !work_dir.join(STAGING_DIR).join(file).exists() && live.exists() {
+            // this file didn't exist before the save
+            std::fs::remove_file(&live).map_err(|e| Error::FileIoError(e, live.clone()))?;
+        }
+    }
+    Ok(())
+}
+
+// whether a save of this project was interrupted while it was being committed
+pub fn interrupted(project: &Path) -> bool {
+    project_dir(project).join(WORK_DIR).join(JOURNAL).exists()
+}
+
+// finish an interrupted save, or put back the files from before it
+pub fn recover(project: &Path, finish: bool) -> Result<()> {
+    let dir = project_dir(project);
+    let work_dir = dir.join(WORK_DIR);
+    let journal = work_dir.join(JOURNAL);
+    let f = File::open(&journal).map_err(|e| Error::FileIoError(e, journal.clone()))?;
+    let (mut files, mut removed) = (Vec::new(), Vec::new());
+    for line in BufReader::new(f).lines() {
+        let line = line?;
+        if let Some(file) = line.strip_prefix("+ ") {
+            files.push(file.to_string());
+        } else if let Some(file) = line.strip_prefix("- ") {
+            removed.push(file.to_string());
+        } else {
+            return Err(Error::SyntaxError(journal))
+        }
+    }
+    if finish {
+        move_files(&dir, &files, &removed)?;
+    } else {
+        undo_move(&dir, &files, &removed)?;
+    }
+    clear_work_dir(&work_dir)
+}
+
+// the staging folder only has what's been written so far, so look in the project folder too
+pub fn exists(path: &Path) -> bool {
+    if path.exists() {
+        return true
+    }
+    let staging = Path::new(WORK_DIR).join(STAGING_DIR);
+    match path.ancestors().find(|p| p.ends_with(&staging)) {
+        Some(p) => match (path.strip_prefix(p), p.parent().and_then(Path::parent)) {
+            (Ok(rest), Some(dir)) => dir.join(rest).exists(),
+            _ => false,
+        },
+        None => false,
+    }
+}
@@ -633,44 +633,44 @@ pub struct Extension {
 #[repr(C)]
 pub struct ActionDefinition {
     vmt: u32,
-    name: UStr,
+    pub name: UStr,
     pub id: u32,
     image: u32,      // pointer
     image_list: u32, // also pointer
     image_index: u32,
-    hidden: bool,
-    advanced: bool,
-    pro_only: bool,
-    short_desc: UStr,
-    list_text: UStr,
-    hint_text: UStr,
-    kind: u32,
-    interface: u32,
-    question: bool,
-    apply_to: bool,
-    relative: bool,
-    arg_count: u32,
-    arg_captions: [UStr; 8],
-    arg_types: [u32; 8],
-    arg_defaults: [UStr; 8],
-    arg_menu_lens: [UStr; 8],
-    execution_type: u32,
-    function_name: UStr,
-    code_string: UStr,
+    pub hidden: bool,
+    pub advanced: bool,
+    pub pro_only: bool,
+    pub short_desc: UStr,
+    pub list_text: UStr,
+    pub hint_text: UStr,
+    pub kind: u32,
+    pub interface: u32,
+    pub question: bool,
+    pub apply_to: bool,
+    pub relative: bool,
+    pub arg_count: u32,
+    pub arg_captions: [UStr; 8],
+    pub arg_types: [u32; 8],
+    pub arg_defaults: [UStr; 8],
+    pub arg_menu_lens: [UStr; 8],
+    pub execution_type: u32,
+    pub function_name: UStr,
+    pub code_string: UStr,
 }
 
 #[repr(C)]
 pub struct ActionLibrary {
     vmt: u32,
-    caption: UStr,
+    pub caption: UStr,
     pub id: u32,
-    author: UStr,
-    version: u32,
+    pub author: UStr,
+    pub version: u32,
     padding: u32,
-    last_changed: f64,
-    information: UStr,
+    pub last_changed: f64,
+    pub information: UStr,
     pub init_code: UStr,
-    advanced: bool,
+    pub advanced: bool,
     pub action_count: usize,
     pub actions: *const &'static ActionDefinition,
     max_id: u32,
@@ -1,10 +1,9 @@
 use crate::{
     asset::{Action, Event, Room, Timeline, Trigger},
     delphi::UStr,
-    ide,
-    ide::AssetListTrait,
-    patch, patch_call,
+    ide, patch, patch_call,
 };
+use gm82file::asset::AssetListTrait;
 use itertools::Itertools;
 use std::{
     arch::asm,
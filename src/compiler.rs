@@ -1,5 +1,6 @@
 use super::{patch, patch_call, InstanceExtra, TileExtra, EXTRA_DATA};
-use crate::{ide, ide::AssetListTrait, UStr};
+use crate::{ide, UStr};
+use gm82file::asset::AssetListTrait;
 use lazy_static::lazy_static;
 use rayon::prelude::*;
 use regex::Regex;
@@ -85,12 +86,31 @@ unsafe extern "fastcall" fn compile_constants(stream: usize) -> bool {
         .filter(|(_, id, _)| *id != 0)
         .collect::<Vec<_>>();
 
+    // tiles are named the same way
+    let tiles = ide::ROOMS
+        .assets()
+        .into_par_iter()
+        .zip(ide::ROOMS.names())
+        .filter_map(|(room, name)| Some((room.as_ref()?, name)))
+        .flat_map(|(room, name)| {
+            let instance_names = &instance_names;
+            room.get_tiles().par_iter().filter_map(move |tile| {
+                EXTRA_DATA
+                    .as_ref()
+                    .and_then(|(_, extra)| extra.get(&tile.id))
+                    .filter(|extra| instance_names.contains(&extra.name))
+                    .map(|extra| (name, tile.id, extra.name))
+            })
+        })
+        .filter(|(_, id, _)| *id != 0)
+        .collect::<Vec<_>>();
+
     // write version
     let _: u32 = delphi_call!(0x52f12c, stream, 800);
     // write count
-    let _: u32 = delphi_call!(0x52f12c, stream, constant_names.len() + instances.len());
-    // write instance ids
-    for (room_name, id, name) in instances {
+    let _: u32 = delphi_call!(0x52f12c, stream, constant_names.len() + instances.len() + tiles.len());
+    // write instance and tile ids
+    for (room_name, id, name) in instances.into_iter().chain(tiles) {
         // write constant name
         let _: u32 = delphi_call!(
             0x52f168,
@@ -700,6 +700,15 @@ impl UStr {
         OsString::from_wide(self.as_slice())
     }
 
+    // text as it's written in project files, with any unpaired surrogates escaped
+    pub fn to_text(&self) -> String {
+        gm82file::escape::from_wide(self.as_slice())
+    }
+
+    pub fn from_text(s: &str) -> Self {
+        Self::new(OsString::from_wide(&gm82file::escape::to_wide(s)))
+    }
+
     pub const unsafe fn from_ptr(s: &*const u16) -> &Self {
         std::mem::transmute(s)
     }
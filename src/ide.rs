@@ -4,6 +4,7 @@ use crate::{
     delphi::{DelphiBox, TTreeNode, TTreeView, UStr},
     list::DelphiList,
 };
+use gm82file::asset as gm82;
 use std::slice;
 
 type IntPtr = *mut usize;
@@ -36,12 +37,11 @@ macro_rules! get_member_mut {
     };
 }
 
-pub trait AssetListTrait<T>: Sync {
-    fn assets(&self) -> &[Option<DelphiBox<T>>];
+// the IDE's lists live at fixed addresses, so they're changed through &self
+pub trait AssetListTrait<T>: gm82::AssetListTrait<DelphiBox<T>, Name = UStr> + Sync {
     fn assets_mut(&self) -> &'static mut [Option<DelphiBox<T>>];
-    fn names(&self) -> &[UStr];
     fn names_mut(&self) -> &'static mut [UStr];
-    fn timestamps(&self) -> &[f64];
+    fn timestamps_mut(&self) -> &'static mut [f64];
     fn alloc(&self, count: usize);
 }
 
@@ -99,8 +99,6 @@ impl<T, const P1: usize, const P2: usize, const P3: usize, const P4: usize> Asse
 
     get_member_mut!(pub forms_mut, Form, forms);
 
-    get_member_mut!(pub timestamps_mut, f64, timestamps);
-
     unsafe fn range(&self) -> std::ops::RangeTo<usize> {
         ..(*self.0).count
     }
@@ -113,8 +111,6 @@ impl<T, const P1: usize, const P2: usize, const P3: usize, const P4: usize, cons
 
     get_member!(pub forms_mut, Form, forms);
 
-    get_member_mut!(pub timestamps_mut, f64, timestamps);
-
     get_member_mut!(pub thumbs_mut, i32, thumbs);
 
     unsafe fn range(&self) -> std::ops::RangeTo<usize> {
@@ -122,18 +118,26 @@ impl<T, const P1: usize, const P2: usize, const P3: usize, const P4: usize, cons
     }
 }
 
-impl<T, const P1: usize, const P2: usize, const P3: usize, const P4: usize> AssetListTrait<T>
+impl<T, const P1: usize, const P2: usize, const P3: usize, const P4: usize> gm82::AssetListTrait<DelphiBox<T>>
     for AssetList<T, P1, P2, P3, P4>
 {
-    get_member!(assets, Option<DelphiBox<T>>, assets);
+    type Name = UStr;
 
-    get_member_mut!(assets_mut, Option<DelphiBox<T>>, assets);
+    get_member!(assets, Option<DelphiBox<T>>, assets);
 
     get_member!(names, UStr, names);
 
+    get_member!(timestamps, f64, timestamps);
+}
+
+impl<T, const P1: usize, const P2: usize, const P3: usize, const P4: usize> AssetListTrait<T>
+    for AssetList<T, P1, P2, P3, P4>
+{
+    get_member_mut!(assets_mut, Option<DelphiBox<T>>, assets);
+
     get_member_mut!(names_mut, UStr, names);
 
-    get_member!(timestamps, f64, timestamps);
+    get_member_mut!(timestamps_mut, f64, timestamps);
 
     fn alloc(&self, count: usize) {
         unsafe {
@@ -146,18 +150,26 @@ impl<T, const P1: usize, const P2: usize, const P3: usize, const P4: usize> Asse
     }
 }
 
-impl<T, const P1: usize, const P2: usize, const P3: usize, const P4: usize, const P5: usize> AssetListTrait<T>
-    for GraphicAssetList<T, P1, P2, P3, P4, P5>
+impl<T, const P1: usize, const P2: usize, const P3: usize, const P4: usize, const P5: usize>
+    gm82::AssetListTrait<DelphiBox<T>> for GraphicAssetList<T, P1, P2, P3, P4, P5>
 {
-    get_member!(assets, Option<DelphiBox<T>>, assets);
+    type Name = UStr;
 
-    get_member_mut!(assets_mut, Option<DelphiBox<T>>, assets);
+    get_member!(assets, Option<DelphiBox<T>>, assets);
 
     get_member!(names, UStr, names);
 
+    get_member!(timestamps, f64, timestamps);
+}
+
+impl<T, const P1: usize, const P2: usize, const P3: usize, const P4: usize, const P5: usize> AssetListTrait<T>
+    for GraphicAssetList<T, P1, P2, P3, P4, P5>
+{
+    get_member_mut!(assets_mut, Option<DelphiBox<T>>, assets);
+
     get_member_mut!(names_mut, UStr, names);
 
-    get_member!(timestamps, f64, timestamps);
+    get_member_mut!(timestamps_mut, f64, timestamps);
 
     fn alloc(&self, count: usize) {
         unsafe {
@@ -9,7 +9,6 @@ mod asset;
 mod delphi;
 mod code_form;
 mod compiler;
-mod events;
 mod font_render;
 mod ide;
 mod list;
@@ -19,6 +18,8 @@ mod save;
 mod save_exe;
 mod stub;
 
+pub use gm82file::{Error, GMLLines, Result};
+
 use crate::{
     delphi::{TMenuItem, TTreeNode, UStr},
     ide::get_triggers,
@@ -26,6 +27,14 @@ use crate::{
     save::GetAsset,
     save_exe::GetAssetList,
 };
+use gm82file::{
+    asset::{AssetListTrait as _, ExtraKeys, InstanceExtra, TileExtra},
+    escape::file_name,
+    events,
+    output::PngEffort,
+    transaction::{self, Transaction},
+    Namer,
+};
 use ide::AssetListTrait;
 use lazy_static::lazy_static;
 use rayon::prelude::*;
@@ -37,116 +46,10 @@ use std::{
     io::Write,
     os::windows::process::CommandExt,
     path::PathBuf,
-    ptr::{self, addr_of, addr_of_mut},
+    ptr::{self, addr_of},
     time::SystemTime,
 };
 
-#[derive(Debug)]
-pub enum Error {
-    IoError(std::io::Error),
-    FileIoError(std::io::Error, PathBuf),
-    DirIoError(std::io::Error, PathBuf),
-    PngDecodeError(PathBuf, png::DecodingError),
-    UnicodeError(String),
-    AssetNotFound(String, &'static str, String),
-    SyntaxError(PathBuf),
-    UnknownKey(PathBuf, String),
-    UnknownAction(u32, u32),
-    ParseIntError(std::num::ParseIntError),
-    ParseFloatError(std::num::ParseFloatError),
-    InvalidVersion(String),
-    DuplicateAsset(String),
-    DuplicateIncludedFile(String),
-    DuplicateTrigger(String),
-    BadAssetName(String, char),
-    BadIncludedFileName(String, char),
-    BadTriggerName(String, char),
-    OldGM82,
-    Other(String),
-}
-
-impl std::fmt::Display for Error {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::IoError(e) => write!(f, "io error: {}", e),
-            Self::FileIoError(e, p) => write!(f, "io error in file {}: {}", p.to_string_lossy(), e),
-            Self::DirIoError(e, p) => write!(f, "io error in directory {}: {}", p.to_string_lossy(), e),
-            Self::PngDecodeError(p, e) => write!(f, "couldn't decode image {}: {}", p.to_string_lossy(), e),
-            Self::UnicodeError(s) => write!(f, "couldn't encode {}", s),
-            Self::AssetNotFound(s, t, src) => write!(f, "couldn't find {} {} (from {})", t, s, src),
-            Self::SyntaxError(p) => write!(f, "syntax error in file {}", p.to_string_lossy()),
-            Self::UnknownKey(p, k) => write!(f, "unknown key in {}: {:?}", p.to_string_lossy(), k),
-            Self::UnknownAction(lib_id, act_id) => write!(f, "unknown action {} in lib with id {}", act_id, lib_id),
-            Self::ParseIntError(e) => write!(f, "integer parse error: {}", e),
-            Self::ParseFloatError(e) => write!(f, "float parse error: {}", e),
-            Self::InvalidVersion(v) => write!(f, "invalid exe_version {}", v),
-            Self::DuplicateAsset(n) => write!(f, "multiple assets named {}", n),
-            Self::DuplicateIncludedFile(n) => write!(f, "multiple included files named {}", n),
-            Self::DuplicateTrigger(n) => write!(f, "multiple triggers named {}", n),
-            Self::BadAssetName(n, c) => {
-                if *c == '.' || *c == ' ' {
-                    write!(f, "asset name \"{n}\" is illegal")
-                } else {
-                    write!(f, "asset name {n} may not contain character {c}")
-                }
-            },
-            Self::BadIncludedFileName(n, c) => write!(f, "included file name {n} may not contain character {c}"),
-            Self::BadTriggerName(n, c) => write!(f, "trigger file name {n} may not contain character {c}"),
-            Self::OldGM82 => write!(f, "this project was made with a newer version of gm82save, please update"),
-            Self::Other(s) => write!(f, "other error: {}", s),
-        }
-    }
-}
-
-impl From<std::io::Error> for Error {
-    fn from(err: std::io::Error) -> Self {
-        Error::IoError(err)
-    }
-}
-
-impl From<std::num::ParseIntError> for Error {
-    fn from(err: std::num::ParseIntError) -> Self {
-        Error::ParseIntError(err)
-    }
-}
-
-impl From<std::num::ParseFloatError> for Error {
-    fn from(err: std::num::ParseFloatError) -> Self {
-        Error::ParseFloatError(err)
-    }
-}
-
-// line iterator that strips right end but only if not in a string
-pub struct GMLLines<'a>(std::str::Lines<'a>, u8);
-
-impl<'a> GMLLines<'a> {
-    fn new(lines: std::str::Lines<'a>) -> Self {
-        Self(lines, 0)
-    }
-}
-
-impl<'a> Iterator for GMLLines<'a> {
-    type Item = &'a str;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        // trim line only if EOL is not in a string
-        let line = self.0.next()?;
-        let trimmed = line.trim_end();
-        for c in trimmed.bytes() {
-            if self.1 == 0 && (c == b'"' || c == b'\'') {
-                self.1 = c;
-            } else if c == self.1 {
-                self.1 = 0;
-            }
-        }
-        Some(if self.1 == 0 { trimmed } else { line })
-    }
-}
-
-pub type Result<T> = std::result::Result<T, Error>;
-
-const ACTION_TOKEN: &str = "/*\"/*'/**//* YYD ACTION";
-
 fn show_message(msg: impl AsRef<OsStr>) {
     unsafe {
         delphi::ShowMessage(&UStr::new(msg));
@@ -154,118 +57,49 @@ fn show_message(msg: impl AsRef<OsStr>) {
 }
 
 #[cfg(not(feature = "smooth_progress_bar"))]
-struct FakeSender;
-#[cfg(not(feature = "smooth_progress_bar"))]
-impl FakeSender {
-    fn send(&self, _: ()) {}
-}
-#[cfg(not(feature = "smooth_progress_bar"))]
-fn run_while_updating_bar<OP>(_bar_start: u32, _bar_end: u32, _count: u32, op: OP) -> Result<()>
+fn run_while_updating_bar<T, OP>(_bar_start: u32, _bar_end: u32, op: OP) -> Result<T>
 where
-    OP: Fn(FakeSender) -> Result<()> + Sync + Send,
+    OP: FnOnce(gm82file::Progress) -> Result<T>,
 {
-    op(FakeSender)
+    op(&|_| ())
 }
 
+// op reports progress out of 100, which gets drawn between bar_start and bar_end
 #[cfg(feature = "smooth_progress_bar")]
-fn run_while_updating_bar<OP>(bar_start: u32, bar_end: u32, count: u32, op: OP) -> Result<()>
+fn run_while_updating_bar<T, OP>(bar_start: u32, bar_end: u32, op: OP) -> Result<T>
 where
-    OP: Fn(crossbeam_channel::Sender<()>) -> Result<()> + Send + Sync,
+    T: Send,
+    OP: FnOnce(gm82file::Progress) -> Result<T> + Send,
 {
-    if count > 0 {
-        let (tx, rx) = crossbeam_channel::unbounded();
-        let handle = {
-            // this is basically what crossbeam does
-            // note that the handle is joined at the end of the function
-            // but it should be done executing by the time we get there anyway
-            let f: Box<dyn FnOnce() -> Result<()> + Send> = Box::new(|| op(tx));
-            let f: Box<dyn FnOnce() -> Result<()> + Send + 'static> = unsafe { std::mem::transmute(f) };
-            std::thread::spawn(f)
-        };
+    let (tx, rx) = crossbeam_channel::unbounded();
+    std::thread::scope(|s| {
+        let handle = s.spawn(move || op(&|progress| drop(tx.send(progress))));
         let mut progress = 0;
-        'outer: loop {
-            'inner: loop {
-                match rx.try_recv() {
-                    Ok(()) => progress += 1,
-                    Err(crossbeam_channel::TryRecvError::Empty) => break 'inner,
-                    Err(_) => break 'outer,
-                }
-            }
-            delphi::advance_progress_form(progress * (bar_end - bar_start) / count + bar_start);
-            // if this errors, it'll error next time too so no need to check
-            if let Ok(()) = rx.recv_timeout(std::time::Duration::from_millis(20)) {
-                progress += 1;
+        loop {
+            match rx.recv_timeout(std::time::Duration::from_millis(20)) {
+                Ok(p) => progress = rx.try_iter().fold(progress.max(p), u32::max),
+                Err(crossbeam_channel::RecvTimeoutError::Timeout) => (),
+                Err(crossbeam_channel::RecvTimeoutError::Disconnected) => break,
             }
+            delphi::advance_progress_form(bar_start + progress.min(100) * (bar_end - bar_start) / 100);
         }
         handle.join().unwrap()
-    } else {
-        Ok(())
-    }
+    })
 }
 
 static mut SAVE_END: SystemTime = SystemTime::UNIX_EPOCH;
-static mut LAST_SAVE: f64 = 0.0;
 
 fn update_timestamp() {
     unsafe {
         SAVE_END = SystemTime::now();
-        delphi::Now(addr_of_mut!(LAST_SAVE));
-    }
-}
-
-#[naked]
-unsafe extern "C" fn reset_if_time_went_backwards() {
-    asm!(
-        "movsd xmm0, qword ptr [{last_save}]", // load last save
-        "ucomisd xmm0, qword ptr [esp]",       // compare to now
-        "jb 2f", // jump if now > last save (i.e. no change needed)
-        "mov dword ptr [{last_save}], 0",      // null out last_save
-        "mov dword ptr [{last_save}+4], 0",
-        "2: add esp, 0x20", // return
-        "ret",
-        last_save = sym LAST_SAVE,
-        options(noreturn),
-    );
-}
-
-#[derive(Clone)]
-struct InstanceExtra {
-    pub name: u32,
-    pub xscale: f64,
-    pub yscale: f64,
-    pub blend: u32,
-    pub angle: f64,
-}
-
-impl InstanceExtra {
-    pub const DEFAULT: Self = Self { name: 0, xscale: 1.0, yscale: 1.0, blend: u32::MAX, angle: 0.0 };
-}
-
-impl Default for InstanceExtra {
-    fn default() -> Self {
-        Self::DEFAULT
-    }
-}
-
-#[derive(Clone)]
-struct TileExtra {
-    pub xscale: f64,
-    pub yscale: f64,
-    pub blend: u32,
-}
-
-impl TileExtra {
-    pub const DEFAULT: Self = Self { xscale: 1.0, yscale: 1.0, blend: u32::MAX };
-}
-
-impl Default for TileExtra {
-    fn default() -> Self {
-        Self::DEFAULT
     }
 }
 
 unsafe extern "fastcall" fn stuff_to_do_on_project_init() {
     EXTRA_DATA = None;
+    EXTRA_KEYS = None;
+    PRESERVE_IDS = false;
+    PNG_EFFORT = PngEffort::Default;
     SEEN_ERROR = false;
     project_watcher::unwatch();
     let _: u32 = delphi_call!(0x7149c4); // reload action libraries (what this overwrote)
@@ -309,6 +143,12 @@ unsafe fn refresh_gm82room_checkbox() {
 }
 
 static mut EXTRA_DATA: Option<(HashMap<usize, InstanceExtra>, HashMap<usize, TileExtra>)> = None;
+// x_ keys from asset files by asset type and index, since there's nowhere to put them in the assets themselves
+static mut EXTRA_KEYS: Option<HashMap<String, HashMap<usize, ExtraKeys>>> = None;
+// set by preserve_ids=1 in the project, saves instance and tile ids instead of renumbering them on load
+static mut PRESERVE_IDS: bool = false;
+// set by png_effort in the project, kept so saving writes it back
+static mut PNG_EFFORT: PngEffort = PngEffort::Default;
 
 unsafe extern "fastcall" fn about_inj(about_dialog: *const *const usize) {
     let info = UStr::new(concat!("gm82save: ", env!("ABOUT_BUILD_DATE")));
@@ -367,7 +207,15 @@ unsafe extern "fastcall" fn save(proj_path: &UStr, stream_ptr: *mut u32) -> u16
         return success as u16
     }
 
-    if let Err(e) = save::save_gmk(&mut path) {
+    // the last save's hashes only say what's on disk if nothing else has touched it since
+    let smart_save = project_watcher::watching();
+    // write into a staging folder and only move it over the project once everything worked
+    let result = Transaction::begin(&path, smart_save, PNG_EFFORT).and_then(|transaction| {
+        save::save_gmk(&mut transaction.stage(&path), &transaction)?;
+        transaction.commit()
+    });
+    path.pop();
+    if let Err(e) = result {
         // display the error
         project_watcher::unwatch();
         delphi::close_progress_form();
@@ -412,10 +260,34 @@ unsafe extern "fastcall" fn load(proj_path: &UStr, stream_ptr: *mut u32, result_
         return false
     }
 
+    match recover_interrupted_save(&path) {
+        Ok(true) => (),
+        Ok(false) => {
+            delphi::close_progress_form();
+            ide::initialize_project();
+            return true
+        },
+        Err(e) => {
+            delphi::close_progress_form();
+            show_message(format!("Failed to recover: {}", e));
+            ide::initialize_project();
+            return true
+        },
+    }
     if let Err(e) = load::load_gmk(path) {
         // display the error and reload
         delphi::close_progress_form();
-        show_message(format!("Failed to load: {}", e));
+        let message = match &e {
+            // the message box doesn't scroll, so only show the first few
+            Error::Multiple(errors) if errors.len() > 20 => format!(
+                "{} errors:\n{}\n...and {} more",
+                errors.len(),
+                errors[..20].iter().map(Error::to_string).collect::<Vec<_>>().join("\n"),
+                errors.len() - 20,
+            ),
+            e => e.to_string(),
+        };
+        show_message(format!("Failed to load: {}", message));
         ide::initialize_project();
     } else {
         delphi::close_progress_form();
@@ -424,6 +296,27 @@ unsafe extern "fastcall" fn load(proj_path: &UStr, stream_ptr: *mut u32, result_
     true
 }
 
+// if the last save died while moving files into place, ask whether to finish or undo it
+// returns false if the user would rather not load the project at all
+unsafe fn recover_interrupted_save(path: &std::path::Path) -> Result<bool> {
+    if !transaction::interrupted(path) {
+        return Ok(true)
+    }
+    let message = UStr::new(
+        "The last save of this project was interrupted while its files were being written.\r\n\r\n\
+        Click Yes to finish that save, or No to put back the files from before it.",
+    );
+    let caption = UStr::new("Interrupted save");
+    // TApplication.MessageBox, MB_YESNOCANCEL | MB_ICONQUESTION
+    let answer: u32 = delphi_call!(0x51fbdc, *(0x7882ec as *const u32), message.0, caption.0, 0x23);
+    match answer {
+        6 => transaction::recover(path, true)?,
+        7 => transaction::recover(path, false)?,
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
 #[naked]
 unsafe extern "C" fn stuff_to_do_on_ide_start() {
     unsafe extern "C" fn inj() {
@@ -606,11 +499,7 @@ unsafe extern "C" fn reset_compression() {
 
 unsafe extern "stdcall" fn duplicate_room(room: &mut asset::Room, old_id: usize, new_id: usize) {
     let room_names = ide::ROOMS.names();
-    fix_instances_when_renaming_room(
-        room,
-        room_names[old_id].to_os_string().to_str().unwrap(),
-        room_names[new_id].to_os_string().to_str().unwrap(),
-    );
+    fix_instances_when_renaming_room(room, &room_names[old_id].to_text(), &room_names[new_id].to_text());
     freshen_room_ids(room);
 }
 
@@ -1787,8 +1676,8 @@ unsafe extern "fastcall" fn rename_room(room_id: usize, new_name: *const u16) ->
             show_message("Can't use illegal character '=' in asset name.");
             return ptr::null()
         }
-        let old_name = room_names[room_id].to_os_string().into_string().unwrap();
-        let new_name = new_name.to_os_string().into_string().unwrap();
+        let old_name = room_names[room_id].to_text();
+        let new_name = new_name.to_text();
         fix_instances_when_renaming_room(room, &old_name, &new_name);
     }
     &room_names[room_id]
@@ -1801,7 +1690,7 @@ lazy_static! {
 fn fix_instances_when_renaming_room(room: &mut asset::Room, old_name: &str, new_name: &str) {
     let re: &Regex = &ROOM_RENAME_REGEX;
     for inst in room.get_instances_mut() {
-        let code = inst.creation_code.to_os_string().into_string().unwrap();
+        let code = inst.creation_code.to_text();
         let mut it = re.captures_iter(&code).filter_map(|c| c.get(1)).filter(|m| m.as_str() == old_name).peekable();
         if it.peek().is_none() {
             continue
@@ -1814,7 +1703,7 @@ fn fix_instances_when_renaming_room(room: &mut asset::Room, old_name: &str, new_
             last_match = m.end();
         }
         new_code.push_str(&code[last_match..]);
-        inst.creation_code = UStr::new(new_code);
+        inst.creation_code = UStr::from_text(&new_code);
     }
 }
 
@@ -1855,16 +1744,22 @@ unsafe extern "C" fn show_instance_id_inj() {
 }
 
 unsafe extern "fastcall" fn show_instance_id(id: usize, out: &mut UStr, room_id: usize) {
-    if let Some((insts, _)) = EXTRA_DATA.as_mut() {
+    if EXTRA_DATA.is_some() {
         let suffix = {
-            let mut name = insts.entry(id).or_default().name;
+            let mut name = EXTRA_DATA.as_ref().and_then(|(insts, _)| insts.get(&id)).map_or(0, |ex| ex.name);
             if name == 0 {
-                loop {
-                    name = delphi::Random();
-                    if !insts.values().any(|ex| ex.name == name) {
-                        insts.get_mut(&id).unwrap().name = name;
-                        break
-                    }
+                // the room editor's own copy of the room can have instances the room doesn't have yet
+                let room = ide::ROOMS.assets()[room_id].as_ref();
+                let (object, x, y) = match room.and_then(|r| r.get_instances().iter().find(|i| i.id == id)) {
+                    Some(inst) => (ide::OBJECTS.names().get_asset(inst.object), inst.x, inst.y),
+                    None => (String::new(), 0, 0),
+                };
+                // the same name name_instances would give it, which then keeps it
+                let mut namer = Namer::new(save::taken_names());
+                namer.room(&ide::ROOMS.names()[room_id].to_text());
+                name = namer.name(&object, x, y);
+                if let Some((insts, _)) = EXTRA_DATA.as_mut() {
+                    insts.entry(id).or_default().name = name;
                 }
             }
             UStr::new(format!("_{:08X}", name))
@@ -1942,7 +1837,7 @@ unsafe extern "fastcall" fn room_form(room_id: usize) -> u32 {
             room_path.pop();
             let mut asset_maps_path = room_path.clone();
             room_path.push("rooms");
-            room_path.push(ide::ROOMS.names()[room_id].to_os_string());
+            room_path.push(&*file_name(&ide::ROOMS.names()[room_id].to_text()));
             let _: u32 = delphi_call!(0x51acd0, *(0x790100 as *const u32)); // hide main form
             let result = std::process::Command::new(editor_path).arg(&room_path).spawn().and_then(|mut c| c.wait());
             let _: u32 = delphi_call!(0x51acd8, *(0x790100 as *const u32)); // show main form
@@ -1985,6 +1880,8 @@ unsafe extern "fastcall" fn room_form(room_id: usize) -> u32 {
 
                 *room_opt = None; // delete room
             }
+            // paths and rooms don't have actions, so there's nothing to check against
+            let no_libs = gm82file::library::ActionLibraries::default();
             // reload whether assets exist
             let asset_maps = {
                 let mut has_backgrounds = true;
@@ -1998,7 +1895,7 @@ unsafe extern "fastcall" fn room_form(room_id: usize) -> u32 {
                 let mut has_timelines = true;
                 let mut has_triggers = true;
                 let project_path = PathBuf::from((&*ide::PROJECT_PATH).to_os_string());
-                load::read_txt(&project_path, |k, v| {
+                gm82file::load::read_txt(&project_path, |k, v| {
                     Ok(match k {
                         "has_backgrounds" => has_backgrounds = v.parse::<u8>()? != 0,
                         "has_datafiles" => has_datafiles = v.parse::<u8>()? != 0,
@@ -2014,8 +1911,9 @@ unsafe extern "fastcall" fn room_form(room_id: usize) -> u32 {
                     })
                 })
                 .expect("reloading project failed");
-                load::load_asset_maps(
+                gm82file::load::load_asset_maps(
                     &mut asset_maps_path,
+                    &no_libs,
                     has_triggers,
                     has_sprites,
                     has_sounds,
@@ -2032,18 +1930,29 @@ unsafe extern "fastcall" fn room_form(room_id: usize) -> u32 {
             asset_maps_path.push("paths");
             let path_names = &asset_maps.paths.index;
             ide::PATHS.alloc(path_names.len());
+            let mut path_keys = vec![ExtraKeys::new(); path_names.len()];
+            let timestamps =
+                gm82file::transaction::read_timestamps(&PathBuf::from((&*ide::PROJECT_PATH).to_os_string()));
             path_names
                 .iter()
                 .zip(ide::PATHS.assets_mut())
                 .zip(ide::PATHS.names_mut())
-                .try_for_each(|((name, asset), name_p)| -> Result<()> {
+                .zip(ide::PATHS.timestamps_mut())
+                .zip(&mut path_keys)
+                .try_for_each(|((((name, asset), name_p), timestamp), keys)| -> Result<()> {
                     if !name.is_empty() {
-                        *name_p = UStr::new(name);
-                        *asset = Some(load::load_path(&mut asset_maps_path.join(name), &asset_maps)?);
+                        *name_p = UStr::from_text(name);
+                        let mut path = asset_maps_path.join(&*file_name(name));
+                        let loaded = gm82file::load::load_path(&mut path, &asset_maps, timestamp)?;
+                        *timestamp = timestamps.get(&format!("paths/{name}")).copied().unwrap_or(*timestamp);
+                        *asset = Some(load::path(loaded, keys));
                     }
                     Ok(())
                 })
                 .expect("loading updated paths failed");
+            EXTRA_KEYS
+                .get_or_insert_with(Default::default)
+                .insert("paths".into(), path_keys.into_iter().enumerate().filter(|(_, k)| !k.is_empty()).collect());
             for (&form, path, name) in ide::PATHS
                 .forms()
                 .iter()
@@ -2062,14 +1971,23 @@ unsafe extern "fastcall" fn room_form(room_id: usize) -> u32 {
             }
             asset_maps_path.pop();
             (**ide::RT_PATHS).DeleteChildren();
-            load::read_resource_tree(ide::RT_PATHS, 8, "paths", &asset_maps.paths.map, true, &mut asset_maps_path)
+            let tree = gm82file::load::read_resource_tree("paths", &asset_maps.paths.map, &mut asset_maps_path)
                 .expect("loading updated path tree failed");
+            load::build_tree(ide::RT_PATHS, 8, &tree, path_names);
             // reload room
+            let mut room_keys = ExtraKeys::new();
             ide::ROOMS.assets_mut()[room_id] = Some(
-                load::load_room(&mut room_path, &asset_maps)
+                load::load_room(&mut room_path, &asset_maps, &mut ide::ROOMS.timestamps_mut()[room_id], &mut room_keys)
                     .map_err(|e| e.to_string())
                     .expect("loading the updated room failed"),
             );
+            save::name_instances();
+            let keys = EXTRA_KEYS.get_or_insert_with(Default::default).entry("rooms".into()).or_default();
+            if room_keys.is_empty() {
+                keys.remove(&room_id);
+            } else {
+                keys.insert(room_id, room_keys);
+            }
             room_path.pop();
             room_path.pop();
             update_timestamp();
@@ -2569,10 +2487,6 @@ unsafe fn injector() {
     patch_timestamp_mask(0x6f34e8);
     patch_timestamp_mask(0x6f3555);
 
-    // check for time going backwards
-    patch(0x4199fb, &[0xe9]);
-    patch_call(0x4199fb, reset_if_time_went_backwards as _);
-
     patch_call(0x6cd928, save_exe::save_assets_inj::<asset::Sound> as usize);
     patch_call(0x6cd943, save_exe::save_assets_inj::<asset::Sprite> as usize);
     patch_call(0x6cd95e, save_exe::save_assets_inj::<asset::Background> as usize);
@@ -1,32 +1,25 @@
 use crate::{
     asset::*,
     delphi,
-    delphi::{advance_progress_form, DelphiBox, UStr},
-    events, ide,
+    delphi::{advance_progress_form, DelphiBox, TTreeNode, UStr},
+    ide,
     ide::AssetListTrait,
     regular::project_watcher,
-    run_while_updating_bar, show_message, update_timestamp, Error, GMLLines, InstanceExtra, Result, TileExtra,
-    ACTION_TOKEN, EXTRA_DATA, PATH_FORM_UPDATED,
+    run_while_updating_bar, save, show_message, update_timestamp, Error, ExtraKeys, Result, EXTRA_DATA, EXTRA_KEYS,
+    PATH_FORM_UPDATED, PNG_EFFORT, PRESERVE_IDS,
 };
-use itertools::izip;
-use parking_lot::Mutex;
-use rayon::prelude::*;
-use std::{
-    collections::{HashMap, HashSet},
-    ffi::OsStr,
-    fs::File,
-    hint::unreachable_unchecked,
-    io::{BufRead, BufReader},
-    path::PathBuf,
-    slice,
+use gm82file::{
+    asset::{self as gm82, AssetListTrait as _},
+    escape::undelimit,
+    library::{self, ActionLibraries},
+    load::{AssetMaps, Diagnostics},
+    GM82_VERSION,
 };
-
-fn undelimit(s: &str) -> String {
-    s.replace("*\\/", "*/").replace("\\n", "\n").replace("\\r", "\r").replace("\\\\", "\\")
-}
+use std::{ffi::OsStr, io::Write, path::PathBuf, slice};
 
 pub trait UStrPtr {
     fn asg(self, s: impl AsRef<OsStr>);
+    fn asg_text(self, s: &str);
     fn asg_undelimit(self, s: &str);
 }
 
@@ -37,1024 +30,513 @@ impl UStrPtr for *mut UStr {
         }
     }
 
-    fn asg_undelimit(self, s: &str) {
-        self.asg(&undelimit(s));
-    }
-}
-
-fn load_gml(code: &str) -> UStr {
-    let mut buf = String::with_capacity(code.len());
-    // don't use string.replace() in case your gml is \r\n for some reason
-    for line in GMLLines::new(code.lines()) {
-        buf += line;
-        buf += "\r\n";
-    }
-    UStr::new(buf)
-}
-
-pub struct Assets {
-    pub index: Vec<String>,
-    pub map: HashMap<String, usize>,
-}
-pub struct AssetMaps {
-    triggers: Assets,
-    sprites: Assets,
-    sounds: Assets,
-    backgrounds: Assets,
-    pub paths: Assets,
-    scripts: Assets,
-    objects: Assets,
-    rooms: Assets,
-    fonts: Assets,
-    timelines: Assets,
-}
-
-fn open_file(path: &std::path::Path) -> Result<BufReader<File>> {
-    Ok(BufReader::new(File::open(path).map_err(|e| Error::FileIoError(e, path.to_path_buf()))?))
-}
-
-fn read_file<P: AsRef<std::path::Path>>(path: P) -> Result<String> {
-    std::fs::read_to_string(path.as_ref()).map_err(|e| Error::FileIoError(e, path.as_ref().to_path_buf()))
-}
-
-fn decode_line<'a, F: FnMut(&'a str, &'a str) -> Result<()>>(
-    path: &std::path::Path,
-    line: &'a str,
-    func: &mut F,
-) -> Result<()> {
-    if !line.is_empty() {
-        let (key, value) = line.split_once('=').ok_or_else(|| Error::SyntaxError(path.to_path_buf()))?;
-        func(key, value)?;
+    fn asg_text(self, s: &str) {
+        unsafe {
+            delphi::UStrAsg(self.as_mut().unwrap(), &UStr::from_text(s));
+        }
     }
-    Ok(())
-}
 
-pub fn read_txt<F: FnMut(&str, &str) -> Result<()>>(path: &std::path::Path, mut func: F) -> Result<()> {
-    let f = open_file(path)?;
-    for line in f.lines() {
-        decode_line(path, &line?, &mut func)?;
-    }
-    Ok(())
+    fn asg_undelimit(self, s: &str) {
+        self.asg_text(&undelimit(s));
+    }
+}
+
+// the IDE's action libraries, extensions included, so gm82file checks and fills in actions against the same ones
+pub unsafe fn action_libraries() -> ActionLibraries {
+    let action = |def: &ActionDefinition| library::ActionDefinition {
+        name: def.name.to_text(),
+        id: def.id,
+        hidden: def.hidden,
+        advanced: def.advanced,
+        pro_only: def.pro_only,
+        short_desc: def.short_desc.to_text(),
+        list_text: def.list_text.to_text(),
+        hint_text: def.hint_text.to_text(),
+        kind: def.kind,
+        interface: def.interface,
+        question: def.question,
+        apply_to: def.apply_to,
+        relative: def.relative,
+        arg_count: def.arg_count,
+        arg_captions: def.arg_captions.each_ref().map(UStr::to_text),
+        arg_types: def.arg_types,
+        arg_defaults: def.arg_defaults.each_ref().map(UStr::to_text),
+        arg_menus: def.arg_menu_lens.each_ref().map(UStr::to_text),
+        execution_type: def.execution_type,
+        function_name: def.function_name.to_text(),
+        code_string: def.code_string.to_text(),
+    };
+    ActionLibraries(
+        ide::get_action_libraries()
+            .iter()
+            .map(|lib| library::ActionLibrary {
+                caption: lib.caption.to_text(),
+                id: lib.id,
+                author: lib.author.to_text(),
+                version: lib.version,
+                last_changed: lib.last_changed,
+                information: lib.information.to_text(),
+                init_code: lib.init_code.to_text(),
+                advanced: lib.advanced,
+                actions: slice::from_raw_parts(lib.actions, lib.action_count).iter().map(|&def| action(def)).collect(),
+            })
+            .collect(),
+    )
 }
 
-pub unsafe fn read_resource_tree(
-    base: *const *const delphi::TTreeNode,
-    kind: u32,
-    type_name: &'static str,
-    names: &HashMap<String, usize>,
-    visible: bool,
-    path: &mut PathBuf,
-) -> Result<()> {
-    if names.is_empty() {
-        return Ok(())
-    }
-    path.push(type_name);
-    path.push("tree.yyd");
-    let f = open_file(path)?;
-    let nodes = &*((**if visible { ide::RESOURCE_TREE } else { ide::RESOURCE_TREE_HIDDEN }).nodes);
-    let mut stack = vec![base.read()];
-    for line in f.lines() {
-        let line = line?;
-        if line.is_empty() {
-            continue
-        }
-        let trimmed = line.trim_start();
-        let level = line.len() - trimmed.len();
-        stack.truncate(level + 1);
-        let rtype = match trimmed.chars().next() {
-            Some('+') => 2,
-            Some('|') => 3,
-            _ => return Err(Error::SyntaxError(path.to_path_buf())),
-        };
-        let name = &trimmed[1..];
-        let index = if rtype == 3 {
-            *names.get(name).ok_or_else(|| {
-                Error::AssetNotFound(name.to_string(), &type_name[..type_name.len() - 1], "resource tree".to_string())
-            })?
-        } else {
-            0
-        };
-
-        let node = &*nodes.AddChild(*stack.last().unwrap(), &UStr::new(name));
-        node.SetData(delphi::TreeNodeData::new(rtype, kind, index));
-        node.SetImageIndex(1);
-        if rtype == 2 {
-            stack.push(node);
-        }
-    }
-    path.pop();
-    path.pop();
-    Ok(())
-}
+// everything below copies a project gm82file has loaded into the IDE
 
-fn load_triggers(maps: &AssetMaps, path: &mut PathBuf) -> Result<()> {
-    path.push("triggers");
-    let names = &maps.triggers.index;
-    ide::alloc_triggers(names.len());
-    for (name, trig_p) in names.iter().zip(ide::get_triggers_mut()) {
-        if name.is_empty() {
-            continue
-        }
-        let mut trig = Trigger::new();
-        trig.name = UStr::new(name);
-        path.push(name);
-        path.set_extension("txt");
-        read_txt(&path, |k, v| {
-            match k {
-                "constant" => trig.constant_name = UStr::new(v),
-                "kind" => trig.kind = v.parse()?,
-                _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
-            }
-            Ok(())
-        })?;
-        path.set_extension("gml");
-        trig.condition = load_gml(&read_file(&path)?);
-        path.pop();
-        *trig_p = Some(trig);
-    }
-    path.pop();
-    Ok(())
+fn trigger(trigger: gm82::Trigger) -> DelphiBox<Trigger> {
+    let mut trig = Trigger::new();
+    trig.name = UStr::from_text(&trigger.name);
+    trig.condition = UStr::from_text(&trigger.condition);
+    trig.constant_name = UStr::from_text(&trigger.constant_name);
+    trig.kind = trigger.kind;
+    trig
 }
 
-fn verify_path(path: &std::path::Path) -> Result<()> {
-    if path.exists() {
-        Ok(())
-    } else {
-        Err(Error::FileIoError(std::io::Error::new(std::io::ErrorKind::NotFound, "file not found"), path.to_path_buf()))
-    }
+unsafe fn memory_stream(data: &[u8]) -> DelphiBox<delphi::TMemoryStream> {
+    let mut stream = delphi::TMemoryStream::new();
+    // writing to memory can't fail
+    let _ = stream.write_all(data);
+    stream.set_pos(0);
+    stream
 }
 
-unsafe fn load_sound(path: &mut PathBuf, _asset_maps: &AssetMaps) -> Result<DelphiBox<Sound>> {
+unsafe fn sound(sound: gm82::Sound, extra_keys: &mut ExtraKeys) -> DelphiBox<Sound> {
     let mut snd = Sound::new();
-    path.set_extension("txt");
-    let mut extension = String::new();
-    let mut exists = false;
-    read_txt(&path, |k, v| {
-        match k {
-            "extension" => {
-                extension = v.to_string();
-                snd.extension = UStr::new(v)
-            },
-            "source" => snd.source = UStr::new(v),
-            "exists" => exists = v.parse::<u8>()? != 0,
-            "kind" => snd.kind = v.parse()?,
-            "effects" => snd.effects = v.parse()?,
-            "volume" => snd.volume = v.parse()?,
-            "pan" => snd.pan = v.parse()?,
-            "preload" => snd.preload = v.parse::<u8>()? != 0,
-            _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
-        }
-        Ok(())
-    })?;
-    if exists {
-        path.set_extension(extension.trim_matches('.'));
-        verify_path(&path)?;
-        let data = delphi::TMemoryStream::new();
-        data.load(&UStr::new(path));
-        snd.data = Some(data);
-    }
-    Ok(snd)
+    snd.kind = sound.kind;
+    snd.extension = UStr::from_text(&sound.extension);
+    snd.effects = sound.effects;
+    snd.source = UStr::from_text(&sound.source);
+    snd.volume = sound.volume;
+    snd.pan = sound.pan;
+    snd.preload = sound.preload;
+    snd.data = sound.data.map(|data| memory_stream(&data));
+    *extra_keys = sound.extra_keys;
+    snd
 }
 
-unsafe fn load_frame(path: &std::path::Path, frame: &mut Frame) -> Result<()> {
-    use png::{BitDepth, ColorType, Decoder, Transformations};
-    let err = |e| Error::PngDecodeError(path.to_path_buf(), e);
-    // no open_file because png uses BufReader internally
-    let mut decoder = Decoder::new(File::open(&path).map_err(|e| Error::FileIoError(e, path.to_path_buf()))?);
-    decoder.set_transformations(Transformations::EXPAND | Transformations::STRIP_16);
-    let mut reader = decoder.read_info().map_err(err)?;
-    let info = reader.info();
-    frame.width = info.width;
-    frame.height = info.height;
-    // do this calculation myself in case the png is a weird format
-    let line_size = info.width as usize * 4;
-    let data_size = line_size * info.height as usize;
-    let data = slice::from_raw_parts_mut(delphi::GetMem(data_size), data_size);
-    match (info.bit_depth, info.color_type) {
-        (BitDepth::Eight, ColorType::Rgba) => {
-            // this should be the only one that actually gets used
-            // but i'll allow other formats too just to be nice
-            reader.next_frame(data).map_err(err)?;
-            // RGBA8 -> BGRA8
-            data.par_chunks_exact_mut(4).for_each(|px| px.swap(0, 2));
-        },
-        (BitDepth::Eight, ColorType::Rgb) => {
-            for dst_row in data.chunks_exact_mut(line_size) {
-                let src_row = reader.next_row().map_err(err)?.ok_or_else(|| {
-                    Error::Other(format!("decoding ended too soon for image {}", path.to_string_lossy()))
-                })?;
-                // RGB8 -> BGR8
-                for (dst, src) in dst_row.chunks_exact_mut(4).zip(src_row.data().chunks_exact(3)) {
-                    dst[0] = src[2];
-                    dst[1] = src[1];
-                    dst[2] = src[0];
-                    dst[3] = 255;
-                }
-            }
-        },
-        (BitDepth::Eight, ColorType::Grayscale) => {
-            for dst_row in data.chunks_exact_mut(line_size) {
-                let src_row = reader.next_row().map_err(err)?.ok_or_else(|| {
-                    Error::Other(format!("decoding ended too soon for image {}", path.to_string_lossy()))
-                })?;
-                for (dst, &src) in dst_row.chunks_exact_mut(4).zip(src_row.data()) {
-                    dst[0..3].fill(src);
-                    dst[3] = 255;
-                }
-            }
-        },
-        (BitDepth::Eight, ColorType::GrayscaleAlpha) => {
-            for dst_row in data.chunks_exact_mut(line_size) {
-                let src_row = reader.next_row().map_err(err)?.ok_or_else(|| {
-                    Error::Other(format!("decoding ended too soon for image {}", path.to_string_lossy()))
-                })?;
-                for (dst, src) in dst_row.chunks_exact_mut(4).zip(src_row.data().chunks_exact(2)) {
-                    dst[0..3].fill(src[0]);
-                    dst[3] = src[1];
-                }
-            }
-        },
-        (depth, coltype) => {
-            // the above should cover all valid formats
-            // see https://www.w3.org/TR/PNG-Chunks.html
-            return Err(Error::Other(format!(
-                "couldn't understand format {:?}/{:?} for image {}",
-                depth,
-                coltype,
-                path.to_string_lossy()
-            )))
-        },
-    }
-    frame.data = data.as_ptr();
-    Ok(())
-}
-
-unsafe fn load_background(path: &mut PathBuf, _asset_maps: &AssetMaps) -> Result<DelphiBox<Background>> {
-    let mut bg = Background::new();
-    path.set_extension("txt");
-    let mut bg_exists = false;
-    read_txt(path, |k, v| {
-        match k {
-            "exists" => bg_exists = v.parse::<u8>()? != 0,
-            "tileset" => bg.is_tileset = v.parse::<u8>()? != 0,
-            "tile_width" => bg.tile_width = v.parse()?,
-            "tile_height" => bg.tile_height = v.parse()?,
-            "tile_hoffset" => bg.h_offset = v.parse()?,
-            "tile_voffset" => bg.v_offset = v.parse()?,
-            "tile_hsep" => bg.h_sep = v.parse()?,
-            "tile_vsep" => bg.v_sep = v.parse()?,
-            _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
-        }
-        Ok(())
-    })?;
-    if bg_exists {
-        path.set_extension("png");
-        load_frame(path, &mut bg.frame)?;
+unsafe fn frame(frame: &gm82::Frame, out: &mut Frame) {
+    out.width = frame.width;
+    out.height = frame.height;
+    if !frame.data.is_empty() {
+        let data = delphi::GetMem::<u8>(frame.data.len());
+        data.copy_from_nonoverlapping(frame.data.as_ptr(), frame.data.len());
+        out.data = data;
     }
-    Ok(bg)
 }
 
-unsafe fn load_sprite(path: &mut PathBuf, _asset_maps: &AssetMaps) -> Result<DelphiBox<Sprite>> {
+unsafe fn sprite(sprite: gm82::Sprite, extra_keys: &mut ExtraKeys) -> DelphiBox<Sprite> {
     let mut sp = Sprite::new();
-    path.push("sprite.txt");
-    read_txt(&path, |k, v| {
-        match k {
-            "frames" => sp.frame_count = v.parse()?,
-            "origin_x" => sp.origin_x = v.parse()?,
-            "origin_y" => sp.origin_y = v.parse()?,
-            "collision_shape" => sp.collision_shape = v.parse()?,
-            "alpha_tolerance" => sp.alpha_tolerance = v.parse()?,
-            "per_frame_colliders" => sp.per_frame_colliders = v.parse::<u8>()? != 0,
-            "bbox_type" => sp.bbox_type = v.parse()?,
-            "bbox_left" => sp.bbox_left = v.parse()?,
-            "bbox_bottom" => sp.bbox_bottom = v.parse()?,
-            "bbox_right" => sp.bbox_right = v.parse()?,
-            "bbox_top" => sp.bbox_top = v.parse()?,
-            _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
-        }
-        Ok(())
-    })?;
-    path.pop();
-    let frame_count = sp.frame_count as usize;
-    for (i, f) in sp.alloc_frames(frame_count).iter_mut().enumerate() {
-        path.push(format!("{}.png", i));
-        load_frame(&path, f)?;
-        path.pop();
-    }
-    Ok(sp)
-}
-
-fn load_script(path: &mut PathBuf, _asset_maps: &AssetMaps) -> Result<DelphiBox<Script>> {
-    path.set_extension("gml");
+    sp.origin_x = sprite.origin_x;
+    sp.origin_y = sprite.origin_y;
+    sp.collision_shape = sprite.collision_shape;
+    sp.alpha_tolerance = sprite.alpha_tolerance;
+    sp.per_frame_colliders = sprite.per_frame_colliders;
+    sp.bbox_type = sprite.bbox_type;
+    sp.bbox_left = sprite.bbox_left;
+    sp.bbox_top = sprite.bbox_top;
+    sp.bbox_right = sprite.bbox_right;
+    sp.bbox_bottom = sprite.bbox_bottom;
+    for (f, out) in sprite.frames.iter().zip(sp.alloc_frames(sprite.frames.len())) {
+        frame(f, out);
+    }
+    *extra_keys = sprite.extra_keys;
+    sp
+}
+
+unsafe fn background(back: gm82::Background, extra_keys: &mut ExtraKeys) -> DelphiBox<Background> {
+    let mut bg = Background::new();
+    frame(&back.frame, &mut bg.frame);
+    bg.is_tileset = back.is_tileset;
+    bg.tile_width = back.tile_width;
+    bg.tile_height = back.tile_height;
+    bg.h_offset = back.h_offset;
+    bg.v_offset = back.v_offset;
+    bg.h_sep = back.h_sep;
+    bg.v_sep = back.v_sep;
+    *extra_keys = back.extra_keys;
+    bg
+}
+
+pub unsafe fn path(path: gm82::Path, extra_keys: &mut ExtraKeys) -> DelphiBox<Path> {
+    let mut p = Path::new();
+    for (point, out) in path.points.iter().zip(p.alloc_points(path.points.len())) {
+        *out = PathPoint { x: point.x, y: point.y, speed: point.speed };
+    }
+    p.connection = path.connection;
+    p.closed = path.closed;
+    p.precision = path.precision;
+    p.path_editor_room_background = path.path_editor_room_background;
+    p.snap_x = path.snap_x;
+    p.snap_y = path.snap_y;
+    p.commit();
+    *extra_keys = path.extra_keys;
+    p
+}
+
+unsafe fn script(script: gm82::Script, _extra_keys: &mut ExtraKeys) -> DelphiBox<Script> {
     let mut s = Script::new();
-    s.source = load_gml(&read_file(path)?);
-    Ok(s)
+    s.source = UStr::from_text(&script.source);
+    s
 }
 
-fn load_font(path: &mut PathBuf, _asset_maps: &AssetMaps) -> Result<DelphiBox<Font>> {
+unsafe fn font(font: gm82::Font, extra_keys: &mut ExtraKeys) -> DelphiBox<Font> {
     let mut f = Font::new();
-    path.set_extension("txt");
-    read_txt(path, |k, v| {
-        match k {
-            "name" => f.sys_name = UStr::new(v),
-            "size" => f.size = v.parse()?,
-            "bold" => f.bold = v.parse::<u8>()? != 0,
-            "italic" => f.italic = v.parse::<u8>()? != 0,
-            "charset" => f.charset = v.parse()?,
-            "aa_level" => f.aa_level = v.parse()?, // DOES NOT CORRESPOND TO .GMK OR .EXE
-            "range_start" => f.range_start = v.parse()?,
-            "range_end" => f.range_end = v.parse()?,
-            _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
-        }
-        Ok(())
-    })?;
-    Ok(f)
-}
-
-unsafe fn load_event(
-    path: &std::path::Path,
-    event: &mut Event,
-    event_code: &str,
-    asset_maps: &AssetMaps,
-) -> Result<()> {
-    let object_name = path.file_stem().map(OsStr::to_string_lossy).unwrap_or_default();
-    for action_code in event_code.split(ACTION_TOKEN) {
-        if action_code.trim().is_empty() {
-            continue
-        }
-        let (params, code) = action_code.split_once("*/").ok_or_else(|| Error::SyntaxError(path.to_path_buf()))?;
-        let action = event.add_action(0, 0);
-        let mut lib_id_set = false;
-        let mut act_id_set = false;
-        for line in params.lines() {
-            decode_line(path, line, &mut |k, v| {
-                match k {
-                    "lib_id" => {
-                        action.lib_id = v.parse()?;
-                        if lib_id_set {
-                            return Err(Error::SyntaxError(path.to_path_buf()))
-                        }
-                        lib_id_set = true;
-                    },
-                    "action_id" => {
-                        action.id = v.parse()?;
-                        if !lib_id_set || act_id_set {
-                            return Err(Error::SyntaxError(path.to_path_buf()))
-                        }
-                        act_id_set = true;
-                        // manually check if action exists so we can throw an error
-                        if ide::get_action_libraries().iter().any(|&l| {
-                            l.id == action.lib_id
-                                && slice::from_raw_parts(l.actions, l.action_count).iter().any(|&a| a.id == action.id)
-                        }) {
-                            action.fill_in(action.lib_id, action.id);
-                        } else {
-                            return Err(Error::UnknownAction(action.lib_id, action.id))
-                        }
-                    },
-                    "relative" => action.is_relative = v.parse::<u8>()? != 0,
-                    "applies_to" => {
-                        action.applies_to = match v {
-                            "other" => -2,
-                            "self" => -1,
-                            "" => -4,
-                            name => *asset_maps.objects.map.get(name).ok_or_else(|| {
-                                Error::AssetNotFound(
-                                    name.to_string(),
-                                    "object",
-                                    format!("object {object_name} code action applies_to"),
-                                )
-                            })? as _,
-                        }
-                    },
-                    "invert" => action.invert_condition = v.parse::<u8>()? != 0,
-                    "var_name" | "repeats" => action.param_strings[0] = UStr::new(v),
-                    "var_value" => action.param_strings[1] = UStr::new(v),
-                    "arg0" | "arg1" | "arg2" | "arg3" | "arg4" | "arg5" | "arg6" | "arg7" => {
-                        if !act_id_set {
-                            return Err(Error::SyntaxError(path.to_path_buf()))
-                        }
-                        let i = k.chars().last().unwrap().to_digit(8).unwrap() as usize;
-                        let err = |t| {
-                            Error::AssetNotFound(
-                                v.to_string(),
-                                t,
-                                format!("object {object_name} code action parameter"),
-                            )
-                        };
-                        let ptype = action.param_types[i];
-                        if (5..=14).contains(&ptype) && ptype != 13 {
-                            action.param_strings[i] = UStr::new(
-                                match action.param_types[i] {
-                                    _ if v.is_empty() => -1,
-                                    5 => *asset_maps.sprites.map.get(v).ok_or_else(|| err("sprite"))? as _,
-                                    6 => *asset_maps.sounds.map.get(v).ok_or_else(|| err("sound"))? as _,
-                                    7 => *asset_maps.backgrounds.map.get(v).ok_or_else(|| err("background"))? as _,
-                                    8 => *asset_maps.paths.map.get(v).ok_or_else(|| err("path"))? as _,
-                                    9 => *asset_maps.scripts.map.get(v).ok_or_else(|| err("script"))? as _,
-                                    10 => *asset_maps.objects.map.get(v).ok_or_else(|| err("object"))? as _,
-                                    11 => *asset_maps.rooms.map.get(v).ok_or_else(|| err("room"))? as _,
-                                    12 => *asset_maps.fonts.map.get(v).ok_or_else(|| err("font"))? as _,
-                                    14 => *asset_maps.timelines.map.get(v).ok_or_else(|| err("timeline"))? as _,
-                                    _ => unreachable_unchecked(),
-                                }
-                                .to_string(),
-                            );
-                        } else {
-                            action.param_strings[i] = UStr::new(undelimit(v));
-                        }
-                    },
-                    _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
-                }
-                Ok(())
-            })?;
-        }
-        if action.action_kind == 7 {
-            // first character will always be a newline because it doesn't cut the newline when searching for */
-            // so skip it
-            let code = if code.starts_with('\n') {
-                &code[1..]
-            } else if code.starts_with("\r\n") {
-                &code[2..]
-            } else {
-                code
-            };
-            action.param_strings[0] = load_gml(code);
-        }
+    f.sys_name = UStr::from_text(&font.sys_name);
+    f.size = font.size;
+    f.bold = font.bold;
+    f.italic = font.italic;
+    f.range_start = font.range_start;
+    f.range_end = font.range_end;
+    f.charset = font.charset;
+    f.aa_level = font.aa_level;
+    *extra_keys = font.extra_keys;
+    f
+}
+
+unsafe fn event(event: &gm82::Event, out: &mut Event) {
+    for action in &event.actions {
+        let out = out.add_action(0, 0);
+        // gm82file has filled these in from the same libraries already, this sets up the rest of the IDE's side
+        out.fill_in(action.lib_id, action.id);
+        out.lib_id = action.lib_id;
+        out.id = action.id;
+        out.action_kind = action.action_kind;
+        out.can_be_relative = action.can_be_relative;
+        out.is_condition = action.is_condition;
+        out.applies_to_something = action.applies_to_something;
+        out.execution_type = action.execution_type;
+        out.fn_name = UStr::from_text(&action.fn_name);
+        out.fn_code = UStr::from_text(&action.fn_code);
+        out.param_count = action.param_count;
+        out.param_types = action.param_types;
+        out.applies_to = action.applies_to;
+        out.is_relative = action.is_relative;
+        out.param_strings = action.param_strings.each_ref().map(|s| UStr::from_text(s));
+        out.invert_condition = action.invert_condition;
+    }
+}
+
+unsafe fn timeline(timeline: gm82::Timeline, _extra_keys: &mut ExtraKeys) -> DelphiBox<Timeline> {
+    let mut tl = Timeline::new();
+    let (events, times) = tl.alloc(timeline.moments.len());
+    for ((time, ev), (out_ev, out_time)) in timeline.moments.iter().zip(events.iter_mut().zip(times)) {
+        *out_time = *time;
+        event(ev, out_ev);
     }
-    Ok(())
+    tl
 }
 
-unsafe fn load_object(path: &mut PathBuf, asset_maps: &AssetMaps) -> Result<DelphiBox<Object>> {
-    path.set_extension("txt");
-    let object_name = path.file_stem().map(OsStr::to_string_lossy).unwrap_or_default();
+unsafe fn object(object: gm82::Object, extra_keys: &mut ExtraKeys) -> DelphiBox<Object> {
     let mut obj = Object::new();
-    let sprite_map = &asset_maps.sprites.map;
-    let object_map = &asset_maps.objects.map;
-    let trigger_map = &asset_maps.triggers.map;
-    read_txt(&path, |k, v| {
-        match k {
-            "sprite" => {
-                obj.sprite_index = match sprite_map.get(v) {
-                    Some(&i) => i as _,
-                    None if v.is_empty() => -1,
-                    _ => {
-                        return Err(Error::AssetNotFound(
-                            v.to_string(),
-                            "sprite",
-                            format!("object {object_name} sprite"),
-                        ))
-                    },
-                }
-            },
-            "visible" => obj.visible = v.parse::<u8>()? != 0,
-            "solid" => obj.solid = v.parse::<u8>()? != 0,
-            "persistent" => obj.persistent = v.parse::<u8>()? != 0,
-            "depth" => obj.depth = v.parse()?,
-            "parent" => {
-                obj.parent_index = match object_map.get(v) {
-                    Some(&i) => i as _,
-                    None if v.is_empty() => -1,
-                    _ => {
-                        return Err(Error::AssetNotFound(
-                            v.to_string(),
-                            "object",
-                            format!("object {object_name} parent"),
-                        ))
-                    },
-                }
-            },
-            "mask" => {
-                obj.mask_index = match sprite_map.get(v) {
-                    Some(&i) => i as _,
-                    None if v.is_empty() => -1,
-                    _ => {
-                        return Err(Error::AssetNotFound(v.to_string(), "sprite", format!("object {object_name} mask")))
-                    },
-                }
-            },
-            _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
-        }
-        Ok(())
-    })?;
-    path.set_extension("gml");
-    let code = read_file(&path)?;
-    for event in code.trim_start_matches("#define ").split("\n#define ") {
-        if event.trim().is_empty() {
-            continue
+    obj.sprite_index = object.sprite_index;
+    obj.solid = object.solid;
+    obj.visible = object.visible;
+    obj.depth = object.depth;
+    obj.persistent = object.persistent;
+    obj.parent_index = object.parent_index;
+    obj.mask_index = object.mask_index;
+    for (ev_type, group) in object.events.iter().enumerate() {
+        for (&ev_numb, ev) in group {
+            event(ev, obj.get_event(ev_type, ev_numb));
         }
-        let err = || Error::SyntaxError(path.to_path_buf());
-        let (name, actions) = event.split_once("\n").ok_or_else(err)?;
-        let (ev_type_s, ev_numb_s) = name.trim().split_once("_").ok_or_else(err)?;
-        let ev_type = events::EVENT_NAMES.iter().position(|&s| s == ev_type_s).ok_or_else(err)?;
-        let ev_numb = match ev_type {
-            events::EV_COLLISION => *object_map.get(ev_numb_s).ok_or_else(err)?,
-            events::EV_TRIGGER => *trigger_map.get(ev_numb_s).ok_or_else(err)?,
-            _ => ev_numb_s.parse()?,
+    }
+    *extra_keys = object.extra_keys;
+    obj
+}
+
+unsafe fn room(room: gm82::Room, extra_keys: &mut ExtraKeys) -> DelphiBox<Room> {
+    let mut r = Room::new();
+    r.caption = UStr::from_text(&room.caption);
+    r.speed = room.speed;
+    r.width = room.width;
+    r.height = room.height;
+    r.snap_x = room.snap_x;
+    r.snap_y = room.snap_y;
+    r.isometric = room.isometric;
+    r.persistent = room.persistent;
+    r.bg_colour = room.bg_colour;
+    r.clear_screen = room.clear_screen;
+    for (bg, out) in room.backgrounds.iter().zip(&mut r.backgrounds) {
+        *out = RoomBackground {
+            visible_on_start: bg.visible_on_start,
+            is_foreground: bg.is_foreground,
+            source_bg: bg.source_bg,
+            xoffset: bg.xoffset,
+            yoffset: bg.yoffset,
+            tile_horz: bg.tile_horz,
+            tile_vert: bg.tile_vert,
+            hspeed: bg.hspeed,
+            vspeed: bg.vspeed,
+            stretch: bg.stretch,
         };
-        let event = obj.get_event(ev_type, ev_numb);
-        load_event(&path, event, actions, asset_maps)?;
     }
-    Ok(obj)
-}
-
-unsafe fn load_timeline(path: &mut PathBuf, asset_maps: &AssetMaps) -> Result<DelphiBox<Timeline>> {
-    let mut tl = Timeline::new();
-    path.set_extension("gml");
-    let code = read_file(&path)?;
-    let iter = code.trim_start_matches("#define ").split("\n#define ");
-    let (events, times) = tl.alloc(iter.clone().count());
-    for (code, time_p, event) in izip!(iter, times, events) {
-        if code.trim().is_empty() {
-            continue
-        }
-        let (name, actions) = match code.split_once("\n") {
-            Some(tuple) => tuple,
-            None if code.as_bytes().iter().all(u8::is_ascii_digit) => (code, ""), // #define 1\n#define 2
-            None => return Err(Error::SyntaxError(path.to_path_buf())),
+    r.views_enabled = room.views_enabled;
+    r.clear_view = room.clear_view;
+    for (view, out) in room.views.iter().zip(&mut r.views) {
+        *out = View {
+            visible: view.visible,
+            source_x: view.source_x,
+            source_y: view.source_y,
+            source_w: view.source_w,
+            source_h: view.source_h,
+            port_x: view.port_x,
+            port_y: view.port_y,
+            port_w: view.port_w,
+            port_h: view.port_h,
+            following_hborder: view.following_hborder,
+            following_vborder: view.following_vborder,
+            following_hspeed: view.following_hspeed,
+            following_vspeed: view.following_vspeed,
+            following_target: view.following_target,
         };
-        *time_p = name.trim().parse()?;
-        load_event(&path, event, actions, asset_maps)?;
     }
-    Ok(tl)
-}
-
-pub fn load_path(file_path: &mut PathBuf, asset_maps: &AssetMaps) -> Result<DelphiBox<Path>> {
-    let mut path = Path::new();
-    file_path.push("path.txt");
-    let path_name =
-        file_path.parent().and_then(std::path::Path::file_name).map(OsStr::to_string_lossy).unwrap_or_default();
-    read_txt(&file_path, |k, v| {
-        match k {
-            "connection" => path.connection = v.parse()?,
-            "closed" => path.closed = v.parse::<u8>()? != 0,
-            "precision" => path.precision = v.parse()?,
-            "background" => {
-                path.path_editor_room_background = if v.is_empty() {
-                    -1
-                } else {
-                    *asset_maps.rooms.map.get(v).ok_or_else(|| {
-                        Error::AssetNotFound(v.to_string(), "room", format!("path {path_name} room background"))
-                    })? as _
+    r.creation_code = UStr::from_text(&room.creation_code);
+    let (extra_insts, extra_tiles) = EXTRA_DATA.get_or_insert_with(Default::default);
+    for (inst, out) in room.instances.iter().zip(r.alloc_instances(room.instances.len())) {
+        out.x = inst.x;
+        out.y = inst.y;
+        out.object = inst.object;
+        out.id = inst.id;
+        out.creation_code = UStr::from_text(&inst.creation_code);
+        out.locked = inst.locked;
+        extra_insts.insert(inst.id, inst.extra.clone());
+    }
+    r.put_tiles(
+        room.tiles
+            .iter()
+            .map(|tile| {
+                extra_tiles.insert(tile.id, tile.extra.clone());
+                Tile {
+                    x: tile.x,
+                    y: tile.y,
+                    source_bg: tile.source_bg,
+                    u: tile.u,
+                    v: tile.v,
+                    width: tile.width,
+                    height: tile.height,
+                    depth: tile.depth,
+                    id: tile.id,
+                    locked: tile.locked,
                 }
-            },
-            "snap_x" => path.snap_x = v.parse()?,
-            "snap_y" => path.snap_y = v.parse()?,
-            _ => return Err(Error::UnknownKey(file_path.to_path_buf(), k.to_string())),
-        }
-        Ok(())
-    })?;
-    file_path.pop();
-    file_path.push("points.txt");
-    let points_txt = read_file(&file_path)?;
-    let point_lines: Vec<_> = points_txt.par_lines().collect();
-    for (point, line) in path.alloc_points(point_lines.len()).iter_mut().zip(point_lines) {
-        let mut iter = line.split(',');
-        let err = || Error::SyntaxError(file_path.to_path_buf());
-        point.x = iter.next().ok_or_else(err)?.parse()?;
-        point.y = iter.next().ok_or_else(err)?.parse()?;
-        point.speed = iter.next().ok_or_else(err)?.parse()?;
-        if iter.next() != None {
-            return Err(err())
+            })
+            .collect(),
+    );
+    r.calc_extents();
+    r.remember_room_editor_info = room.remember_room_editor_info;
+    r.editor_width = room.editor_width;
+    r.editor_height = room.editor_height;
+    r.show_grid = room.show_grid;
+    r.show_objects = room.show_objects;
+    r.show_tiles = room.show_tiles;
+    r.show_backgrounds = room.show_backgrounds;
+    r.show_foregrounds = room.show_foregrounds;
+    r.show_views = room.show_views;
+    r.delete_underlying_objects = room.delete_underlying_objects;
+    r.delete_underlying_tiles = room.delete_underlying_tiles;
+    r.tab = room.tab;
+    r.x_position_scroll = room.x_position_scroll;
+    r.y_position_scroll = room.y_position_scroll;
+    *extra_keys = room.extra_keys;
+    r
+}
+
+unsafe fn included_file(file: gm82::IncludedFile, out: &mut IncludedFile) {
+    out.file_name = UStr::from_text(&file.file_name);
+    out.source_path = UStr::new(&file.source_path);
+    out.data_exists = file.data_exists;
+    out.source_length = file.source_length;
+    out.stored_in_gmk = file.stored_in_gmk;
+    if let Some(data) = file.data {
+        out.data = memory_stream(&data);
+    }
+    out.export_setting = file.export_setting;
+    out.export_custom_folder = UStr::from_text(&file.export_custom_folder);
+    out.overwrite_file = file.overwrite_file;
+    out.free_memory = file.free_memory;
+    out.remove_at_end = file.remove_at_end;
+}
+
+// kind is what the IDE calls this type of asset in the tree
+pub unsafe fn build_tree(base: *const *const TTreeNode, kind: u32, tree: &[gm82::TreeNode], names: &[String]) {
+    let nodes = &*((**ide::RESOURCE_TREE).nodes);
+    let mut stack = vec![(base.read(), tree.iter())];
+    while let Some((parent, children)) = stack.last_mut() {
+        let parent = *parent;
+        let Some(child) = children.next() else {
+            stack.pop();
+            continue
+        };
+        let (rtype, name, index) = match child {
+            gm82::TreeNode::Group(name, _) => (2, name.as_str(), 0),
+            gm82::TreeNode::Asset(index) => (3, names[*index].as_str(), *index),
+        };
+        let node = &*nodes.AddChild(parent, &UStr::from_text(name));
+        node.SetData(delphi::TreeNodeData::new(rtype, kind, index));
+        node.SetImageIndex(1);
+        if let gm82::TreeNode::Group(_, grandchildren) = child {
+            stack.push((node, grandchildren.iter()));
         }
     }
-    path.commit();
-    file_path.pop();
-    Ok(path)
 }
 
-unsafe fn load_instances(room: &mut Room, path: &mut PathBuf, objs: &HashMap<String, usize>) -> Result<()> {
-    path.push("instances.txt");
-    let instances_txt = read_file(&path)?;
-    let instances: Vec<_> = instances_txt.lines().filter(|s| !s.is_empty()).collect();
-    let inst_path = path.to_path_buf(); // save instances.txt path for errors
-    path.pop();
-    let room_name = path.file_name().map(OsStr::to_string_lossy).unwrap_or_default();
-    let err = || Error::SyntaxError(inst_path.to_path_buf());
-    let last_instance_id = *ide::LAST_INSTANCE_ID + 1;
-    let ids = Mutex::new(HashSet::with_capacity(instances.len()));
-    let mut extras: Vec<InstanceExtra> = vec![Default::default(); instances.len()];
-    room.alloc_instances(instances.len()).into_par_iter().zip(&instances).enumerate().zip(&mut extras).try_for_each(
-        |((i, (instance, line)), extra)| -> Result<()> {
-            let mut iter = line.split(',');
-            instance.object = match iter.next().ok_or_else(err)? {
-                "" => -1,
-                obj => *objs.get(obj).ok_or_else(|| {
-                    Error::AssetNotFound(obj.to_string(), "object", format!("room {room_name} instances"))
-                })? as _,
-            };
-            instance.x = iter.next().ok_or_else(err)?.parse()?;
-            instance.y = iter.next().ok_or_else(err)?.parse()?;
-            let code_hash = iter.next().ok_or_else(err)?;
-            extra.name = if !code_hash.is_empty() {
-                // check if hash is taken
-                let mut id = u32::from_str_radix(code_hash, 16)?;
-                // while it's taken, generate random id
-                while ids.lock().contains(&id) {
-                    id = delphi::Random();
-                }
-                id
-            } else {
-                // no id, generate random id until we get an unused one
-                loop {
-                    let id = delphi::Random();
-                    if !ids.lock().contains(&id) {
-                        break id
-                    }
-                }
-            };
-            ids.lock().insert(extra.name);
-            instance.locked = iter.next().ok_or_else(err)?.parse::<u8>()? != 0;
-            instance.id = last_instance_id + i;
-            if let Some(s) = iter.next() {
-                extra.xscale = s.parse()?;
-            }
-            if let Some(s) = iter.next() {
-                extra.yscale = s.parse()?;
-            }
-            if let Some(s) = iter.next() {
-                extra.blend = s.parse()?;
-            }
-            if let Some(s) = iter.next() {
-                extra.angle = s.parse()?;
-            }
-            // use the id from the file, not the generated one if relevant
-            let has_code = if let Some(s) = iter.next() { s.parse::<u8>()? != 0 } else { !code_hash.is_empty() };
-            if has_code {
-                let mut path = path.join(code_hash);
-                path.set_extension("gml");
-                instance.creation_code = load_gml(&read_file(&path)?);
+unsafe fn put_assets<T: 'static, U>(
+    name: &str,
+    list: &impl AssetListTrait<T>,
+    assets: gm82::AssetList<U>,
+    convert: unsafe fn(U, &mut ExtraKeys) -> DelphiBox<T>,
+) {
+    list.alloc(assets.assets.len());
+    let keys = EXTRA_KEYS.get_or_insert_with(Default::default).entry(name.to_string()).or_default();
+    for (i, ((asset, name), timestamp)) in
+        assets.assets.into_iter().zip(&assets.names).zip(assets.timestamps).enumerate()
+    {
+        if let Some(asset) = asset {
+            let mut extra_keys = ExtraKeys::new();
+            list.assets_mut()[i] = Some(convert(asset, &mut extra_keys));
+            list.names_mut()[i] = UStr::from_text(name);
+            list.timestamps_mut()[i] = timestamp;
+            if !extra_keys.is_empty() {
+                keys.insert(i, extra_keys);
             }
-            Ok(())
-        },
-    )?;
-    let extra_data = &mut EXTRA_DATA.as_mut().unwrap().0;
-    extra_data.reserve(extras.len());
-    for (inst, extra) in room.get_instances().iter().zip(extras.drain(..)) {
-        extra_data.insert(inst.id, extra);
+        }
     }
-    *ide::LAST_INSTANCE_ID += instances.len();
-    Ok(())
 }
 
-unsafe fn load_tiles(path: &mut PathBuf, bgs: &HashMap<String, usize>) -> Result<Vec<Tile>> {
-    let mut tiles = Vec::new();
-    path.push("layers.txt");
-    let f = open_file(&path)?;
-    path.pop();
-    let extra_data = &mut EXTRA_DATA.as_mut().unwrap().1;
-    for line in f.lines() {
-        let line = line?;
-        if line.is_empty() {
-            continue
-        }
-        let depth = line.parse()?;
-        path.push(line);
-        path.set_extension("txt");
-        let room_name =
-            path.parent().and_then(std::path::Path::file_name).map(OsStr::to_string_lossy).unwrap_or_default();
-        let layer_txt = read_file(&path)?;
-        let layer: Vec<_> = layer_txt.lines().filter(|s| !s.is_empty()).collect();
-        tiles.reserve(layer.len());
-        let err = || Error::SyntaxError(path.to_path_buf());
-        let last_tile_id = *ide::LAST_TILE_ID + 1;
-        let mut extras: Vec<TileExtra> = vec![Default::default(); layer.len()];
-        let layer_tiles = layer
-            .par_iter()
-            .enumerate()
-            .zip(&mut extras)
-            .map(|((i, tile), extra)| {
-                let mut iter = tile.split(',');
-                let t = Tile {
-                    source_bg: match iter.next().ok_or_else(err)? {
-                        "" => -1,
-                        bg => *bgs.get(bg).ok_or_else(|| {
-                            Error::AssetNotFound(bg.to_string(), "background", format!("room {room_name} tiles"))
-                        })? as _,
-                    },
-                    x: iter.next().ok_or_else(err)?.parse()?,
-                    y: iter.next().ok_or_else(err)?.parse()?,
-                    u: iter.next().ok_or_else(err)?.parse()?,
-                    v: iter.next().ok_or_else(err)?.parse()?,
-                    width: iter.next().ok_or_else(err)?.parse()?,
-                    height: iter.next().ok_or_else(err)?.parse()?,
-                    locked: iter.next().ok_or_else(err)?.parse::<u8>()? != 0,
-                    depth,
-                    id: last_tile_id + i,
-                };
-                if let Some(s) = iter.next() {
-                    extra.xscale = s.parse()?;
-                }
-                if let Some(s) = iter.next() {
-                    extra.yscale = s.parse()?;
-                }
-                if let Some(s) = iter.next() {
-                    extra.blend = s.parse()?;
-                }
-                if iter.next() != None {
-                    return Err(err())
-                }
-                Ok(t)
-            })
-            .collect::<Result<Vec<_>>>()?;
-        extra_data.reserve(extras.len());
-        for (tile, extra) in layer_tiles.iter().zip(extras.drain(..)) {
-            extra_data.insert(tile.id, extra);
-        }
-        *ide::LAST_TILE_ID += layer_tiles.len();
-        tiles.extend_from_slice(&layer_tiles);
+unsafe fn settings(settings: gm82::Settings, mut path: PathBuf) {
+    use ide::settings::*;
+    FULLSCREEN.write(settings.fullscreen);
+    INTERPOLATE_PIXELS.write(settings.interpolate_pixels);
+    DONT_DRAW_BORDER.write(settings.dont_draw_border);
+    DISPLAY_CURSOR.write(settings.display_cursor);
+    SCALING.write(settings.scaling);
+    ALLOW_RESIZE.write(settings.allow_resize);
+    WINDOW_ON_TOP.write(settings.window_on_top);
+    CLEAR_COLOUR.write(settings.clear_colour);
+    SET_RESOLUTION.write(settings.set_resolution);
+    COLOUR_DEPTH.write(settings.colour_depth);
+    RESOLUTION.write(settings.resolution);
+    FREQUENCY.write(settings.frequency);
+    DONT_SHOW_BUTTONS.write(settings.dont_show_buttons);
+    VSYNC_AND_FORCE_CPU.write(settings.vsync_and_force_cpu);
+    DISABLE_SCREENSAVER.write(settings.disable_screensaver);
+    F4_FULLSCREEN.write(settings.f4_fullscreen);
+    F1_HELP.write(settings.f1_help);
+    ESC_CLOSE.write(settings.esc_close);
+    F5_SAVE_F6_LOAD.write(settings.f5_save_f6_load);
+    F9_SCREENSHOT.write(settings.f9_screenshot);
+    TREAT_CLOSE_AS_ESC.write(settings.treat_close_as_esc);
+    PRIORITY.write(settings.priority);
+    FREEZE_ON_LOSE_FOCUS.write(settings.freeze_on_lose_focus);
+    LOADING_BAR.write(settings.loading_bar);
+    HAS_CUSTOM_LOAD_IMAGE.write(settings.has_custom_load_image);
+    LOADING_TRANSPARENT.write(settings.loading_transparent);
+    LOADING_TRANSLUCENCY.write(settings.loading_translucency);
+    LOADING_PROGRESS_BAR_SCALE.write(settings.loading_progress_bar_scale);
+    SHOW_ERROR_MESSAGES.write(settings.show_error_messages);
+    LOG_ERRORS.write(settings.log_errors);
+    ALWAYS_ABORT.write(settings.always_abort);
+    ZERO_UNINITIALIZED_VARS.write(settings.zero_uninitialized_vars);
+    ERROR_ON_UNINITIALIZED_ARGS.write(settings.error_on_uninitialized_args);
+    INFO_AUTHOR.asg_text(&settings.info_author);
+    INFO_VERSION.asg_text(&settings.info_version);
+    INFO_TIMESTAMP.write(settings.info_timestamp);
+    INFO_INFORMATION.asg_text(&settings.info_information);
+    VERSION_MAJOR.write(settings.version_major);
+    VERSION_MINOR.write(settings.version_minor);
+    VERSION_RELEASE.write(settings.version_release);
+    VERSION_BUILD.write(settings.version_build);
+    EXE_COMPANY.asg_text(&settings.exe_company);
+    EXE_PRODUCT.asg_text(&settings.exe_product);
+    EXE_COPYRIGHT.asg_text(&settings.exe_copyright);
+    EXE_DESCRIPTION.asg_text(&settings.exe_description);
+    // delphi can only load these from a file, and they're in the settings folder just as gm82file found them
+    let bitmap = |path: &mut PathBuf, name: &str| {
+        path.push(name);
+        let bitmap = delphi::TBitmap::new();
+        bitmap.LoadFromFile(&UStr::new(&path));
         path.pop();
+        Some(bitmap)
+    };
+    if settings.loading_background.is_some() {
+        *LOADING_BACKGROUND = bitmap(&mut path, "back.bmp");
     }
-    Ok(tiles)
-}
-
-pub unsafe fn load_room(path: &mut PathBuf, asset_maps: &AssetMaps) -> Result<DelphiBox<Room>> {
-    let mut room = Room::new();
-    path.push("room.txt");
-    let room_name = path.parent().and_then(std::path::Path::file_name).map(OsStr::to_string_lossy).unwrap_or_default();
-    read_txt(&path, |k, v| {
-        match k {
-            "caption" => room.caption = UStr::new(v),
-            "width" => room.width = v.parse()?,
-            "height" => room.height = v.parse()?,
-            "snap_x" => room.snap_x = v.parse()?,
-            "snap_y" => room.snap_y = v.parse()?,
-            "isometric" => room.isometric = v.parse::<u8>()? != 0,
-            "roomspeed" => room.speed = v.parse()?,
-            "roompersistent" => room.persistent = v.parse::<u8>()? != 0,
-            "bg_color" => room.bg_colour = v.parse()?,
-            "clear_screen" => room.clear_screen = v.parse::<u8>()? != 0,
-            "clear_view" => room.clear_view = v.parse::<u8>()? != 0,
-            // 8 backgrounds/views
-            k if k.chars().last().map(|c| c.is_digit(8)) == Some(true) => {
-                let i = k.chars().last().and_then(|c| c.to_digit(8)).unwrap() as usize;
-                match &k[..k.len() - 1] {
-                    "bg_visible" => room.backgrounds[i].visible_on_start = v.parse::<u8>()? != 0,
-                    "bg_is_foreground" => room.backgrounds[i].is_foreground = v.parse::<u8>()? != 0,
-                    "bg_source" => {
-                        room.backgrounds[i].source_bg = if v.is_empty() {
-                            -1
-                        } else {
-                            *asset_maps.backgrounds.map.get(v).ok_or_else(|| {
-                                Error::AssetNotFound(
-                                    v.to_string(),
-                                    "background",
-                                    format!("room {room_name} backgrounds"),
-                                )
-                            })? as _
-                        }
-                    },
-                    "bg_xoffset" => room.backgrounds[i].xoffset = v.parse()?,
-                    "bg_yoffset" => room.backgrounds[i].yoffset = v.parse()?,
-                    "bg_tile_h" => room.backgrounds[i].tile_horz = v.parse::<u8>()? != 0,
-                    "bg_tile_v" => room.backgrounds[i].tile_vert = v.parse::<u8>()? != 0,
-                    "bg_hspeed" => room.backgrounds[i].hspeed = v.parse()?,
-                    "bg_vspeed" => room.backgrounds[i].vspeed = v.parse()?,
-                    "bg_stretch" => room.backgrounds[i].stretch = v.parse::<u8>()? != 0,
-                    "view_visible" => room.views[i].visible = v.parse::<u8>()? != 0,
-                    "view_xview" => room.views[i].source_x = v.parse()?,
-                    "view_yview" => room.views[i].source_y = v.parse()?,
-                    "view_wview" => room.views[i].source_w = v.parse()?,
-                    "view_hview" => room.views[i].source_h = v.parse()?,
-                    "view_xport" => room.views[i].port_x = v.parse()?,
-                    "view_yport" => room.views[i].port_y = v.parse()?,
-                    "view_wport" => room.views[i].port_w = v.parse()?,
-                    "view_hport" => room.views[i].port_h = v.parse()?,
-                    "view_fol_hbord" => room.views[i].following_hborder = v.parse()?,
-                    "view_fol_vbord" => room.views[i].following_vborder = v.parse()?,
-                    "view_fol_hspeed" => {
-                        room.views[i].following_hspeed =
-                            if let Ok(s) = v.parse() { s } else { v.parse::<u32>()? as i32 };
-                    },
-                    "view_fol_vspeed" => {
-                        room.views[i].following_vspeed =
-                            if let Ok(s) = v.parse() { s } else { v.parse::<u32>()? as i32 };
-                    },
-                    "view_fol_target" => {
-                        room.views[i].following_target = if v.is_empty() {
-                            -1
-                        } else {
-                            *asset_maps.objects.map.get(v).ok_or_else(|| {
-                                Error::AssetNotFound(v.to_string(), "object", format!("room {room_name} view targets"))
-                            })? as _
-                        }
-                    },
-                    _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
-                }
-            },
-            "views_enabled" => room.views_enabled = v.parse::<u8>()? != 0,
-            // views
-            "remember" => room.remember_room_editor_info = v.parse::<u8>()? != 0,
-            "editor_width" => room.editor_width = v.parse()?,
-            "editor_height" => room.editor_height = v.parse()?,
-            "show_grid" => room.show_grid = v.parse::<u8>()? != 0,
-            "show_objects" => room.show_objects = v.parse::<u8>()? != 0,
-            "show_tiles" => room.show_tiles = v.parse::<u8>()? != 0,
-            "show_backgrounds" => room.show_backgrounds = v.parse::<u8>()? != 0,
-            "show_foregrounds" => room.show_foregrounds = v.parse::<u8>()? != 0,
-            "show_views" => room.show_views = v.parse::<u8>()? != 0,
-            "delete_underlying_objects" => room.delete_underlying_objects = v.parse::<u8>()? != 0,
-            "delete_underlying_tiles" => room.delete_underlying_tiles = v.parse::<u8>()? != 0,
-            "tab" => room.tab = v.parse()?, // i still don't know wtf this is
-            "editor_x" => room.x_position_scroll = v.parse()?,
-            "editor_y" => room.y_position_scroll = v.parse()?,
-            _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
-        }
-        Ok(())
-    })?;
-    path.pop();
-    path.push("code.gml");
-    room.creation_code = load_gml(&read_file(&path)?);
-    path.pop();
-    load_instances(&mut room, path, &asset_maps.objects.map)?;
-    room.put_tiles(load_tiles(path, &asset_maps.backgrounds.map)?);
-    room.calc_extents();
-    Ok(room)
-}
-
-unsafe fn load_constants(path: &mut PathBuf) -> Result<()> {
-    path.push("constants.txt");
-    let s = read_file(&path)?;
-    path.pop();
-    let lines: Vec<_> = s.par_lines().collect();
-    ide::alloc_constants(lines.len());
-    for (line, name_p, value_p) in izip!(lines, ide::get_constant_names_mut(), ide::get_constants_mut()) {
-        decode_line(&path, line, &mut |name, value| {
-            *name_p = UStr::new(name);
-            *value_p = UStr::new(value);
-            Ok(())
-        })?;
+    if settings.loading_foreground.is_some() {
+        *LOADING_FOREGROUND = bitmap(&mut path, "front.bmp");
     }
-    Ok(())
-}
-
-unsafe fn load_included_files(path: &mut PathBuf) -> Result<()> {
-    path.push("datafiles");
-    path.push("index.yyd");
-    let index = read_file(&path)?;
-    path.pop();
-    let files: Vec<_> = index.par_lines().collect();
-    ide::alloc_included_files(files.len());
-    for (fname, file) in files.iter().zip(ide::get_included_files_mut()) {
-        file.file_name = UStr::new(fname);
-        path.push(fname.to_string() + ".txt");
-        read_txt(&path, |k, v| {
-            match k {
-                "store" => file.stored_in_gmk = v.parse::<u8>()? != 0,
-                "free" => file.free_memory = v.parse::<u8>()? != 0,
-                "overwrite" => file.overwrite_file = v.parse::<u8>()? != 0,
-                "remove" => file.remove_at_end = v.parse::<u8>()? != 0,
-                "export" => file.export_setting = v.parse()?,
-                "export_folder" => file.export_custom_folder = UStr::new(v),
-                _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
-            }
-            Ok(())
-        })?;
-        path.pop();
-        path.push("include");
-        path.push(fname);
-        file.source_path = UStr::new(&path);
-        file.source_length =
-            std::fs::metadata(&path).map_err(|e| Error::FileIoError(e, path.to_path_buf()))?.len() as _;
-        if file.stored_in_gmk {
-            verify_path(&path)?;
-            file.data_exists = true;
-            let data = delphi::TMemoryStream::new();
-            data.load(&UStr::new(&path));
-            file.source_length = data.get_size();
-            file.data = data;
-        }
-        path.pop();
-        path.pop();
+    if settings.custom_load_image.is_some() {
+        *CUSTOM_LOAD_IMAGE = bitmap(&mut path, "loader.bmp");
     }
+    path.push("icon.ico");
+    (*ICON).LoadFromFile(&UStr::new(&path));
     path.pop();
-    Ok(())
 }
 
-unsafe fn load_game_information(path: &mut PathBuf) -> Result<()> {
+unsafe fn game_info(info: gm82::GameInfo, mut path: PathBuf) {
     use ide::game_info::*;
     let editor = &mut *(**FORM).editor;
-    path.push("game_information.txt");
-    read_txt(&path, |k, v| {
-        match k {
-            "color" => editor.colour = v.parse()?,
-            "new_window" => NEW_WINDOW.write(v.parse::<u8>()? != 0),
-            "caption" => CAPTION.asg(v),
-            "left" => LEFT.write(v.parse()?),
-            "top" => TOP.write(v.parse()?),
-            "width" => WIDTH.write(v.parse()?),
-            "height" => HEIGHT.write(v.parse()?),
-            "border" => BORDER.write(v.parse::<u8>()? != 0),
-            "resizable" => RESIZABLE.write(v.parse::<u8>()? != 0),
-            "window_on_top" => WINDOW_ON_TOP.write(v.parse::<u8>()? != 0),
-            "freeze_game" => FREEZE_GAME.write(v.parse::<u8>()? != 0),
-            _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
-        }
-        Ok(())
-    })?;
-    path.set_extension("rtf");
-    verify_path(&path)?;
-    (&mut *editor.rich_edit_strings).LoadFromFile(&UStr::new(&path));
-    path.pop();
-    Ok(())
-}
-
-unsafe fn load_settings(path: &mut PathBuf) -> Result<()> {
-    use ide::settings::*;
-    path.push("settings");
-    load_constants(path)?;
-    let mut custom_load_bar = false;
-    let mut bar_bg = false;
-    let mut bar_fg = false;
-    let mut custom_load_bg = false;
-    path.push("settings.txt");
-    *VSYNC_AND_FORCE_CPU = 0; // bitwise or is used, so reset this first
-    read_txt(&path, |k, v| {
-        match k {
-            "fullscreen" => FULLSCREEN.write(v.parse::<u8>()? != 0),
-            "interpolate_pixels" => INTERPOLATE_PIXELS.write(v.parse::<u8>()? != 0),
-            "dont_draw_border" => DONT_DRAW_BORDER.write(v.parse::<u8>()? != 0),
-            "display_cursor" => DISPLAY_CURSOR.write(v.parse::<u8>()? != 0),
-            "scaling" => SCALING.write(if let Ok(s) = v.parse() { s } else { v.parse::<u32>()? as i32 }),
-            "allow_resize" => ALLOW_RESIZE.write(v.parse::<u8>()? != 0),
-            "window_on_top" => WINDOW_ON_TOP.write(v.parse::<u8>()? != 0),
-            "clear_color" => CLEAR_COLOUR.write(v.parse()?),
-            "set_resolution" => SET_RESOLUTION.write(v.parse::<u8>()? != 0),
-            "color_depth" => COLOUR_DEPTH.write(v.parse()?),
-            "resolution" => RESOLUTION.write(v.parse()?),
-            "frequency" => FREQUENCY.write(v.parse()?),
-            "dont_show_buttons" => DONT_SHOW_BUTTONS.write(v.parse::<u8>()? != 0),
-            "vsync" => *VSYNC_AND_FORCE_CPU |= u32::from(v.parse::<u8>()? != 0),
-            "force_cpu_render" => (), // legacy, and saving didn't even work so
-            "swap_creation_events" => *VSYNC_AND_FORCE_CPU |= u32::from(v.parse::<u8>()? != 0) << 31,
-            "disable_screensaver" => DISABLE_SCREENSAVER.write(v.parse::<u8>()? != 0),
-            "f4_fullscreen_toggle" => F4_FULLSCREEN.write(v.parse::<u8>()? != 0),
-            "f1_help_menu" => F1_HELP.write(v.parse::<u8>()? != 0),
-            "esc_close_game" => ESC_CLOSE.write(v.parse::<u8>()? != 0),
-            "f5_save_f6_load" => F5_SAVE_F6_LOAD.write(v.parse::<u8>()? != 0),
-            "f9_screenshot" => F9_SCREENSHOT.write(v.parse::<u8>()? != 0),
-            "treat_close_as_esc" => TREAT_CLOSE_AS_ESC.write(v.parse::<u8>()? != 0),
-            "priority" => PRIORITY.write(v.parse()?),
-            "freeze_on_lose_focus" => FREEZE_ON_LOSE_FOCUS.write(v.parse::<u8>()? != 0),
-            "custom_loader" => {
-                custom_load_bg = v.parse::<u8>()? != 0;
-                HAS_CUSTOM_LOAD_IMAGE.write(custom_load_bg);
-            },
-            "custom_bar" => {
-                let bar = v.parse()?;
-                custom_load_bar = bar == 2;
-                LOADING_BAR.write(bar);
-            },
-            "bar_has_bg" => bar_bg = v.parse::<u8>()? != 0,
-            "bar_has_fg" => bar_fg = v.parse::<u8>()? != 0,
-            "transparent" => LOADING_TRANSPARENT.write(v.parse::<u8>()? != 0),
-            "translucency" => LOADING_TRANSLUCENCY.write(v.parse()?),
-            "scale_progress_bar" => LOADING_PROGRESS_BAR_SCALE.write(v.parse::<u8>()? != 0),
-            "show_error_messages" => SHOW_ERROR_MESSAGES.write(v.parse::<u8>()? != 0),
-            "log_errors" => LOG_ERRORS.write(v.parse::<u8>()? != 0),
-            "always_abort" => ALWAYS_ABORT.write(v.parse::<u8>()? != 0),
-            "zero_uninitialized_vars" => ZERO_UNINITIALIZED_VARS.write(v.parse::<u8>()? != 0),
-            "error_on_uninitialized_args" => ERROR_ON_UNINITIALIZED_ARGS.write(v.parse::<u8>()? != 0),
-            _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
-        }
-        Ok(())
-    })?;
-    path.pop();
-    if custom_load_bar {
-        if bar_bg {
-            path.push("back.bmp");
-            verify_path(&path)?;
-            let bg = delphi::TBitmap::new();
-            bg.LoadFromFile(&UStr::new(&path));
-            *LOADING_BACKGROUND = Some(bg);
-            path.pop();
-        }
-        if bar_fg {
-            path.push("front.bmp");
-            verify_path(&path)?;
-            let fg = delphi::TBitmap::new();
-            fg.LoadFromFile(&UStr::new(&path));
-            *LOADING_FOREGROUND = Some(fg);
-            path.pop();
-        }
+    editor.colour = info.colour;
+    NEW_WINDOW.write(info.new_window);
+    CAPTION.asg_text(&info.caption);
+    LEFT.write(info.left);
+    TOP.write(info.top);
+    WIDTH.write(info.width);
+    HEIGHT.write(info.height);
+    BORDER.write(info.border);
+    RESIZABLE.write(info.resizable);
+    WINDOW_ON_TOP.write(info.window_on_top);
+    FREEZE_GAME.write(info.freeze_game);
+    path.push("game_information.rtf");
+    (*editor.rich_edit_strings).LoadFromFile(&UStr::new(&path));
+}
+
+// loads a room on its own, for when something outside the IDE has changed it
+// its ids carry on from the rest of the project's, and can't clash with them
+pub unsafe fn load_room(
+    path: &mut PathBuf,
+    asset_maps: &AssetMaps,
+    timestamp: &mut f64,
+    extra_keys: &mut ExtraKeys,
+) -> Result<DelphiBox<Room>> {
+    let room_name = path.file_name().map(OsStr::to_string_lossy).unwrap_or_default().into_owned();
+    let mut loaded = gm82file::load::load_room(path, asset_maps, timestamp)?;
+    let mut diagnostics = Diagnostics::new(false);
+    let ids = vec![(
+        room_name.as_str(),
+        loaded.instances.iter_mut().map(|i| &mut i.id).collect(),
+        loaded.tiles.iter_mut().map(|t| &mut t.id).collect(),
+    )];
+    gm82file::load::assign_ids(
+        ids,
+        PRESERVE_IDS,
+        &mut *ide::LAST_INSTANCE_ID,
+        &mut *ide::LAST_TILE_ID,
+        &mut diagnostics,
+    )?;
+    let (extra_insts, extra_tiles) = EXTRA_DATA.get_or_insert_with(Default::default);
+    if let Some(inst) = loaded.instances.iter().find(|i| extra_insts.contains_key(&i.id)) {
+        return Err(Error::DuplicateId("instance", inst.id, room_name))
     }
-    if custom_load_bg {
-        path.push("loader.bmp");
-        let im = delphi::TBitmap::new();
-        im.LoadFromFile(&UStr::new(&path));
-        *CUSTOM_LOAD_IMAGE = Some(im);
-        path.pop();
+    if let Some(tile) = loaded.tiles.iter().find(|t| extra_tiles.contains_key(&t.id)) {
+        return Err(Error::DuplicateId("tile", tile.id, room_name))
     }
-    path.push("icon.ico");
-    verify_path(&path)?;
-    (*ICON).LoadFromFile(&UStr::new(&path));
-    path.pop();
+    // names clashing with other rooms' get new ones from name_instances
+    gm82file::load::reset_taken_names(&mut loaded, &mut save::taken_names());
+    Ok(room(loaded, extra_keys))
+}
+
+pub unsafe fn load_gmk(mut path: PathBuf) -> Result<()> {
+    ide::initialize_project();
+    PATH_FORM_UPDATED = false;
+    EXTRA_DATA = Some(Default::default());
+    // files from older versions all need rewriting in the current format, so do a full save
+    let importing_old_version = gm82file::load::read_version(&path)?.map_or(false, |v| v < GM82_VERSION);
+    // extensions go first, since they can add action libraries
     {
-        path.push("extensions.txt");
-        let f = open_file(&path)?;
         let mut any = false;
-        for line in f.lines() {
-            let name = line?;
+        for name in gm82file::load::load_extensions(&mut path.with_file_name("settings"))? {
             if let Some((_, loaded)) = ide::get_extensions()
                 .iter()
                 .zip(ide::get_extensions_loaded_mut())
@@ -1070,191 +552,65 @@ unsafe fn load_settings(path: &mut PathBuf) -> Result<()> {
             // reload action libraries, including the extensions
             let _: u32 = delphi_call!(0x7149c4);
         }
-        path.pop();
     }
-    load_game_information(path)?;
+    let libs = action_libraries();
+    let project_path = path.clone();
+    let project = run_while_updating_bar(0, 60, |progress| {
+        gm82file::load::load_gmk_with_progress(project_path, &libs, true, progress)
+    })?;
     path.pop();
-    Ok(())
-}
 
-fn load_index(name: &str, has_any: bool, path: &mut PathBuf) -> Result<Assets> {
-    if !has_any {
-        return Ok(Assets { index: Vec::new(), map: HashMap::new() })
+    ide::GAME_ID.write(project.game_id as _);
+    *ide::LAST_INSTANCE_ID = project.last_instance_id;
+    *ide::LAST_TILE_ID = project.last_tile_id;
+    PRESERVE_IDS = project.preserve_ids;
+    PNG_EFFORT = project.png_effort;
+    settings(project.settings, path.join("settings"));
+    game_info(project.game_info, path.join("settings"));
+    ide::alloc_constants(project.constants.len());
+    for ((name, value), (name_p, value_p)) in
+        project.constants.iter().zip(ide::get_constant_names_mut().iter_mut().zip(ide::get_constants_mut()))
+    {
+        *name_p = UStr::from_text(name);
+        *value_p = UStr::from_text(value);
     }
-    path.push(name);
-    path.push("index.yyd");
-    let text = read_file(&path)?;
-    let index: Vec<_> = text.par_lines().map(String::from).collect();
-    let map = index.par_iter().enumerate().filter_map(|(i, s)| (!s.is_empty()).then(|| (s.to_string(), i))).collect();
-    path.pop();
-    path.pop();
-    Ok(Assets { index, map })
-}
-
-unsafe fn load_assets<'a, T: 'static + Sync, AL: AssetListTrait<T> + Sync>(
-    name: &str,
-    load_asset: unsafe fn(&mut PathBuf, &AssetMaps) -> Result<DelphiBox<T>>,
-    the_assets: &AL,
-    assets: &Assets,
-    bar_start: u32,
-    bar_end: u32,
-    path: &mut PathBuf,
-    asset_maps: &AssetMaps,
-) -> Result<()> {
-    path.push(name);
-    let names = &assets.index;
-    the_assets.alloc(names.len());
-    // blank object in 0th slot
-    if names.is_empty() && name == "objects" {
-        the_assets.alloc(1);
+    ide::alloc_triggers(project.triggers.len());
+    for (trig, trig_p) in project.triggers.into_iter().zip(ide::get_triggers_mut()) {
+        *trig_p = trig.map(trigger);
     }
-    if name != "rooms" {
-        run_while_updating_bar(bar_start, bar_end, names.len() as u32, |tx| {
-            names.par_iter().zip(the_assets.assets_mut()).zip(the_assets.names_mut()).try_for_each(
-                |((name, asset), name_p)| -> Result<()> {
-                    if !name.is_empty() {
-                        *name_p = UStr::new(name);
-                        *asset = Some(load_asset(&mut path.join(name), asset_maps)?);
-                    }
-                    let _ = tx.send(());
-                    Ok(())
-                },
-            )
-        })?;
-    } else {
-        run_while_updating_bar(bar_start, bar_end, names.len() as u32, |tx| {
-            names.iter().zip(the_assets.assets_mut()).zip(the_assets.names_mut()).try_for_each(
-                |((name, asset), name_p)| -> Result<()> {
-                    if !name.is_empty() {
-                        *name_p = UStr::new(name);
-                        *asset = Some(load_asset(&mut path.join(name), asset_maps)?);
-                    }
-                    let _ = tx.send(());
-                    Ok(())
-                },
-            )
-        })?;
+    advance_progress_form(60);
+    let trees = [
+        (ide::RT_SOUNDS, 3, project.sounds.tree.clone(), project.sounds.names.clone()),
+        (ide::RT_SPRITES, 2, project.sprites.tree.clone(), project.sprites.names.clone()),
+        (ide::RT_BACKGROUNDS, 6, project.backgrounds.tree.clone(), project.backgrounds.names.clone()),
+        (ide::RT_PATHS, 8, project.paths.tree.clone(), project.paths.names.clone()),
+        (ide::RT_SCRIPTS, 7, project.scripts.tree.clone(), project.scripts.names.clone()),
+        (ide::RT_FONTS, 9, project.fonts.tree.clone(), project.fonts.names.clone()),
+        (ide::RT_TIMELINES, 12, project.timelines.tree.clone(), project.timelines.names.clone()),
+        (ide::RT_OBJECTS, 1, project.objects.tree.clone(), project.objects.names.clone()),
+        (ide::RT_ROOMS, 4, project.rooms.tree.clone(), project.rooms.names.clone()),
+    ];
+    put_assets("sounds", &ide::SOUNDS, project.sounds, sound);
+    put_assets("sprites", &ide::SPRITES, project.sprites, sprite);
+    put_assets("backgrounds", &ide::BACKGROUNDS, project.backgrounds, background);
+    advance_progress_form(65);
+    put_assets("paths", &ide::PATHS, project.paths, self::path);
+    put_assets("scripts", &ide::SCRIPTS, project.scripts, script);
+    put_assets("fonts", &ide::FONTS, project.fonts, font);
+    put_assets("timelines", &ide::TIMELINES, project.timelines, timeline);
+    advance_progress_form(70);
+    put_assets("objects", &ide::OBJECTS, project.objects, object);
+    put_assets("rooms", &ide::ROOMS, project.rooms, room);
+    advance_progress_form(75);
+    ide::alloc_included_files(project.included_files.len());
+    for (file, file_p) in project.included_files.into_iter().zip(ide::get_included_files_mut()) {
+        included_file(file, file_p);
     }
-    path.pop();
-    Ok(())
-}
-
-pub fn load_asset_maps(
-    path: &mut PathBuf,
-    has_triggers: bool,
-    has_sprites: bool,
-    has_sounds: bool,
-    has_backgrounds: bool,
-    has_paths: bool,
-    has_scripts: bool,
-    has_objects: bool,
-    has_fonts: bool,
-    has_timelines: bool,
-) -> Result<AssetMaps> {
-    Ok(AssetMaps {
-        triggers: load_index("triggers", has_triggers, path)?,
-        sprites: load_index("sprites", has_sprites, path)?,
-        sounds: load_index("sounds", has_sounds, path)?,
-        backgrounds: load_index("backgrounds", has_backgrounds, path)?,
-        paths: load_index("paths", has_paths, path)?,
-        scripts: load_index("scripts", has_scripts, path)?,
-        objects: load_index("objects", has_objects, path)?,
-        rooms: load_index("rooms", true, path)?,
-        fonts: load_index("fonts", has_fonts, path)?,
-        timelines: load_index("timelines", has_timelines, path)?,
-    })
-}
+    for (base, kind, tree, names) in &trees {
+        build_tree(*base, *kind, tree, names);
+    }
+    advance_progress_form(80);
 
-pub unsafe fn load_gmk(mut path: PathBuf) -> Result<()> {
-    ide::initialize_project();
-    PATH_FORM_UPDATED = false;
-    EXTRA_DATA = Some(Default::default());
-    let mut has_backgrounds = true;
-    let mut has_datafiles = true;
-    let mut has_fonts = true;
-    let mut has_objects = true;
-    let mut has_paths = true;
-    let mut has_scripts = true;
-    let mut has_sounds = true;
-    let mut has_sprites = true;
-    let mut has_timelines = true;
-    let mut has_triggers = true;
-    let mut importing_old_version = false;
-    read_txt(&path, |k, v| {
-        match k {
-            "gm82_version" => match v.parse::<u8>()? {
-                newer if newer > 5 => return Err(Error::OldGM82),
-                older if older < 5 => importing_old_version = true,
-                _ => (),
-            },
-            "gameid" => ide::GAME_ID.write(v.parse()?),
-            "info_author" => ide::settings::INFO_AUTHOR.asg(v),
-            "info_version" => ide::settings::INFO_VERSION.asg(v),
-            "info_timestamp" => ide::settings::INFO_TIMESTAMP.write(v.parse()?), // legacy
-            "info_information" => ide::settings::INFO_INFORMATION.asg_undelimit(v),
-            "exe_company" => ide::settings::EXE_COMPANY.asg(v),
-            "exe_product" => ide::settings::EXE_PRODUCT.asg(v),
-            "exe_copyright" => ide::settings::EXE_COPYRIGHT.asg(v),
-            "exe_description" => ide::settings::EXE_DESCRIPTION.asg(v),
-            "exe_version" => {
-                let err = || Error::InvalidVersion(v.to_string());
-                let mut iter = v.split('.');
-                ide::settings::VERSION_MAJOR.write(iter.next().ok_or_else(err)?.parse()?);
-                ide::settings::VERSION_MINOR.write(iter.next().ok_or_else(err)?.parse()?);
-                ide::settings::VERSION_RELEASE.write(iter.next().ok_or_else(err)?.parse()?);
-                ide::settings::VERSION_BUILD.write(iter.next().ok_or_else(err)?.parse()?);
-                if iter.next() != None {
-                    return Err(Error::InvalidVersion(v.to_string()))
-                }
-            },
-            "has_backgrounds" => has_backgrounds = v.parse::<u8>()? != 0,
-            "has_datafiles" => has_datafiles = v.parse::<u8>()? != 0,
-            "has_fonts" => has_fonts = v.parse::<u8>()? != 0,
-            "has_objects" => has_objects = v.parse::<u8>()? != 0,
-            "has_paths" => has_paths = v.parse::<u8>()? != 0,
-            "has_scripts" => has_scripts = v.parse::<u8>()? != 0,
-            "has_sounds" => has_sounds = v.parse::<u8>()? != 0,
-            "has_sprites" => has_sprites = v.parse::<u8>()? != 0,
-            "has_timelines" => has_timelines = v.parse::<u8>()? != 0,
-            "has_triggers" => has_triggers = v.parse::<u8>()? != 0,
-            _ => return Err(Error::UnknownKey(path.to_path_buf(), k.to_string())),
-        }
-        Ok(())
-    })?;
-    path.pop();
-    advance_progress_form(5);
-    load_settings(&mut path)?;
-    advance_progress_form(10);
-    let asset_maps = load_asset_maps(
-        &mut path,
-        has_triggers,
-        has_sprites,
-        has_sounds,
-        has_backgrounds,
-        has_paths,
-        has_scripts,
-        has_objects,
-        has_fonts,
-        has_timelines,
-    )?;
-    advance_progress_form(15);
-    load_triggers(&asset_maps, &mut path)?;
-    advance_progress_form(20);
-    load_assets("sounds", load_sound, &ide::SOUNDS, &asset_maps.sounds, 20, 30, &mut path, &asset_maps)?;
-    advance_progress_form(30);
-    load_assets("sprites", load_sprite, &ide::SPRITES, &asset_maps.sprites, 30, 40, &mut path, &asset_maps)?;
-    advance_progress_form(40);
-    load_assets(
-        "backgrounds",
-        load_background,
-        &ide::BACKGROUNDS,
-        &asset_maps.backgrounds,
-        40,
-        45,
-        &mut path,
-        &asset_maps,
-    )?;
-    advance_progress_form(45);
     // register sprite icons
     let bg_col = (*ide::RESOURCE_TREE.read()).color;
     let mut last_refresh = std::time::Instant::now();
@@ -1263,12 +619,12 @@ pub unsafe fn load_gmk(mut path: PathBuf) -> Result<()> {
         if let Some(sp) = sp {
             *thumb = sp.register_thumb(bg_col);
             if last_refresh.elapsed() > std::time::Duration::from_secs(1) {
-                advance_progress_form((i * 15 / sprites_len + 45) as u32);
+                advance_progress_form((i * 10 / sprites_len + 80) as u32);
                 last_refresh = std::time::Instant::now();
             }
         }
     }
-    advance_progress_form(60);
+    advance_progress_form(90);
     // register background icons
     let mut last_refresh = std::time::Instant::now();
     let bg_len = ide::BACKGROUNDS.assets().len();
@@ -1276,39 +632,14 @@ pub unsafe fn load_gmk(mut path: PathBuf) -> Result<()> {
         if let Some(bg) = bg {
             *thumb = bg.register_thumb(bg_col);
             if last_refresh.elapsed() > std::time::Duration::from_secs(1) {
-                advance_progress_form((i * 5 / bg_len + 60) as u32);
+                advance_progress_form((i * 5 / bg_len + 90) as u32);
                 last_refresh = std::time::Instant::now();
             }
         }
     }
     // image list OnChange
     let _: u32 = delphi_call!(0x5081b8, *(0x789b38 as *const usize));
-    advance_progress_form(65);
-    load_assets("paths", load_path, &ide::PATHS, &asset_maps.paths, 65, 70, &mut path, &asset_maps)?;
-    advance_progress_form(70);
-    load_assets("scripts", load_script, &ide::SCRIPTS, &asset_maps.scripts, 70, 75, &mut path, &asset_maps)?;
-    advance_progress_form(75);
-    load_assets("fonts", load_font, &ide::FONTS, &asset_maps.fonts, 75, 80, &mut path, &asset_maps)?;
-    advance_progress_form(80);
-    load_assets("timelines", load_timeline, &ide::TIMELINES, &asset_maps.timelines, 80, 85, &mut path, &asset_maps)?;
-    advance_progress_form(85);
-    load_assets("objects", load_object, &ide::OBJECTS, &asset_maps.objects, 85, 90, &mut path, &asset_maps)?;
-    advance_progress_form(90);
-    load_assets("rooms", load_room, &ide::ROOMS, &asset_maps.rooms, 90, 95, &mut path, &asset_maps)?;
     advance_progress_form(95);
-    if has_datafiles {
-        load_included_files(&mut path)?;
-    }
-
-    read_resource_tree(ide::RT_SOUNDS, 3, "sounds", &asset_maps.sounds.map, true, &mut path)?;
-    read_resource_tree(ide::RT_SPRITES, 2, "sprites", &asset_maps.sprites.map, true, &mut path)?;
-    read_resource_tree(ide::RT_BACKGROUNDS, 6, "backgrounds", &asset_maps.backgrounds.map, true, &mut path)?;
-    read_resource_tree(ide::RT_PATHS, 8, "paths", &asset_maps.paths.map, true, &mut path)?;
-    read_resource_tree(ide::RT_SCRIPTS, 7, "scripts", &asset_maps.scripts.map, true, &mut path)?;
-    read_resource_tree(ide::RT_FONTS, 9, "fonts", &asset_maps.fonts.map, true, &mut path)?;
-    read_resource_tree(ide::RT_TIMELINES, 12, "timelines", &asset_maps.timelines.map, true, &mut path)?;
-    read_resource_tree(ide::RT_OBJECTS, 1, "objects", &asset_maps.objects.map, true, &mut path)?;
-    read_resource_tree(ide::RT_ROOMS, 4, "rooms", &asset_maps.rooms.map, true, &mut path)?;
 
     // this is the part where i set all the updated flags to false
     // i don't feel like doing it nicely so enjoy
@@ -1349,7 +680,7 @@ pub unsafe fn load_gmk(mut path: PathBuf) -> Result<()> {
                     message = "Warning: this game uses the following fonts, which are not installed:".to_string();
                 }
                 message += "\n";
-                message += &font.sys_name.to_os_string().into_string().unwrap();
+                message += &font.sys_name.to_text();
             }
         }
         // free font list
@@ -7,7 +7,7 @@ use flate2::{write::ZlibEncoder, Compression};
 use rayon::prelude::*;
 use std::{arch::asm, io, io::Write, ptr, slice};
 
-pub trait GetAssetList: Sync + 'static {
+pub trait GetAssetList: Sized + Sync + 'static {
     fn get_asset_list() -> &'static dyn AssetListTrait<Self>;
     fn save(&mut self, exe: bool, out: impl Write) -> io::Result<()>;
     fn write_additional(_stream: &mut TMemoryStream) -> io::Result<()> {